@@ -15,79 +15,30 @@ use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use ethereal_rust_sdk::{
     async_client::client::HttpClient,
     enums::Environment,
-    models::{
-        OrderSide, OrderTimeInForce, OrderType, SubmitOrderData, SubmitOrderDto,
-        SubmitOrderLimitDtoData,
-    },
-    signable_messages::TradeOrder,
-    signing::{to_scaled_e9, Eip712, SigningContext},
-    with_signing_fields,
+    models::{OrderSide, OrderTimeInForce, SubmitOrderDto},
+    order_builder::OrderBuilder,
+    signing::SigningContext,
 };
-use ethers::utils::hex;
-use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use tokio::runtime::Runtime;
 
 pub async fn create_test_client() -> anyhow::Result<HttpClient> {
     let env = Environment::Testnet;
     let private_key = "0bb5d63b84421e1268dda020818ae30cf26e7f10e321fb820a8aa69216dea92a";
-    let http_client = HttpClient::new(env, private_key, None).await;
+    let http_client = HttpClient::new(env, private_key, None).await?;
     Ok(http_client)
 }
 
-#[allow(clippy::too_many_arguments)]
 pub fn build_submit_order_dto_for_bench(
     client: &HttpClient,
-    ticker: &str,
-    quantity: Decimal,
-    price: Decimal,
-    side: OrderSide,
-    _type: OrderType,
-    time_in_force: OrderTimeInForce,
-    post_only: bool,
-    reduce_only: bool,
-    expires_at: Option<i64>,
+    order: OrderBuilder,
 ) -> Result<SubmitOrderDto, Box<dyn std::error::Error>> {
-    if !client.product_hashmap.contains_key(ticker) {
-        return Err(format!("Ticker {ticker} not found").into());
-    }
-    let product_info = client.product_hashmap.get(ticker).unwrap();
+    let product_info = client
+        .product_hashmap
+        .get(order.ticker())
+        .ok_or_else(|| format!("Ticker {} not found", order.ticker()))?;
     let ctx = SigningContext::new(&client.wallet, &client.subaccounts[0]);
-    let message = with_signing_fields!(
-        eip_signing_fields,
-        ctx,
-        TradeOrder {
-            quantity: to_scaled_e9(quantity)?,
-            price: to_scaled_e9(price)?,
-            reduce_only,
-            side: side as u8,
-            engine_type: product_info.engine_type.to_string().parse()?,
-            product_id: product_info.onchain_id.to_string().parse()?,
-        }
-    );
-    let signature = message.sign(client.env, &client.wallet)?;
-
-    let order_dto = with_signing_fields!(
-        dto_signing_fields,
-        ctx,
-        SubmitOrderLimitDtoData {
-            quantity: quantity,
-            price: price,
-            side,
-            onchain_id: product_info.onchain_id,
-            engine_type: product_info.engine_type,
-            reduce_only: Some(reduce_only),
-            post_only,
-            expires_at,
-            time_in_force,
-            ..Default::default()
-        }
-    );
-    let dto = SubmitOrderDto {
-        data: SubmitOrderData::SubmitOrderLimitDtoData(order_dto),
-        signature: "0x".to_string() + &hex::encode(signature.to_vec()),
-    };
-    Ok(dto)
+    Ok(order.build(product_info, &ctx, client.env)?)
 }
 
 // Helper used by the bench to synchronously produce a ready client. This wraps the
@@ -113,11 +64,9 @@ fn bench_submit_order_build_and_sign(c: &mut Criterion) {
     let quantity = dec!(0.01);
     let price = dec!(50_000.0);
     let side = OrderSide::BUY;
-    let r#type = OrderType::Limit;
     let time_in_force = OrderTimeInForce::Ioc;
     let post_only = true;
     let reduce_only = false;
-    let expires_at = None;
 
     c.bench_function("submit_order/build_sign_encode_dto", |b| {
         let client = client.clone();
@@ -128,44 +77,17 @@ fn bench_submit_order_build_and_sign(c: &mut Criterion) {
                 // to measure allocations.
                 (
                     client.clone(),
-                    ticker.to_string(),
-                    quantity,
-                    price,
-                    side,
-                    r#type,
-                    time_in_force,
-                    post_only,
-                    reduce_only,
-                    expires_at,
+                    OrderBuilder::limit(ticker, side, quantity, price)
+                        .time_in_force(time_in_force)
+                        .post_only(post_only)
+                        .reduce_only(reduce_only),
                 )
             },
-            |(
-                client,
-                ticker,
-                quantity,
-                price,
-                side,
-                r#type,
-                tif,
-                post_only,
-                reduce_only,
-                expires_at,
-            )| {
+            |(client, order)| {
                 rt.block_on(async move {
                     // Bench the extracted helper (no network).
-                    let _dto = build_submit_order_dto_for_bench(
-                        &client,
-                        &ticker,
-                        quantity,
-                        price,
-                        side,
-                        r#type,
-                        tif,
-                        post_only,
-                        reduce_only,
-                        expires_at,
-                    )
-                    .expect("dto build/sign failed");
+                    let _dto = build_submit_order_dto_for_bench(&client, order)
+                        .expect("dto build/sign failed");
                 });
             },
             BatchSize::SmallInput,
@@ -7,6 +7,10 @@ from pathlib import Path
 from templates import (
     CHANNEL_ENUM_TEMPLATE,
     METHOD_TEMPLATE,
+    PAGINATED_METHOD_TEMPLATE,
+    PAGE_IMPL_TEMPLATE,
+    CURSOR_PARAMS_IMPL_TEMPLATE,
+    PAGINATION_IMPORTS,
     SIGNABLE_MESSAGE_HEADER,
     SIGNABLE_MESSAGE_TEMPLATE,
     SUBSCRIPTION_FUNCTION_TEMPLATE,
@@ -112,6 +116,32 @@ def check_sync_client_has_sub_client(file_path: Path):
     if expected_client_name not in sync_client_content:
         print(f"    Warning: {ASYNC_CLIENT_PATH} does not have client for {expected_client_name}")
 
+def has_cursor_param(source_content: str, params_struct_name: str) -> bool:
+    """
+    Check whether a params struct takes a pagination cursor.
+    """
+    start = source_content.find(f"pub struct {params_struct_name} {{")
+    if start == -1:
+        return False
+    end = source_content.find("}", start)
+    return "pub cursor: Option<String>" in source_content[start:end]
+
+
+def get_page_fields(page_type: str, models_module: str = "models") -> tuple[str, str]:
+    """
+    Read the item type of a PageOf* model from its `data` field, and how to read `has_next`,
+    which is optional on some pages.
+    """
+    for model_file in (CRATE_ROOT / models_module).glob("*.rs"):
+        content = model_file.read_text()
+        if f"pub struct {page_type} {{" in content:
+            item_type = content.split("pub data: Vec<")[1].split(">")[0].split("::")[-1]
+            if "pub has_next: Option<bool>" in content:
+                return item_type, "self.has_next.unwrap_or(false)"
+            return item_type, "self.has_next"
+    raise ValueError(f"Could not find item type of {page_type}")
+
+
 def check_sub_client_methods(file_path: Path, models_module: str = "models"):
     """
     Check that the sync client has methods for sub-clients.
@@ -124,7 +154,8 @@ def check_sub_client_methods(file_path: Path, models_module: str = "models"):
     func_started = False
     model_imports = set()
     client_imports = set()
-    functions, tests = [], []
+    functions, tests, trait_impls = [], [], []
+    page_types = set()
     for ix, line in enumerate(lines):
         if line.startswith("pub async fn "):
             func_started = True
@@ -166,13 +197,26 @@ def check_sub_client_methods(file_path: Path, models_module: str = "models"):
                 )
             tests.append(templated_test_function)
             functions.append(templated_function)
+            if return_type.startswith("PageOf") and has_cursor_param(source_content, params_struct_name):
+                item_type, has_next = get_page_fields(return_type, models_module=models_module)
+                functions.append(PAGINATED_METHOD_TEMPLATE.substitute(
+                    short_function_name=short_method_name,
+                    params_struct_name=params_struct_name,
+                    function_name=long_method_name,
+                    item_type=item_type,
+                ))
+                trait_impls.append(CURSOR_PARAMS_IMPL_TEMPLATE.substitute(params_struct_name=params_struct_name))
+                if return_type not in page_types:
+                    page_types.add(return_type)
+                    trait_impls.append(PAGE_IMPL_TEMPLATE.substitute(page_type=return_type, item_type=item_type, has_next=has_next))
+                model_imports.add(item_type)
             model_imports.add(return_type)
             client_imports.add(error_name)
             client_imports.add(long_method_name)
-    return functions, tests, model_imports, client_imports
+    return functions, tests, model_imports, client_imports, trait_impls
 
 
-def write_sub_client_file(api_name: str, functions: list[str], model_imports: set[str], client_imports: set[str], apis_module: str = "apis", models_module: str = "models"):
+def write_sub_client_file(api_name: str, functions: list[str], model_imports: set[str], client_imports: set[str], trait_impls: list[str], apis_module: str = "apis", models_module: str = "models"):
     sub_client_file = ASYNC_CLIENT_PATH / f"{api_name[:-4]}.rs"
     client_name = f"{to_upper_camel_case(api_name[:-4])}Client"
     functions_str = "\n".join(functions)
@@ -186,6 +230,8 @@ def write_sub_client_file(api_name: str, functions: list[str], model_imports: se
         client_imports=client_imports_str,
         apis_module=apis_module,
         models_module=models_module,
+        pagination_imports=PAGINATION_IMPORTS if trait_impls else "",
+        trait_impls="\n".join(trait_impls),
     )
     sub_client_file.write_text(sub_client_content)
     print(f"    Wrote sub-client file: {sub_client_file.stem}")
@@ -227,12 +273,13 @@ def post_process_generated_files(generated_files: list[Path], apis_module: str =
         if "_api" in file.stem:
             print("Processing API file:", file.stem)
             check_sync_client_has_sub_client(file)
-            functions, tests, model_imports, client_imports = check_sub_client_methods(file, models_module=models_module)
+            functions, tests, model_imports, client_imports, trait_impls = check_sub_client_methods(file, models_module=models_module)
             write_sub_client_file(
                 api_name=file.stem,
                 functions=functions,
                 model_imports=model_imports,
                 client_imports=client_imports,
+                trait_impls=trait_impls,
                 apis_module=apis_module,
                 models_module=models_module,
             )
@@ -244,6 +291,26 @@ def post_process_generated_files(generated_files: list[Path], apis_module: str =
                 apis_module=apis_module,
             )
 
+PRICE_LEVEL_FIELDS = {
+    "l2_book_message_data.rs": "Vec<Vec<rust_decimal::Decimal>>",
+    "market_liquidity_dto.rs": "Vec<Vec<String>>",
+}
+
+
+def use_price_level_types():
+    """
+    Replace the untyped [price, quantity] tuple arrays of the book models with PriceLevel.
+    """
+    for file_name, tuple_type in PRICE_LEVEL_FIELDS.items():
+        file_path = CRATE_ROOT / "models" / file_name
+        content = file_path.read_text()
+        if tuple_type not in content:
+            print(f"    Warning: {file_name} has no {tuple_type} fields")
+            continue
+        file_path.write_text(content.replace(tuple_type, "Vec<crate::types::PriceLevel>"))
+        print(f"    Using PriceLevel in {file_name}")
+
+
 def generate_domain_config_files():
     """
     Generate domain configuration files for different environments.
@@ -392,6 +459,7 @@ def build_subscriptions(channels):
 
 if __name__ == "__main__":
     generate_domain_config_files()
+    use_price_level_types()
 
     generated_files = gather_generated_files(API_SOURCE_DIR)
     post_process_generated_files(generated_files)
@@ -21,6 +21,47 @@ METHOD_TEMPLATE = Template("""
     }
 """)
 
+PAGINATED_METHOD_TEMPLATE = Template("""
+    pub fn ${short_function_name}_stream(
+        &self,
+        params: $params_struct_name,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<$item_type, crate::Error>> + '_ {
+        paginate(params, options, move |params| $function_name(self.config, params))
+    }
+
+    pub async fn ${short_function_name}_all(
+        &self,
+        params: $params_struct_name,
+        options: PaginationOptions,
+    ) -> Result<Vec<$item_type>, crate::Error> {
+        self.${short_function_name}_stream(params, options).try_collect().await
+    }
+""")
+
+PAGE_IMPL_TEMPLATE = Template("""
+impl Page for $page_type {
+    type Item = $item_type;
+
+    fn into_parts(self) -> (Vec<$item_type>, bool, Option<String>) {
+        (self.data, $has_next, self.next_cursor)
+    }
+}
+""")
+
+CURSOR_PARAMS_IMPL_TEMPLATE = Template("""
+impl CursorParams for $params_struct_name {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+""")
+
+PAGINATION_IMPORTS = """
+use crate::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use futures_util::{Stream, TryStreamExt};
+"""
+
 TEST_METHOD_TEMPLATE_WITH_PARAMS = Template("""
 #[tokio::test]
 async fn test_$short_function_name() {
@@ -55,6 +96,7 @@ use crate::{
     },
     ${models_module}::{${model_imports}},
 };
+${pagination_imports}
 pub struct ${client_name}<'a> {
     pub config: &'a Configuration,
 }
@@ -62,6 +104,7 @@ pub struct ${client_name}<'a> {
 impl<'a> ${client_name}<'a> {
 ${functions}
 }
+${trait_impls}
 """)
 
 
@@ -152,42 +195,58 @@ impl Channels {
 SUBSCRIPTIONS_TEMPLATE = Template("""
 use std::future::Future;
 
-use bytes::Bytes;
-
 use crate::{
     channels::Channels,
     models::{$subscription_message_imports},
+    subscription_stream::{StreamConfig, SubscriptionStream},
     types::{ProductSubscriptionMessage, SubaccountSubscriptionMessage},
-    ws_client::{ClientError, WsClient},
+    ws_client::{ClientError, Subscriber, SubscriptionHandle, WsClient},
 };
 
-pub struct Subscriptions<'a> {
-    pub client: &'a WsClient,
+/// Typed subscriptions to every channel, made through a [`WsClient`] or a
+/// [`crate::ws_pool::WsPool`].
+pub struct Subscriptions<'a, C = WsClient> {
+    pub client: &'a C,
+    pub stream_config: StreamConfig,
 }
-impl<'a> Subscriptions<'a> {
+impl<'a, C: Subscriber> Subscriptions<'a, C> {
+    /// Queue settings of the callback and `*_stream` subscriptions made from here.
+    pub fn stream_config(mut self, stream_config: StreamConfig) -> Self {
+        self.stream_config = stream_config;
+        self
+    }
 $functions
 }
 """)
 
 SUBSCRIPTION_FUNCTION_TEMPLATE = Template("""
-    pub async fn $func_name<F, Fut>(&self, $inputs: Vec<String>, callback: F) -> Result<(), ClientError>
+    pub async fn $func_name<F, Fut>(&self, $inputs: Vec<String>, callback: F) -> Result<SubscriptionHandle, ClientError>
     where
         F: FnMut($result_msg) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let payloads = $inputs
+        let requests = $inputs
+            .iter()
+            .map(|i| $sub_message {
+                msg_type: Channels::$stream_name,
+                $input_type: i.to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.client
+            .subscribe_channels(Channels::$stream_name, requests, self.stream_config, callback)
+            .await
+    }
+
+    pub async fn ${func_name}_stream(&self, $inputs: Vec<String>) -> Result<SubscriptionStream<$result_msg>, ClientError> {
+        let requests = $inputs
             .iter()
-            .map(|i| {
-                $sub_message {
-                    msg_type: Channels::$stream_name,
-                    $input_type: i.to_string(),
-                }
-                .into()
+            .map(|i| $sub_message {
+                msg_type: Channels::$stream_name,
+                $input_type: i.to_string(),
             })
-            .collect::<Vec<Bytes>>();
+            .collect::<Vec<_>>();
         self.client
-            .subscribe_channels(Channels::$stream_name, payloads, callback)
-            .await?;
-        Ok(())
+            .subscribe_stream(Channels::$stream_name, requests, self.stream_config)
+            .await
     }
 """)
@@ -5,6 +5,7 @@ use ethereal_rust_sdk::{
     async_client::client::HttpClient,
     enums::Environment,
     models::{OrderFillDto, OrderSide},
+    pagination::PaginationOptions,
     utils::create_client,
 };
 use log::info;
@@ -17,31 +18,21 @@ async fn collect_exchange_trades(
     subaccount_id: String,
     start_timestamp: Option<i64>,
 ) -> Result<Vec<OrderFillDto>, Box<dyn std::error::Error>> {
-    let mut all_trades = Vec::new();
-    let mut cursor: Option<String> = None;
-
     println!(
         "Collecting trades starting from timestamp: {:?}",
         start_timestamp
     );
-    loop {
-        let params = OrderControllerListFillsBySubaccountIdParams {
-            subaccount_id: subaccount_id.clone(),
-            created_after: start_timestamp.map(|ts| ts * 1000),
-            cursor: cursor.clone(),
-            order_by: Some("createdAt".to_string()),
-            limit: Some(100),
-            ..Default::default()
-        };
-
-        let result = client.order().list_fills_by_subaccount_id(params).await?;
-        all_trades.extend(result.data);
-
-        if !result.has_next.unwrap_or(false) {
-            break;
-        }
-        cursor = result.next_cursor;
-    }
+    let params = OrderControllerListFillsBySubaccountIdParams {
+        subaccount_id,
+        created_after: start_timestamp.map(|ts| ts * 1000),
+        order_by: Some("createdAt".to_string()),
+        limit: Some(100),
+        ..Default::default()
+    };
+    let all_trades = client
+        .order()
+        .list_fills_by_subaccount_id_all(params, PaginationOptions::default())
+        .await?;
 
     info!("Collected {} total trades", all_trades.len());
     Ok(all_trades)
@@ -6,9 +6,9 @@ use ethereal_rust_sdk::{
     async_client::client::HttpClient,
     enums::Environment,
     utils::create_client,
-    ws_client::{ConnectionState, WsClient},
+    ws_client::{ConnectionState, WsClient, WsEvent},
 };
-use log::info;
+use log::{info, warn};
 pub const TEST_PRIVATE_KEY: &str =
     "0bb5d63b84421e1268dda020818ae30cf26e7f10e321fb820a8aa69216dea92a";
 
@@ -52,20 +52,37 @@ pub async fn run_forever(ws_client: &WsClient) {
     ws_client.wait_for_connection().await;
     loop {
         match ws_client.run_till_event().await {
-            ConnectionState::Connected => {
+            WsEvent::Connection(ConnectionState::Connected) => {
                 info!("WebSocket connected");
-                ws_client.resubscribe_all().await.unwrap();
             }
-            ConnectionState::Disconnected => {
+            WsEvent::Connection(ConnectionState::Disconnected) => {
                 info!("WebSocket disconnected");
             }
-            ConnectionState::Exited => {
+            WsEvent::Connection(ConnectionState::Exited) => {
                 info!("WebSocket exited");
                 break;
             }
-            ConnectionState::Reconnecting => {
+            WsEvent::Connection(ConnectionState::Reconnecting) => {
                 info!("WebSocket reconnecting...");
             }
+            WsEvent::Resubscribed { restored, failed } => {
+                info!("Resubscribed to {} targets", restored.len());
+                if !failed.is_empty() {
+                    warn!("Failed to resubscribe to {failed:?}");
+                }
+            }
+            WsEvent::Authenticated => {
+                info!("WebSocket session authenticated");
+            }
+            WsEvent::AuthFailed(e) => {
+                warn!("WebSocket authentication failed: {e}");
+            }
+            WsEvent::SlowConsumer(warning) => {
+                warn!(
+                    "Slow consumer on {:?} {}: {:?}",
+                    warning.channel, warning.target, warning.metrics
+                );
+            }
         }
     }
 }
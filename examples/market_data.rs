@@ -15,7 +15,7 @@ async fn main() {
     info!("HTTP client and WS client created");
     let tickers = common::get_product_tickers(&http_client).await.unwrap();
 
-    ws_client
+    let _subscription = ws_client
         .subscriptions()
         .ticker(tickers, ticker_callback)
         .await
@@ -0,0 +1,38 @@
+mod common;
+
+use ethereal_rust_sdk::{
+    models::OrderSide,
+    order_book::{OrderBookFeed, ResyncStrategy},
+};
+use log::info;
+use rust_decimal_macros::dec;
+
+#[tokio::main]
+async fn main() {
+    simple_logger::init_with_level(log::Level::Info).expect("log");
+    let (http_client, ws_client) = common::create_test_clients().await.unwrap();
+    ws_client.wait_for_connection().await;
+
+    let feed = OrderBookFeed::subscribe(
+        &ws_client,
+        vec!["BTCUSD".to_string()],
+        ResyncStrategy::rest(&http_client),
+    )
+    .await
+    .unwrap();
+    let book = feed.book("BTCUSD").unwrap();
+
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        if !book.is_synced() {
+            continue;
+        }
+        info!(
+            "bid: {:?} ask: {:?} vwap(buy 1): {:?}",
+            book.best_bid(),
+            book.best_ask(),
+            book.vwap(OrderSide::BUY, dec!(1))
+        );
+    }
+}
@@ -1,10 +1,15 @@
 mod common;
-use ethereal_rust_sdk::models::{OrderSide, OrderTimeInForce, OrderType};
+use ethereal_rust_sdk::{
+    async_client::session::SubaccountSelector,
+    models::{OrderSide, OrderTimeInForce},
+    order_builder::OrderBuilder,
+};
 use rust_decimal_macros::dec;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (client, _) = common::create_test_clients().await?;
+    let session = client.session(SubaccountSelector::First)?;
 
     println!("Creating order...");
 
@@ -12,75 +17,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let quantity = dec!(0.001);
     let price = dec!(80000.0);
     let side = OrderSide::BUY;
-    let r#type = OrderType::Limit;
 
-    let expires_at = None;
-    let time_in_force = OrderTimeInForce::Gtd;
-
-    // We have a few more options when creating an order now.
-    let mut post_only = false;
-    let mut reduce_only = false;
-
-    let order = client
+    let order = session
         .submit_order(
-            ticker,
-            quantity,
-            price,
-            side,
-            r#type,
-            time_in_force,
-            post_only,
-            reduce_only,
-            expires_at,
+            OrderBuilder::limit(ticker, side, quantity, price).time_in_force(OrderTimeInForce::Gtd),
         )
         .await
         .unwrap();
     println!("Order submitted: {order:?}");
 
-    // We can also create orders with post only flag
-    println!("Creating post only reduce only order...");
-    post_only = true;
-    reduce_only = false;
-    let order = client
-        .submit_order(
-            ticker,
-            quantity,
-            price,
-            side,
-            r#type,
-            time_in_force,
-            post_only,
-            reduce_only,
-            expires_at,
-        )
+    // We have a few more options when creating an order now.
+    println!("Creating post only order...");
+    let order = session
+        .submit_order(OrderBuilder::limit(ticker, side, quantity, price).post_only(true))
         .await
         .unwrap();
-    println!("Post and reduce only order submitted: {order:?}");
+    println!("Post only order submitted: {order:?}");
 
     println!("Creating order with expires_at...");
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)?
         .as_secs() as i64;
-    let expires_at = Some(now + 60); // Expires in 60 seconds
-    let order = client
+    let order = session
         .submit_order(
-            ticker,
-            quantity,
-            price,
-            side,
-            r#type,
-            time_in_force,
-            post_only,
-            reduce_only,
-            expires_at,
+            OrderBuilder::limit(ticker, side, quantity, price)
+                .post_only(true)
+                .expires_at(now + 60), // Expires in 60 seconds
         )
         .await
         .unwrap();
     println!("Order with expires_at submitted: {order:?}");
 
     println!("Fetching all current orders to cancel...");
-    let orders = client.get_open_orders().await?;
-    let cancel_result = client
+    let orders = session.get_open_orders().await?;
+    let cancel_result = session
         .cancel_orders(orders.iter().map(|order| order.id.to_string()).collect())
         .await?;
     println!("Cancel result: {cancel_result:?}");
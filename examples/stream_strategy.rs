@@ -0,0 +1,54 @@
+mod common;
+
+use ethereal_rust_sdk::{
+    subscription_stream::{OverflowPolicy, StreamConfig},
+    ws_client::ClientError,
+};
+use futures_util::StreamExt;
+use log::{info, warn};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+
+    let (http_client, ws_client) = common::create_test_clients().await?;
+    let subaccount_ids = common::get_subaccount_ids(&http_client).await?;
+    ws_client.wait_for_connection().await;
+
+    // Only the latest ticker per symbol matters if the strategy falls behind.
+    let mut tickers = ws_client
+        .subscriptions()
+        .stream_config(StreamConfig::default().overflow(OverflowPolicy::ConflateLatest))
+        .ticker_stream(vec!["BTCUSD".to_string(), "ETHUSD".to_string()])
+        .await?;
+    // Order updates must never be dropped.
+    let mut order_updates = ws_client
+        .subscriptions()
+        .stream_config(StreamConfig::default().overflow(OverflowPolicy::Block))
+        .order_update_stream(subaccount_ids)
+        .await?;
+
+    loop {
+        tokio::select! {
+            Some(ticker) = tickers.next() => match ticker {
+                Ok(ticker) => info!(
+                    "{} bid: {:?} ask: {:?}",
+                    ticker.data.s, ticker.data.bid_px, ticker.data.ask_px
+                ),
+                Err(e) => warn!("Bad ticker message: {e}"),
+            },
+            Some(update) = order_updates.next() => match update {
+                Ok(update) => {
+                    for order in update.data.d {
+                        info!("Order {} is now {:?}", order.id, order.st);
+                    }
+                }
+                // Updates sent while reconnecting were missed.
+                Err(ClientError::Gap) => warn!("Order updates missed, refetch open orders"),
+                Err(e) => warn!("Bad order update: {e}"),
+            },
+            else => break,
+        }
+    }
+    Ok(())
+}
@@ -19,7 +19,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let (http_client, ws_client) = common::create_test_clients().await?;
     let subaccount_ids = common::get_subaccount_ids(&http_client).await?;
-    ws_client
+    let _subscription = ws_client
         .subscriptions()
         .subaccount_liquidation(subaccount_ids, subaccount_liquidation_callback)
         .await?;
@@ -0,0 +1,715 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock, RwLockReadGuard},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures_util::{
+    stream::{self, BoxStream},
+    FutureExt, Stream, StreamExt, TryStreamExt,
+};
+use log::{error, warn};
+use rust_decimal::Decimal;
+use tokio::{
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+use uuid::Uuid;
+
+use crate::{
+    apis::{
+        configuration::Configuration,
+        order_api::OrderControllerListBySubaccountIdParams,
+        position_api::{
+            position_controller_list_by_subaccount_id, PositionControllerListBySubaccountIdParams,
+        },
+        subaccount_api::{
+            subaccount_controller_list_subaccount_balances,
+            SubaccountControllerListSubaccountBalancesParams,
+        },
+    },
+    async_client::{
+        client::{list_working_orders, HttpClient},
+        session::SubaccountSelector,
+    },
+    cancel::is_working,
+    error::Error,
+    models::{
+        OrderDto, OrderFillMessage, OrderFillMessageDataDInner, OrderSide, OrderStatus,
+        OrderUpdateMessage, OrderUpdateMessageDataDInner, PositionDto, PositionUpdateMessage,
+        PositionUpdateMessageDataDInner, SubaccountBalanceDto, TokenTransferMessage,
+        TokenTransferMessageData, TransferDtoOrderStatus,
+    },
+    pagination::{paginate, PaginationOptions},
+    ws_client::{ClientError, WsClient},
+};
+
+/// Capacity of the change channel; slower receivers get [`AccountChange::Missed`].
+pub const CHANGE_CAPACITY: usize = 1024;
+
+const RECONCILE_ATTEMPTS: u32 = 3;
+const RECONCILE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// An order resting on the book or awaiting placement, from REST or `OrderUpdate` messages.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpenOrder {
+    pub id: Uuid,
+    pub client_order_id: Option<String>,
+    /// Product ticker, e.g. `BTCUSD`.
+    pub symbol: String,
+    pub side: OrderSide,
+    pub status: OrderStatus,
+    pub quantity: Decimal,
+    pub available_quantity: Decimal,
+    pub filled: Decimal,
+    /// `None` for market orders.
+    pub price: Option<Decimal>,
+    pub reduce_only: bool,
+    /// Timestamp (ms) of the update this state is from.
+    pub updated_at: i64,
+}
+
+impl OpenOrder {
+    fn from_update(order: &OrderUpdateMessageDataDInner) -> Self {
+        Self {
+            id: order.id,
+            client_order_id: order.cloid.clone(),
+            symbol: order.s.clone(),
+            side: order.sd,
+            status: order.st,
+            quantity: order.qty,
+            available_quantity: order.aqty,
+            filled: order.fill,
+            price: order.px,
+            reduce_only: order.ro,
+            updated_at: order.t,
+        }
+    }
+
+    fn from_dto(order: &OrderDto, symbol: String) -> Self {
+        Self {
+            id: order.id,
+            client_order_id: order.client_order_id.clone(),
+            symbol,
+            side: order.side,
+            status: order.status,
+            quantity: order.quantity,
+            available_quantity: order.available_quantity,
+            filled: order.filled,
+            price: (!order.price.is_zero()).then_some(order.price),
+            reduce_only: order.reduce_only,
+            updated_at: order.updated_at,
+        }
+    }
+}
+
+/// An open position, from REST or `PositionUpdate` messages.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+    pub id: Uuid,
+    /// Product ticker, e.g. `BTCUSD`.
+    pub symbol: String,
+    pub side: OrderSide,
+    pub size: Decimal,
+    pub cost: Decimal,
+    pub realized_pnl: Decimal,
+    pub liquidation_price: Option<Decimal>,
+    /// Timestamp (ms) of the update this state is from.
+    pub updated_at: i64,
+}
+
+impl Position {
+    fn from_update(position: &PositionUpdateMessageDataDInner, t: i64) -> Self {
+        Self {
+            id: position.id,
+            symbol: position.s.clone(),
+            side: position.sd,
+            size: position.sz,
+            cost: position.cost,
+            realized_pnl: position.rpnl,
+            liquidation_price: position.lpx,
+            updated_at: t,
+        }
+    }
+
+    fn from_dto(position: &PositionDto, symbol: String) -> Self {
+        Self {
+            id: position.id,
+            symbol,
+            side: position.side,
+            size: position.size,
+            cost: position.cost,
+            realized_pnl: position.realized_pnl,
+            liquidation_price: position.liquidation_price,
+            updated_at: position.updated_at,
+        }
+    }
+}
+
+/// Collateral summed over the subaccount's token balances.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Margin {
+    pub total: Decimal,
+    pub available: Decimal,
+    pub used: Decimal,
+}
+
+/// What changed in an [`AccountState`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccountChange {
+    /// An order was placed or updated and is still open.
+    OrderUpdated(OpenOrder),
+    /// An order was filled, cancelled, rejected or expired, with its final state.
+    OrderClosed(OpenOrder),
+    Filled(OrderFillMessageDataDInner),
+    PositionUpdated(Position),
+    /// A position was closed, with its final state.
+    PositionClosed(Position),
+    Transfer(TokenTransferMessageData),
+    /// Balances were reloaded, so [`AccountState::margin`] may have changed.
+    BalancesRefreshed,
+    /// The whole state was replaced by a REST snapshot, e.g. after a reconnect.
+    Reconciled,
+    /// This many changes were dropped because the receiver fell behind; read the state instead.
+    Missed(u64),
+}
+
+/// Orders, positions and balances of one subaccount, seeded from REST and kept up to date by
+/// applying WebSocket updates in order.
+///
+/// Updates older than the state they would replace are ignored, so updates received while a
+/// snapshot was being loaded can be applied after it. That includes updates older than the
+/// snapshot for orders and positions missing from it, which were closed by then.
+#[derive(Clone, Debug, Default)]
+pub struct AccountState {
+    subaccount_id: Uuid,
+    /// Product ticker by product id, since REST refers to products by id.
+    symbols: HashMap<Uuid, String>,
+    orders: HashMap<Uuid, OpenOrder>,
+    /// Timestamp (ms) at which each order seen closed since the last snapshot was closed.
+    closed: HashMap<Uuid, i64>,
+    /// Timestamp (ms) as of which the last snapshot was taken.
+    seeded_at: i64,
+    positions: HashMap<String, Position>,
+    balances: Vec<SubaccountBalanceDto>,
+    synced: bool,
+}
+
+impl AccountState {
+    pub fn new(subaccount_id: Uuid, symbols: HashMap<Uuid, String>) -> Self {
+        Self {
+            subaccount_id,
+            symbols,
+            ..Default::default()
+        }
+    }
+
+    pub fn subaccount_id(&self) -> Uuid {
+        self.subaccount_id
+    }
+
+    /// Whether the state was seeded and no updates were missed since.
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    /// Marks updates as missed, e.g. after a reconnect, until the next [`AccountState::seed`].
+    pub fn mark_stale(&mut self) {
+        self.synced = false;
+    }
+
+    /// Replaces the state with REST snapshots of the open orders, open positions and balances,
+    /// requested at `as_of` (ms).
+    ///
+    /// Orders which are no longer open and products without a known ticker are skipped. The
+    /// snapshot counts as taken at `as_of` or its newest update, whichever is later.
+    pub fn seed(
+        &mut self,
+        orders: &[OrderDto],
+        positions: &[PositionDto],
+        balances: Vec<SubaccountBalanceDto>,
+        as_of: i64,
+    ) {
+        self.seeded_at = orders
+            .iter()
+            .map(|order| order.updated_at)
+            .chain(positions.iter().map(|position| position.updated_at))
+            .fold(as_of, i64::max);
+        self.orders = orders
+            .iter()
+            .filter(|order| is_working(order.status))
+            .filter_map(|order| {
+                let symbol = self.symbol(order.product_id)?;
+                Some((order.id, OpenOrder::from_dto(order, symbol)))
+            })
+            .collect();
+        self.positions = positions
+            .iter()
+            .filter(|position| !position.size.is_zero())
+            .filter_map(|position| {
+                let symbol = self.symbol(position.product_id)?;
+                Some((symbol.clone(), Position::from_dto(position, symbol)))
+            })
+            .collect();
+        self.closed.clear();
+        self.balances = balances;
+        self.synced = true;
+    }
+
+    pub fn set_balances(&mut self, balances: Vec<SubaccountBalanceDto>) {
+        self.balances = balances;
+    }
+
+    /// Applies an `OrderUpdate` entry, unless it is for another subaccount or older than the
+    /// order's current state.
+    pub fn apply_order_update(
+        &mut self,
+        update: &OrderUpdateMessageDataDInner,
+    ) -> Option<AccountChange> {
+        if update.sid != self.subaccount_id
+            || self.closed.get(&update.id).is_some_and(|t| *t >= update.t)
+            || self
+                .orders
+                .get(&update.id)
+                .map_or(update.t <= self.seeded_at, |order| {
+                    order.updated_at > update.t
+                })
+        {
+            return None;
+        }
+        let order = OpenOrder::from_update(update);
+        if is_working(order.status) {
+            self.orders.insert(order.id, order.clone());
+            Some(AccountChange::OrderUpdated(order))
+        } else {
+            self.orders.remove(&order.id);
+            self.closed.insert(order.id, order.updated_at);
+            Some(AccountChange::OrderClosed(order))
+        }
+    }
+
+    /// Applies an `OrderFill` entry to its order, unless the order was updated since the fill.
+    pub fn apply_fill(&mut self, fill: &OrderFillMessageDataDInner) -> Option<AccountChange> {
+        if fill.sid != self.subaccount_id {
+            return None;
+        }
+        if let Some(order) = self.orders.get_mut(&fill.oid) {
+            if order.updated_at < fill.t {
+                order.filled += fill.sz;
+                order.available_quantity = (order.available_quantity - fill.sz).max(Decimal::ZERO);
+                order.updated_at = fill.t;
+            }
+        }
+        Some(AccountChange::Filled(fill.clone()))
+    }
+
+    /// Applies a `PositionUpdate` entry sent at `t`, unless it is for another subaccount or
+    /// older than the position's current state.
+    pub fn apply_position_update(
+        &mut self,
+        update: &PositionUpdateMessageDataDInner,
+        t: i64,
+    ) -> Option<AccountChange> {
+        if update.sid != self.subaccount_id
+            || self
+                .positions
+                .get(&update.s)
+                .map_or(t <= self.seeded_at, |position| position.updated_at > t)
+        {
+            return None;
+        }
+        let position = Position::from_update(update, t);
+        if position.size.is_zero() {
+            self.positions.remove(&position.symbol);
+            Some(AccountChange::PositionClosed(position))
+        } else {
+            self.positions
+                .insert(position.symbol.clone(), position.clone());
+            Some(AccountChange::PositionUpdated(position))
+        }
+    }
+
+    /// Balances are only available over REST, so a transfer is reported but leaves them as
+    /// they are until reloaded.
+    pub fn apply_transfer(&mut self, transfer: &TokenTransferMessageData) -> Option<AccountChange> {
+        (transfer.sid == self.subaccount_id).then(|| AccountChange::Transfer(transfer.clone()))
+    }
+
+    pub fn order(&self, id: &Uuid) -> Option<&OpenOrder> {
+        self.orders.get(id)
+    }
+
+    pub fn order_by_client_id(&self, client_order_id: &str) -> Option<&OpenOrder> {
+        self.orders
+            .values()
+            .find(|order| order.client_order_id.as_deref() == Some(client_order_id))
+    }
+
+    pub fn open_orders(&self) -> impl Iterator<Item = &OpenOrder> {
+        self.orders.values()
+    }
+
+    pub fn open_orders_for<'a>(&'a self, symbol: &'a str) -> impl Iterator<Item = &'a OpenOrder> {
+        self.orders
+            .values()
+            .filter(move |order| order.symbol == symbol)
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<&Position> {
+        self.positions.get(symbol)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions.values()
+    }
+
+    pub fn balances(&self) -> &[SubaccountBalanceDto] {
+        &self.balances
+    }
+
+    /// As of the last balance snapshot; fills and position updates do not change it.
+    pub fn margin(&self) -> Margin {
+        self.balances
+            .iter()
+            .fold(Margin::default(), |margin, balance| Margin {
+                total: margin.total + balance.amount,
+                available: margin.available + balance.available,
+                used: margin.used + balance.total_used,
+            })
+    }
+
+    fn symbol(&self, product_id: Uuid) -> Option<String> {
+        let symbol = self.symbols.get(&product_id).cloned();
+        if symbol.is_none() {
+            warn!("No ticker for product {product_id}, skipping it");
+        }
+        symbol
+    }
+}
+
+/// Read-only handle to the state maintained by an [`AccountFeed`], cheap to clone across tasks.
+#[derive(Clone, Debug)]
+pub struct AccountView {
+    inner: Arc<RwLock<AccountState>>,
+}
+
+impl AccountView {
+    pub fn read(&self) -> RwLockReadGuard<'_, AccountState> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn snapshot(&self) -> AccountState {
+        self.read().clone()
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.read().is_synced()
+    }
+
+    pub fn order(&self, id: &Uuid) -> Option<OpenOrder> {
+        self.read().order(id).cloned()
+    }
+
+    pub fn order_by_client_id(&self, client_order_id: &str) -> Option<OpenOrder> {
+        self.read().order_by_client_id(client_order_id).cloned()
+    }
+
+    pub fn open_orders(&self) -> Vec<OpenOrder> {
+        self.read().open_orders().cloned().collect()
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<Position> {
+        self.read().position(symbol).cloned()
+    }
+
+    pub fn positions(&self) -> Vec<Position> {
+        self.read().positions().cloned().collect()
+    }
+
+    pub fn margin(&self) -> Margin {
+        self.read().margin()
+    }
+}
+
+/// REST endpoints the feed loads snapshots from.
+struct Snapshots {
+    config: Configuration,
+    subaccount_id: Uuid,
+}
+
+impl Snapshots {
+    async fn orders(&self) -> Result<Vec<OrderDto>, Error> {
+        list_working_orders(
+            &self.config,
+            OrderControllerListBySubaccountIdParams {
+                subaccount_id: self.subaccount_id.to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    async fn positions(&self) -> Result<Vec<PositionDto>, Error> {
+        let params = PositionControllerListBySubaccountIdParams {
+            subaccount_id: self.subaccount_id.to_string(),
+            open: Some(true),
+            ..Default::default()
+        };
+        paginate(params, PaginationOptions::default(), |params| {
+            position_controller_list_by_subaccount_id(&self.config, params)
+        })
+        .try_collect()
+        .await
+    }
+
+    async fn balances(&self) -> Result<Vec<SubaccountBalanceDto>, Error> {
+        let params = SubaccountControllerListSubaccountBalancesParams {
+            subaccount_id: self.subaccount_id.to_string(),
+            ..Default::default()
+        };
+        paginate(params, PaginationOptions::default(), |params| {
+            subaccount_controller_list_subaccount_balances(&self.config, params)
+        })
+        .try_collect()
+        .await
+    }
+
+    async fn seed(&self, state: &RwLock<AccountState>) -> Result<(), Error> {
+        let as_of = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as i64);
+        let (orders, positions, balances) =
+            tokio::try_join!(self.orders(), self.positions(), self.balances())?;
+        state
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .seed(&orders, &positions, balances, as_of);
+        Ok(())
+    }
+}
+
+enum Update {
+    Order(OrderUpdateMessage),
+    Fill(OrderFillMessage),
+    Position(PositionUpdateMessage),
+    Transfer(Box<TokenTransferMessage>),
+}
+
+/// The [`AccountState`] of one subaccount, kept up to date from the `OrderUpdate`, `OrderFill`,
+/// `PositionUpdate` and `TokenTransfer` channels.
+///
+/// The subscriptions are made before the REST snapshot is loaded, so nothing is missed in
+/// between. After a reconnect the state is marked stale and reloaded; completed transfers reload
+/// the balances. Dropping the feed unsubscribes.
+pub struct AccountFeed {
+    state: Arc<RwLock<AccountState>>,
+    changes: broadcast::Sender<AccountChange>,
+    reconcile_tx: mpsc::UnboundedSender<()>,
+    task: JoinHandle<()>,
+}
+
+impl AccountFeed {
+    pub async fn subscribe(
+        http_client: &HttpClient,
+        ws_client: &WsClient,
+        subaccount: impl Into<SubaccountSelector>,
+    ) -> Result<Self, Error> {
+        let subaccount_id = http_client.get_subaccount(subaccount)?.id;
+        let symbols = http_client
+            .product_id_hashmap
+            .values()
+            .map(|p| (p.id, p.ticker.clone()))
+            .collect();
+        let state = Arc::new(RwLock::new(AccountState::new(subaccount_id, symbols)));
+        let snapshots = Snapshots {
+            config: http_client.config.clone(),
+            subaccount_id,
+        };
+
+        let subscriptions = ws_client.subscriptions();
+        let ids = vec![subaccount_id.to_string()];
+        let updates: Vec<BoxStream<'static, Result<Update, ClientError>>> = vec![
+            updates(
+                subscriptions.order_update_stream(ids.clone()).await?,
+                Update::Order,
+            ),
+            updates(
+                subscriptions.order_fill_stream(ids.clone()).await?,
+                Update::Fill,
+            ),
+            updates(
+                subscriptions.position_update_stream(ids.clone()).await?,
+                Update::Position,
+            ),
+            updates(subscriptions.token_transfer_stream(ids).await?, |msg| {
+                Update::Transfer(Box::new(msg))
+            }),
+        ];
+        snapshots.seed(&state).await?;
+
+        let (changes, _) = broadcast::channel(CHANGE_CAPACITY);
+        let (reconcile_tx, reconcile_rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_feed(
+            stream::select_all(updates),
+            reconcile_rx,
+            state.clone(),
+            snapshots,
+            changes.clone(),
+        ));
+        Ok(Self {
+            state,
+            changes,
+            reconcile_tx,
+            task,
+        })
+    }
+
+    pub fn view(&self) -> AccountView {
+        AccountView {
+            inner: self.state.clone(),
+        }
+    }
+
+    /// Changes applied from now on, in order.
+    pub fn changes(&self) -> impl Stream<Item = AccountChange> + Send + 'static {
+        stream::unfold(self.changes.subscribe(), |mut rx| async move {
+            let change = match rx.recv().await {
+                Ok(change) => change,
+                Err(broadcast::error::RecvError::Lagged(missed)) => AccountChange::Missed(missed),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+            Some((change, rx))
+        })
+    }
+
+    /// Reloads the whole state from REST, e.g. to refresh the margin after trading.
+    pub fn reconcile(&self) {
+        let _ = self.reconcile_tx.send(());
+    }
+}
+
+impl Drop for AccountFeed {
+    fn drop(&mut self) {
+        // Drops the subscription streams, which unsubscribes.
+        self.task.abort();
+    }
+}
+
+fn updates<T: Send + 'static>(
+    stream: impl Stream<Item = Result<T, ClientError>> + Send + 'static,
+    update: fn(T) -> Update,
+) -> BoxStream<'static, Result<Update, ClientError>> {
+    stream.map(move |msg| msg.map(update)).boxed()
+}
+
+async fn run_feed(
+    mut updates: impl Stream<Item = Result<Update, ClientError>> + Unpin,
+    mut reconcile_rx: mpsc::UnboundedReceiver<()>,
+    state: Arc<RwLock<AccountState>>,
+    snapshots: Snapshots,
+    changes: broadcast::Sender<AccountChange>,
+) {
+    let mut next = None;
+    loop {
+        let update = match next.take() {
+            Some(update) => update,
+            None => tokio::select! {
+                update = updates.next() => match update {
+                    Some(update) => update,
+                    None => return,
+                },
+                Some(()) = reconcile_rx.recv() => {
+                    reconcile(&state, &snapshots, &changes).await;
+                    continue;
+                }
+            },
+        };
+        let update = match update {
+            Ok(update) => update,
+            Err(ClientError::Gap) => {
+                state
+                    .write()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .mark_stale();
+                // Every channel reports the same reconnect; reload once for the gaps queued.
+                next = loop {
+                    match updates.next().now_or_never() {
+                        Some(Some(Err(ClientError::Gap))) => continue,
+                        Some(update) => break update,
+                        None => break None,
+                    }
+                };
+                reconcile(&state, &snapshots, &changes).await;
+                continue;
+            }
+            Err(e) => {
+                warn!("Failed to parse account update: {e}");
+                continue;
+            }
+        };
+        let mut refresh_balances = false;
+        let applied: Vec<AccountChange> = {
+            let mut state = state.write().unwrap_or_else(|e| e.into_inner());
+            match &update {
+                Update::Order(msg) => msg
+                    .data
+                    .d
+                    .iter()
+                    .filter_map(|order| state.apply_order_update(order))
+                    .collect(),
+                Update::Fill(msg) => msg
+                    .data
+                    .d
+                    .iter()
+                    .filter_map(|fill| state.apply_fill(fill))
+                    .collect(),
+                Update::Position(msg) => msg
+                    .data
+                    .d
+                    .iter()
+                    .filter_map(|position| state.apply_position_update(position, msg.data.t))
+                    .collect(),
+                Update::Transfer(msg) => {
+                    refresh_balances = msg.data.st == TransferDtoOrderStatus::Completed;
+                    state.apply_transfer(&msg.data).into_iter().collect()
+                }
+            }
+        };
+        for change in applied {
+            let _ = changes.send(change);
+        }
+        if refresh_balances {
+            match snapshots.balances().await {
+                Ok(balances) => {
+                    state
+                        .write()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .set_balances(balances);
+                    let _ = changes.send(AccountChange::BalancesRefreshed);
+                }
+                Err(e) => error!("Failed to reload balances after a transfer: {e}"),
+            }
+        }
+    }
+}
+
+/// Reloads the state, retrying a few times before leaving it stale until the next reconnect or
+/// [`AccountFeed::reconcile`].
+async fn reconcile(
+    state: &RwLock<AccountState>,
+    snapshots: &Snapshots,
+    changes: &broadcast::Sender<AccountChange>,
+) {
+    for attempt in 1..=RECONCILE_ATTEMPTS {
+        match snapshots.seed(state).await {
+            Ok(()) => {
+                let _ = changes.send(AccountChange::Reconciled);
+                return;
+            }
+            Err(e) => {
+                error!("Failed to reload account state (attempt {attempt}): {e}");
+                tokio::time::sleep(RECONCILE_RETRY_DELAY).await;
+            }
+        }
+    }
+}
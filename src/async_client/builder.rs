@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use ethers::signers::{LocalWallet, Signer, WalletError};
+use thiserror::Error;
+
+use crate::{
+    apis::{
+        self, configuration::Configuration, product_api::ProductControllerListError,
+        subaccount_api::SubaccountControllerListByAccountError,
+    },
+    archive_apis::configuration::Configuration as ArchiveConfiguration,
+    async_client::client::{get_archive_server_url, get_server_url, HttpClient},
+    enums::Environment,
+    order_builder::ClientOrderIdStrategy,
+};
+
+#[derive(Error, Debug)]
+pub enum ClientBuildError {
+    #[error("Invalid private key: {0}")]
+    InvalidPrivateKey(#[from] WalletError),
+    #[error("Failed to build HTTP client: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Failed to load subaccounts: {0}")]
+    Subaccounts(#[from] apis::Error<SubaccountControllerListByAccountError>),
+    #[error("Failed to load products: {0}")]
+    Products(#[from] apis::Error<ProductControllerListError>),
+}
+
+/// Whether products and subaccounts are fetched while building the client.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoadStrategy {
+    /// Fetch products and subaccounts in [`HttpClientBuilder::build`].
+    #[default]
+    Eager,
+    /// Start empty; call [`HttpClient::refresh_products`] and
+    /// [`HttpClient::refresh_subaccounts`] before trading.
+    Lazy,
+}
+
+pub struct HttpClientBuilder {
+    env: Environment,
+    private_key: String,
+    owner_address: Option<String>,
+    base_url: Option<String>,
+    archive_base_url: Option<String>,
+    http_client: Option<reqwest::Client>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    load_strategy: LoadStrategy,
+    client_order_ids: ClientOrderIdStrategy,
+}
+
+impl HttpClientBuilder {
+    pub fn new(env: Environment, private_key: &str) -> Self {
+        Self {
+            env,
+            private_key: private_key.to_string(),
+            owner_address: None,
+            base_url: None,
+            archive_base_url: None,
+            http_client: None,
+            timeout: None,
+            connect_timeout: None,
+            user_agent: None,
+            load_strategy: LoadStrategy::default(),
+            client_order_ids: ClientOrderIdStrategy::default(),
+        }
+    }
+
+    /// Account owning the subaccounts, when signing with a linked signer.
+    pub fn owner_address(mut self, owner_address: impl Into<String>) -> Self {
+        self.owner_address = Some(owner_address.into());
+        self
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn archive_base_url(mut self, archive_base_url: impl Into<String>) -> Self {
+        self.archive_base_url = Some(archive_base_url.into());
+        self
+    }
+
+    /// Use a preconfigured client; `timeout` and `connect_timeout` are then ignored.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    pub fn load_strategy(mut self, load_strategy: LoadStrategy) -> Self {
+        self.load_strategy = load_strategy;
+        self
+    }
+
+    /// How client order ids are generated for orders which do not set one; random UUIDs by
+    /// default.
+    pub fn client_order_ids(mut self, client_order_ids: ClientOrderIdStrategy) -> Self {
+        self.client_order_ids = client_order_ids;
+        self
+    }
+
+    pub async fn build(self) -> Result<HttpClient, ClientBuildError> {
+        let env = self.env;
+        let wallet = self.private_key.parse::<LocalWallet>()?;
+        let address = format!("{:?}", wallet.address());
+
+        let client = match self.http_client {
+            Some(client) => client,
+            None => {
+                let mut builder = reqwest::Client::builder();
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(connect_timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                builder.build()?
+            }
+        };
+        let defaults = Configuration::default();
+        let user_agent = self.user_agent.or(defaults.user_agent);
+        let config = Configuration {
+            base_path: self
+                .base_url
+                .unwrap_or_else(|| get_server_url(&env).to_string()),
+            user_agent: user_agent.clone(),
+            client: client.clone(),
+            ..defaults
+        };
+        let archive_config = ArchiveConfiguration {
+            base_path: self
+                .archive_base_url
+                .unwrap_or_else(|| get_archive_server_url(&env).to_string()),
+            user_agent,
+            client,
+            ..Default::default()
+        };
+
+        let mut http_client = HttpClient {
+            env,
+            config,
+            archive_config,
+            wallet,
+            address,
+            owner_address: self.owner_address,
+            subaccounts: Vec::new(),
+            product_hashmap: Default::default(),
+            product_id_hashmap: Default::default(),
+            client_order_ids: self.client_order_ids,
+        };
+        if self.load_strategy == LoadStrategy::Eager {
+            http_client.refresh_subaccounts().await?;
+            http_client.refresh_products().await?;
+        }
+        Ok(http_client)
+    }
+}
@@ -2,38 +2,49 @@ use crate::{
     apis::{
         configuration::Configuration,
         order_api::{
-            OrderControllerCancelParams, OrderControllerListBySubaccountIdParams,
-            OrderControllerSubmitParams,
+            order_controller_list_by_subaccount_id, OrderControllerCancelParams,
+            OrderControllerGetByIdParams, OrderControllerListBySubaccountIdParams,
+            OrderControllerListGroupByOrderIdParams, OrderControllerSubmitParams,
         },
         product_api::ProductControllerListParams,
         subaccount_api::SubaccountControllerListByAccountParams,
     },
     archive_apis::configuration::Configuration as ArchiveConfiguration,
     async_client::{
+        builder::{ClientBuildError, HttpClientBuilder},
         funding::FundingClient,
         linked_signer::LinkedSignerClient,
         maintenance::MaintenanceClient,
         order::OrderClient,
         points::PointsClient,
         position::PositionClient,
-        product::{self, ProductClient},
+        product::ProductClient,
         rate_limit::RateLimitClient,
         referral::ReferralClient,
         rpc::RpcClient,
+        session::{SubaccountSelector, SubaccountSession},
         subaccount::SubaccountClient,
         subaccount_archive::SubaccountArchiveClient,
         time::TimeClient,
         token::TokenClient,
         whitelist::WhitelistClient,
     },
+    cancel::{is_working, CancelBatch, CancelFilter, CancelReport, FailedCancelBatch},
     enums::Environment,
+    error::Error,
+    managed_order::{is_terminal, ManagedOrder, OrderTracker},
     models::{
-        CancelOrderDto, CancelOrderDtoData, CancelOrderResultDto, OrderSide, OrderStatus,
-        OrderTimeInForce, OrderType, ProductDto, SubaccountDto, SubmitOrderCreatedDto,
-        SubmitOrderData, SubmitOrderDto, SubmitOrderLimitDtoData,
+        CancelOrderDto, CancelOrderDtoData, CancelOrderResultCode, CancelOrderResultDto,
+        OrderDtoOrderType, OrderSide, OrderStatus, ProductDto, StopType, SubaccountDto,
+        SubmitCreatedCode, SubmitDryOrderDto, SubmitDryOrderDtoData, SubmitOrderCreatedDto,
+        SubmitOrderData, SubmitOrderDto,
     },
-    signable_messages::{CancelOrder, TradeOrder},
-    signing::{hex_to_bytes32, to_scaled_e9, SigningContext},
+    order_builder::{validate_client_order_id, ClientOrderIdStrategy, OrderBuilder},
+    order_group::{BracketOrder, OrderGroup},
+    pagination::{paginate, PaginationOptions},
+    replace_order::{ReplaceError, ReplaceMode, ReplaceOptions, ReplaceReport},
+    signable_messages::CancelOrder,
+    signing::{hex_to_bytes32, SigningContext},
 };
 use anyhow::Result;
 
@@ -42,33 +53,29 @@ use ethers::{
     signers::{LocalWallet, Signer},
     utils::hex,
 };
-use log::debug;
+use futures_util::TryStreamExt;
+use log::{debug, error, warn};
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 use uuid::Uuid;
 
-fn get_server_url(environment: &Environment) -> &str {
+pub(crate) fn get_server_url(environment: &Environment) -> &str {
     match environment {
         Environment::Mainnet => "https://api.ethereal.trade",
         Environment::Testnet => "https://api.etherealtest.net",
     }
 }
 
-fn get_archive_server_url(environment: &Environment) -> &str {
+pub(crate) fn get_archive_server_url(environment: &Environment) -> &str {
     match environment {
         Environment::Mainnet => "https://archive.ethereal.trade",
         Environment::Testnet => "https://archive.etherealtest.net",
     }
 }
 
-fn round_to_tick(value: Decimal, tick_size: Decimal) -> Result<Decimal> {
-    if tick_size.is_zero() {
-        return Ok(value);
-    }
-    let ticks = (value / tick_size).round();
-    Ok(ticks * tick_size)
-}
-
 #[macro_export]
 macro_rules! with_signing_fields {
     ($signing_fn:ident, $ctx:expr, $struct:ident { $($rest:tt)* }) => {{
@@ -83,75 +90,85 @@ macro_rules! with_signing_fields {
     }};
 }
 
+/// How [`HttpClient::submit_order_with_retry`] retries a submission which may not have landed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubmitRetryPolicy {
+    /// Submissions in all, including the first.
+    pub max_attempts: u32,
+    /// Wait after a failed submission before looking the order up, so that an accepted order
+    /// has time to show up.
+    pub lookup_delay: Duration,
+}
+
+impl Default for SubmitRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            lookup_delay: Duration::from_secs(1),
+        }
+    }
+}
+
 pub struct HttpClient {
     pub env: Environment,
-    config: Configuration,
-    archive_config: ArchiveConfiguration,
+    pub(crate) config: Configuration,
+    pub(crate) archive_config: ArchiveConfiguration,
     pub wallet: LocalWallet,
     pub address: String,
     pub owner_address: Option<String>,
     pub subaccounts: Vec<SubaccountDto>,
     pub product_hashmap: HashMap<String, ProductDto>,
     pub product_id_hashmap: HashMap<Uuid, ProductDto>,
+    pub(crate) client_order_ids: ClientOrderIdStrategy,
 }
 
 impl HttpClient {
-    pub async fn new(env: Environment, private_key: &str, owner_address: Option<String>) -> Self {
-        let config = Configuration {
-            base_path: get_server_url(&env).to_string(),
-            ..Default::default()
-        };
-        let archive_config = ArchiveConfiguration {
-            base_path: get_archive_server_url(&env).to_string(),
-            ..Default::default()
-        };
+    pub fn builder(env: Environment, private_key: &str) -> HttpClientBuilder {
+        HttpClientBuilder::new(env, private_key)
+    }
+
+    pub async fn new(
+        env: Environment,
+        private_key: &str,
+        owner_address: Option<String>,
+    ) -> Result<Self, ClientBuildError> {
+        let mut builder = Self::builder(env, private_key);
+        if let Some(owner_address) = owner_address {
+            builder = builder.owner_address(owner_address);
+        }
+        builder.build().await
+    }
 
-        let wallet = private_key.parse::<LocalWallet>().unwrap();
-        let address = format!("{:?}", wallet.address());
-        let sender_address = owner_address
+    /// Reloads the subaccounts of the owner account (or of the signer when there is no owner).
+    pub async fn refresh_subaccounts(&mut self) -> Result<(), ClientBuildError> {
+        let sender = self
+            .owner_address
             .clone()
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| address.clone());
-        let subaccounts = SubaccountClient { config: &config }
+            .unwrap_or_else(|| self.address.clone());
+        self.subaccounts = self
+            .subaccount()
             .list_by_account(SubaccountControllerListByAccountParams {
-                sender: sender_address,
+                sender,
                 ..Default::default()
             })
-            .await
-            .unwrap()
+            .await?
             .data;
-        let product_hashmap = product::ProductClient { config: &config }
-            .list(ProductControllerListParams {
-                ..Default::default()
-            })
-            .await
-            .unwrap()
-            .data
+        Ok(())
+    }
+
+    /// Reloads the products, keyed by display ticker and by id.
+    pub async fn refresh_products(&mut self) -> Result<(), ClientBuildError> {
+        let products = self
+            .product()
+            .list(ProductControllerListParams::default())
+            .await?
+            .data;
+        self.product_id_hashmap = products.iter().map(|p| (p.id, p.clone())).collect();
+        self.product_hashmap = products
             .into_iter()
             .map(|p| (p.display_ticker.clone(), p))
             .collect();
-        let product_id_hashmap = product::ProductClient { config: &config }
-            .list(ProductControllerListParams {
-                ..Default::default()
-            })
-            .await
-            .unwrap()
-            .data
-            .into_iter()
-            .map(|p| (p.id, p))
-            .collect();
-
-        Self {
-            env,
-            config,
-            archive_config,
-            wallet,
-            address,
-            owner_address,
-            subaccounts,
-            product_hashmap,
-            product_id_hashmap,
-        }
+        Ok(())
     }
 
     pub fn product(&self) -> ProductClient<'_> {
@@ -231,137 +248,709 @@ impl HttpClient {
         }
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub async fn submit_order(
+    /// Resolves a subaccount loaded at construction, by name, id or handle.
+    pub fn get_subaccount(
         &self,
-        ticker: &str,
-        quantity: Decimal,
-        price: Decimal,
-        side: OrderSide,
-        r#type: OrderType,
-        time_in_force: OrderTimeInForce,
-        post_only: bool,
-        reduce_only: bool,
-        expires_at: Option<i64>,
-    ) -> Result<SubmitOrderCreatedDto, Box<dyn std::error::Error>> {
-        if !self.product_hashmap.contains_key(ticker) {
-            return Err(format!("Ticker {ticker} not found").into());
+        subaccount: impl Into<SubaccountSelector>,
+    ) -> Result<&SubaccountDto, Error> {
+        let selector = subaccount.into();
+        self.subaccounts
+            .iter()
+            .find(|s| selector.matches(s))
+            .ok_or_else(|| Error::Validation(format!("No {selector} found for {}", self.address)))
+    }
+
+    /// Binds the trading methods to a single subaccount.
+    pub fn session(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+    ) -> Result<SubaccountSession<'_>, Error> {
+        Ok(SubaccountSession {
+            client: self,
+            subaccount: self.get_subaccount(subaccount)?,
+        })
+    }
+
+    /// Signs an order for `subaccount` without sending it, e.g. to submit it over the WebSocket
+    /// with `WsClient::submit_order`.
+    pub fn prepare_order(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        order: OrderBuilder,
+    ) -> Result<SubmitOrderDto, Error> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        let order = self.with_client_order_id(order);
+        let ticker = order.ticker();
+        let product_info = self
+            .product_hashmap
+            .get(ticker)
+            .ok_or_else(|| Error::Validation(format!("Ticker {ticker} not found")))?;
+
+        let ctx = SigningContext::new(&self.wallet, subaccount);
+        Ok(order.build(product_info, &ctx, self.env)?)
+    }
+
+    /// Gives the order a client order id from the client's [`ClientOrderIdStrategy`], unless it
+    /// has one.
+    fn with_client_order_id(&self, order: OrderBuilder) -> OrderBuilder {
+        if order.get_client_order_id().is_some() {
+            return order;
         }
-        if OrderType::Limit != r#type {
-            return Err("Only limit orders are supported in this method".into());
+        match self.client_order_ids.generate() {
+            Some(client_order_id) => order.client_order_id(client_order_id),
+            None => order,
         }
-        let product_info = self.product_hashmap.get(ticker).unwrap();
-        let tick_size = product_info.tick_size;
-        let lot_size = product_info.lot_size;
+    }
 
-        let price = round_to_tick(price, tick_size)?;
-        let quantity = round_to_tick(quantity, lot_size)?;
+    /// Builds the dry run request of an order, which carries the signed fields without the
+    /// signature.
+    pub fn prepare_dry_run(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        order: OrderBuilder,
+    ) -> Result<SubmitDryOrderDto, Error> {
+        let data = match self.prepare_order(subaccount, order)?.data {
+            SubmitOrderData::SubmitOrderLimitDtoData(data) => {
+                SubmitDryOrderDtoData::SubmitOrderLimitDtoData(data)
+            }
+            SubmitOrderData::SubmitOrderMarketDtoData(data) => {
+                SubmitDryOrderDtoData::SubmitOrderMarketDtoData(data)
+            }
+        };
+        Ok(SubmitDryOrderDto { data })
+    }
 
-        debug!(
-            "Submitting order with quantity: {}, price: {}, side: {:?}, type: {:?}, time_in_force: {:?}, post_only: {}, reduce_only: {}, expires_at: {:?}",
-            quantity, price, side, r#type, time_in_force, post_only, reduce_only, expires_at
-        );
+    /// Signs the cancellation of `order_ids` without sending it.
+    pub fn prepare_cancel(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        order_ids: Vec<String>,
+    ) -> Result<CancelOrderDto, Error> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        let ids = order_ids
+            .iter()
+            .map(|id| {
+                Uuid::parse_str(id)
+                    .map_err(|e| Error::Validation(format!("Invalid order id {id}: {e}")))
+            })
+            .collect::<Result<Vec<Uuid>, Error>>()?;
+        self.sign_cancel(
+            subaccount,
+            CancelBatch {
+                order_ids: ids,
+                ..Default::default()
+            },
+        )
+    }
 
-        let ctx = SigningContext::new(&self.wallet, &self.subaccounts[0]);
-        let message = with_signing_fields!(
-            eip_signing_fields,
-            ctx,
-            TradeOrder {
-                quantity: to_scaled_e9(quantity)?,
-                price: to_scaled_e9(price)?,
-                reduce_only,
-                side: side as u8,
-                engine_type: product_info.engine_type.to_string().parse()?,
-                product_id: product_info.onchain_id.to_string().parse()?,
-            }
-        );
+    /// Signs the cancellation of a batch with a fresh nonce.
+    fn sign_cancel(
+        &self,
+        subaccount: &SubaccountDto,
+        batch: CancelBatch,
+    ) -> Result<CancelOrderDto, Error> {
+        let ctx = SigningContext::new(&self.wallet, subaccount);
+        let message = CancelOrder {
+            sender: self.wallet.address(),
+            subaccount: hex_to_bytes32(&subaccount.name)
+                .map_err(|e| Error::Signing(e.to_string()))?,
+            nonce: ctx.nonce, // increment nonce for the cancel order
+        };
 
         let signature = message.sign(self.env, &self.wallet)?;
-        let order_dto = with_signing_fields!(
-            dto_signing_fields,
-            ctx,
-            SubmitOrderLimitDtoData {
-                quantity,
-                price,
-                side,
-                onchain_id: product_info.onchain_id,
-                engine_type: product_info.engine_type,
-                reduce_only: Some(reduce_only),
-                post_only,
-                expires_at,
-                time_in_force,
-                r#type,
-                ..Default::default()
-            }
-        );
-        let dto = SubmitOrderDto {
-            data: SubmitOrderData::SubmitOrderLimitDtoData(order_dto),
+        Ok(CancelOrderDto {
+            data: CancelOrderDtoData {
+                subaccount: subaccount.name.clone(),
+                sender: self.address.to_string(),
+                nonce: ctx.nonce.to_string(),
+                order_ids: Some(batch.order_ids).filter(|ids| !ids.is_empty()),
+                client_order_ids: Some(batch.client_order_ids).filter(|ids| !ids.is_empty()),
+            },
             signature: "0x".to_string() + &hex::encode(signature.to_vec()),
-        };
+        })
+    }
+
+    pub async fn submit_order(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        order: OrderBuilder,
+    ) -> Result<SubmitOrderCreatedDto, Error> {
+        debug!("Submitting order: {order:?}");
+        let dto = self.prepare_order(subaccount, order)?;
 
-        let result = self
+        let response = self
             .order()
             .submit(OrderControllerSubmitParams {
                 submit_order_dto: dto,
             })
-            .await;
-        match result {
-            Ok(response) => Ok(response),
-            Err(e) => Err(Box::new(e)),
+            .await?;
+        Ok(response)
+    }
+
+    /// Like [`HttpClient::submit_order`], but retries failures which may be transient without
+    /// risking a duplicate order.
+    ///
+    /// The order keeps one client order id, generated if need be, and is signed once. After a
+    /// retryable failure the order is looked up by that id, and only resubmitted if it did not
+    /// land; an order found this way is returned as created, with its current fill.
+    pub async fn submit_order_with_retry(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        order: OrderBuilder,
+        policy: SubmitRetryPolicy,
+    ) -> Result<SubmitOrderCreatedDto, Error> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        let mut order = self.with_client_order_id(order);
+        let client_order_id = match order.get_client_order_id() {
+            Some(client_order_id) => client_order_id.to_string(),
+            None => {
+                let client_order_id = Uuid::new_v4().to_string();
+                order = order.client_order_id(client_order_id.clone());
+                client_order_id
+            }
+        };
+        let dto = self.prepare_order(subaccount, order)?;
+
+        let mut attempt = 1;
+        loop {
+            let e = match self
+                .order()
+                .submit(OrderControllerSubmitParams {
+                    submit_order_dto: dto.clone(),
+                })
+                .await
+            {
+                Ok(created) => return Ok(created),
+                Err(e) => Error::from(e),
+            };
+            if !e.is_retryable() || attempt >= policy.max_attempts {
+                return Err(e);
+            }
+            warn!("Submitting order {client_order_id} failed (attempt {attempt}): {e}");
+            tokio::time::sleep(policy.lookup_delay).await;
+            // A rate limited submission was refused before reaching the matching engine.
+            if !e.is_rate_limited() {
+                match self
+                    .get_order_by_client_id(subaccount, &client_order_id)
+                    .await
+                {
+                    Ok(Some(order)) => {
+                        debug!("Order {client_order_id} landed despite the failure");
+                        return Ok(SubmitOrderCreatedDto {
+                            client_order_id: order.client_order_id,
+                            filled: order.filled,
+                            id: order.id,
+                            result: SubmitCreatedCode::Ok,
+                        });
+                    }
+                    Ok(None) => {}
+                    Err(lookup) => {
+                        error!("Failed to look up order {client_order_id}: {lookup}");
+                        return Err(e);
+                    }
+                }
+            }
+            attempt += 1;
+        }
+    }
+
+    /// The order with this client order id, if any.
+    pub async fn get_order_by_client_id(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        client_order_id: &str,
+    ) -> Result<Option<crate::models::OrderDto>, Error> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        let orders = self
+            .order()
+            .list_by_subaccount_id(OrderControllerListBySubaccountIdParams {
+                subaccount_id: subaccount.id.to_string(),
+                client_order_id: Some(client_order_id.to_string()),
+                ..Default::default()
+            })
+            .await?
+            .data;
+        Ok(orders
+            .into_iter()
+            .find(|order| order.client_order_id.as_deref() == Some(client_order_id)))
+    }
+
+    /// Submits the entry and exit legs of a bracket in order.
+    ///
+    /// If any leg is rejected, the legs which were already accepted are cancelled before the
+    /// error is returned.
+    pub async fn submit_bracket(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        bracket: BracketOrder,
+    ) -> Result<OrderGroup, Error> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        let group_id = bracket.get_group_id();
+        let mut group = OrderGroup {
+            group_id,
+            subaccount_id: subaccount.id,
+            orders: Vec::new(),
+        };
+        for (leg, order) in bracket.into_orders()? {
+            match self.submit_order(subaccount, order).await {
+                Ok(created) => group.orders.push((leg, created)),
+                Err(e) => {
+                    error!("Failed to submit {leg:?} leg of group {group_id}: {e}");
+                    if !group.orders.is_empty() {
+                        self.cancel_order_group(&group).await?;
+                    }
+                    return Err(e);
+                }
+            }
         }
+        Ok(group)
+    }
+
+    pub async fn get_order_group(
+        &self,
+        group: &OrderGroup,
+    ) -> Result<Vec<crate::models::OrderDto>, Error> {
+        let entry_id = group.entry_id().ok_or_else(|| {
+            Error::Validation(format!("Order group {} has no entry order", group.group_id))
+        })?;
+        let orders = self
+            .order()
+            .list_group_by_order_id(OrderControllerListGroupByOrderIdParams {
+                id: entry_id.to_string(),
+                ..Default::default()
+            })
+            .await?
+            .data;
+        Ok(orders)
+    }
+
+    pub async fn cancel_order_group(
+        &self,
+        group: &OrderGroup,
+    ) -> Result<Vec<CancelOrderResultDto>, Error> {
+        let order_ids = group.order_ids().iter().map(|id| id.to_string()).collect();
+        self.cancel_orders(group.subaccount_id, order_ids).await
     }
 
     pub async fn cancel_orders(
         &self,
+        subaccount: impl Into<SubaccountSelector>,
         order_ids: Vec<String>,
-    ) -> Result<Vec<CancelOrderResultDto>, Box<dyn std::error::Error>> {
-        let subaccount = &self.subaccounts[0];
-        let ctx = SigningContext::new(&self.wallet, &self.subaccounts[0]);
-        let message = CancelOrder {
-            sender: self.address.clone().parse()?,
-            subaccount: hex_to_bytes32(&subaccount.name.clone())?,
-            nonce: ctx.nonce, // increment nonce for the cancel order
-        };
+    ) -> Result<Vec<CancelOrderResultDto>, Error> {
+        let cancel_order_dto = self.prepare_cancel(subaccount, order_ids)?;
+        let response = self
+            .order()
+            .cancel(OrderControllerCancelParams { cancel_order_dto })
+            .await?;
+        Ok(response.data)
+    }
 
-        let signature = message.sign(self.env, &self.wallet)?;
-        let ids: Vec<Uuid> = order_ids
+    /// Cancels the working orders of `subaccount` selected by `filter`, including stop orders
+    /// which have not triggered yet.
+    pub async fn cancel_where(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        filter: CancelFilter,
+    ) -> Result<CancelReport, Error> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        let product_id = match &filter.product {
+            Some(ticker) => Some(
+                self.product_hashmap
+                    .get(ticker)
+                    .ok_or_else(|| Error::Validation(format!("Ticker {ticker} not found")))?
+                    .id,
+            ),
+            None => None,
+        };
+        let orders = list_working_orders(
+            &self.config,
+            OrderControllerListBySubaccountIdParams {
+                subaccount_id: subaccount.id.to_string(),
+                product_ids: product_id.map(|id| vec![id]),
+                side: filter.side,
+                ..Default::default()
+            },
+        )
+        .await?;
+        let order_ids: Vec<Uuid> = orders
             .iter()
-            .map(|id| Uuid::parse_str(id).unwrap())
+            .filter(|order| filter.matches(order, product_id))
+            .map(|order| order.id)
             .collect();
-        let cancel_result = self
+        debug!(
+            "Cancelling {} orders of {} matching {filter:?}",
+            order_ids.len(),
+            subaccount.id
+        );
+        Ok(self
+            .send_cancel_batches(subaccount, CancelBatch::split(&order_ids, &[]))
+            .await)
+    }
+
+    pub async fn cancel_all(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+    ) -> Result<CancelReport, Error> {
+        self.cancel_where(subaccount, CancelFilter::all()).await
+    }
+
+    pub async fn cancel_by_product(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        ticker: &str,
+    ) -> Result<CancelReport, Error> {
+        self.cancel_where(subaccount, CancelFilter::all().product(ticker))
+            .await
+    }
+
+    pub async fn cancel_by_side(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        side: OrderSide,
+    ) -> Result<CancelReport, Error> {
+        self.cancel_where(subaccount, CancelFilter::all().side(side))
+            .await
+    }
+
+    /// Cancels orders by id in as many requests as needed.
+    pub async fn cancel_order_ids(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        order_ids: &[Uuid],
+    ) -> Result<CancelReport, Error> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        Ok(self
+            .send_cancel_batches(subaccount, CancelBatch::split(order_ids, &[]))
+            .await)
+    }
+
+    /// Cancels orders by client order id in as many requests as needed. Malformed ids are not
+    /// sent; they are listed in [`CancelReport::invalid_client_order_ids`].
+    pub async fn cancel_by_client_order_ids(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        client_order_ids: &[String],
+    ) -> Result<CancelReport, Error> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        let (valid, invalid): (Vec<String>, Vec<String>) = client_order_ids
+            .iter()
+            .cloned()
+            .partition(|id| validate_client_order_id(id).is_ok());
+        let mut report = self
+            .send_cancel_batches(subaccount, CancelBatch::split(&[], &valid))
+            .await;
+        report.invalid_client_order_ids = invalid;
+        Ok(report)
+    }
+
+    /// Sends each batch with its own nonce, resending a batch once if the server saw its nonce
+    /// already. A batch which fails is recorded and does not stop the others.
+    async fn send_cancel_batches(
+        &self,
+        subaccount: &SubaccountDto,
+        batches: Vec<CancelBatch>,
+    ) -> CancelReport {
+        let mut report = CancelReport::default();
+        for batch in batches {
+            let mut attempt = self.send_cancel_batch(subaccount, &batch).await;
+            let reused_nonce = attempt.as_ref().is_ok_and(|results| {
+                results
+                    .iter()
+                    .any(|r| r.result == CancelOrderResultCode::NonceAlreadyUsed)
+            });
+            if reused_nonce {
+                warn!("Nonce of a cancel request was already used, resending it");
+                attempt = self.send_cancel_batch(subaccount, &batch).await;
+            }
+            match attempt {
+                Ok(results) => report.results.extend(results),
+                Err(error) => {
+                    error!("Failed to cancel {} orders: {error}", batch.len());
+                    report.failed.push(FailedCancelBatch { batch, error });
+                }
+            }
+        }
+        report
+    }
+
+    async fn send_cancel_batch(
+        &self,
+        subaccount: &SubaccountDto,
+        batch: &CancelBatch,
+    ) -> Result<Vec<CancelOrderResultDto>, Error> {
+        let cancel_order_dto = self.sign_cancel(subaccount, batch.clone())?;
+        let response = self
+            .order()
+            .cancel(OrderControllerCancelParams { cancel_order_dto })
+            .await?;
+        Ok(response.data)
+    }
+
+    /// Replaces a working order with `replacement`, cancelling the original before or after
+    /// placing it as set by [`ReplaceOptions::mode`].
+    ///
+    /// `tracker` must follow `subaccount`; its updates confirm that the original closed and how
+    /// much of it filled in the meantime. The REST API is asked instead if no update arrives
+    /// within [`ReplaceOptions::confirm_timeout`].
+    pub async fn replace_order(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        tracker: &OrderTracker,
+        order_id: Uuid,
+        replacement: OrderBuilder,
+        options: ReplaceOptions,
+    ) -> Result<ReplaceReport, ReplaceError> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        let original = self.working_order(subaccount, order_id).await?;
+        self.replace_working_order(subaccount, tracker, original, replacement, options)
+            .await
+    }
+
+    /// Requotes a working limit order at a new price and quantity, keeping its side, flags and
+    /// time in force.
+    pub async fn amend_order(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        tracker: &OrderTracker,
+        order_id: Uuid,
+        price: Decimal,
+        quantity: Decimal,
+        options: ReplaceOptions,
+    ) -> Result<ReplaceReport, ReplaceError> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        let original = self.working_order(subaccount, order_id).await?;
+        if original.r#type != OrderDtoOrderType::Limit || original.stop_type.is_some() {
+            return Err(Error::Validation(format!(
+                "Only limit orders can be amended, {order_id} is a {} order",
+                original.r#type
+            ))
+            .into());
+        }
+        let product = self
+            .product_id_hashmap
+            .get(&original.product_id)
+            .ok_or_else(|| {
+                Error::Validation(format!("Product {} not found", original.product_id))
+            })?;
+        let mut replacement =
+            OrderBuilder::limit(&product.display_ticker, original.side, quantity, price)
+                .reduce_only(original.reduce_only)
+                .post_only(original.post_only.unwrap_or_default());
+        if let Some(time_in_force) = original.time_in_force {
+            replacement = replacement.time_in_force(time_in_force);
+        }
+        self.replace_working_order(subaccount, tracker, original, replacement, options)
+            .await
+    }
+
+    async fn working_order(
+        &self,
+        subaccount: &SubaccountDto,
+        order_id: Uuid,
+    ) -> Result<crate::models::OrderDto, ReplaceError> {
+        let order = self
             .order()
-            .cancel(OrderControllerCancelParams {
-                cancel_order_dto: CancelOrderDto {
-                    data: CancelOrderDtoData {
-                        subaccount: subaccount.name.clone(),
-                        sender: self.address.to_string(),
-                        nonce: ctx.nonce.to_string(),
-                        order_ids: ids.into(),
-                        ..Default::default()
-                    },
-                    signature: "0x".to_string() + &hex::encode(signature.to_vec()),
-                },
+            .get_by_id(OrderControllerGetByIdParams {
+                id: order_id.to_string(),
             })
-            .await;
-        match cancel_result {
-            Err(e) => Err(Box::new(e)),
-            Ok(result) => Ok(result.data),
+            .await
+            .map_err(Error::from)?;
+        if order.subaccount_id != subaccount.id {
+            return Err(Error::Validation(format!(
+                "Order {order_id} does not belong to subaccount {}",
+                subaccount.id
+            ))
+            .into());
+        }
+        if is_terminal(order.status) {
+            return Err(ReplaceError::NotWorking {
+                id: order_id,
+                status: order.status,
+            });
         }
+        Ok(order)
     }
+
+    async fn replace_working_order(
+        &self,
+        subaccount: &SubaccountDto,
+        tracker: &OrderTracker,
+        original: crate::models::OrderDto,
+        replacement: OrderBuilder,
+        options: ReplaceOptions,
+    ) -> Result<ReplaceReport, ReplaceError> {
+        let id = original.id;
+        let followed = tracker.get(&id).unwrap_or_else(|| {
+            tracker.track(&SubmitOrderCreatedDto {
+                client_order_id: original.client_order_id.clone(),
+                filled: original.filled,
+                id,
+                result: SubmitCreatedCode::Ok,
+            })
+        });
+        match options.mode {
+            ReplaceMode::CancelThenPlace => {
+                let mut report = self
+                    .close_replaced(subaccount, &followed, &original, options.confirm_timeout)
+                    .await?;
+                if report.cancel_result != CancelOrderResultCode::Ok
+                    || report.original_status != OrderStatus::Canceled
+                {
+                    debug!(
+                        "Order {id} ended as {} before it was cancelled, not replacing it",
+                        report.original_status
+                    );
+                    return Ok(report);
+                }
+                let replacement =
+                    match (replacement.get_client_order_id(), original.client_order_id) {
+                        (None, Some(client_order_id)) => {
+                            replacement.client_order_id(client_order_id)
+                        }
+                        _ => replacement,
+                    };
+                let created =
+                    self.submit_order(subaccount, replacement)
+                        .await
+                        .map_err(|source| ReplaceError::PlaceFailed {
+                            id,
+                            filled_during_replace: report.filled_during_replace,
+                            source,
+                        })?;
+                report.replacement = Some(tracker.track(&created));
+                Ok(report)
+            }
+            ReplaceMode::PlaceThenCancel => {
+                let created = self.submit_order(subaccount, replacement).await?;
+                let placed = tracker.track(&created);
+                let mut report = self
+                    .close_replaced(subaccount, &followed, &original, options.confirm_timeout)
+                    .await
+                    .map_err(|e| e.with_replacement(placed.clone()))?;
+                report.replacement = Some(placed);
+                Ok(report)
+            }
+        }
+    }
+
+    /// Cancels the original of a replace and waits until it is closed.
+    async fn close_replaced(
+        &self,
+        subaccount: &SubaccountDto,
+        followed: &ManagedOrder,
+        original: &crate::models::OrderDto,
+        confirm_timeout: Duration,
+    ) -> Result<ReplaceReport, ReplaceError> {
+        let id = original.id;
+        let mut cancelled = self.cancel_order_ids(subaccount, &[id]).await?;
+        if let Some(failed) = cancelled.failed.pop() {
+            return Err(failed.error.into());
+        }
+        let code = cancelled
+            .results
+            .iter()
+            .find(|r| r.id == id)
+            .map_or(CancelOrderResultCode::Unknown, |r| r.result);
+        if !matches!(
+            code,
+            CancelOrderResultCode::Ok
+                | CancelOrderResultCode::AlreadyCanceled
+                | CancelOrderResultCode::AlreadyFilled
+                | CancelOrderResultCode::AlreadyExpired
+        ) {
+            return Err(ReplaceError::CancelRejected {
+                id,
+                code,
+                replacement: None,
+            });
+        }
+        let (status, filled) = match followed.wait_terminal(confirm_timeout).await {
+            Ok(state) => (state.status(), state.filled()),
+            Err(wait) => {
+                warn!("No update closed order {id} ({wait}), asking the API");
+                let order = self
+                    .order()
+                    .get_by_id(OrderControllerGetByIdParams { id: id.to_string() })
+                    .await
+                    .map_err(Error::from)?;
+                if !is_terminal(order.status) {
+                    return Err(ReplaceError::Unconfirmed {
+                        id,
+                        source: wait,
+                        replacement: None,
+                    });
+                }
+                (order.status, order.filled)
+            }
+        };
+        Ok(ReplaceReport {
+            original_id: id,
+            cancel_result: code,
+            original_status: status,
+            filled_during_replace: (filled - original.filled).max(Decimal::ZERO),
+            replacement: None,
+        })
+    }
+
+    /// Every working order of the subaccount, i.e. new, pending or partially filled.
     pub async fn get_open_orders(
         &self,
-    ) -> Result<Vec<crate::models::OrderDto>, Box<dyn std::error::Error>> {
-        let orders = self
-            .order()
-            .list_by_subaccount_id(OrderControllerListBySubaccountIdParams {
-                subaccount_id: self.subaccounts[0].id.clone().to_string(),
+        subaccount: impl Into<SubaccountSelector>,
+    ) -> Result<Vec<crate::models::OrderDto>, Error> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        list_working_orders(
+            &self.config,
+            OrderControllerListBySubaccountIdParams {
+                subaccount_id: subaccount.id.to_string(),
                 ..Default::default()
+            },
+        )
+        .await
+    }
+
+    pub async fn get_stop_orders(
+        &self,
+        subaccount: impl Into<SubaccountSelector>,
+        stop_types: Vec<StopType>,
+    ) -> Result<Vec<crate::models::OrderDto>, Error> {
+        let subaccount = self.get_subaccount(subaccount)?;
+        list_working_orders(
+            &self.config,
+            OrderControllerListBySubaccountIdParams {
+                subaccount_id: subaccount.id.to_string(),
+                stop_types: Some(stop_types),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+}
+
+/// Every page of the working orders matching `params`. isWorking leaves out PENDING orders,
+/// e.g. stops which have not triggered, so those are listed separately; an order which moved
+/// from pending to working in between is kept once.
+pub(crate) async fn list_working_orders(
+    config: &Configuration,
+    params: OrderControllerListBySubaccountIdParams,
+) -> Result<Vec<crate::models::OrderDto>, Error> {
+    let mut orders: Vec<crate::models::OrderDto> = Vec::new();
+    let mut seen = HashSet::new();
+    for pending in [false, true] {
+        let params = OrderControllerListBySubaccountIdParams {
+            is_working: Some(!pending),
+            is_pending: Some(pending),
+            ..params.clone()
+        };
+        let page: Vec<crate::models::OrderDto> =
+            paginate(params, PaginationOptions::default(), |params| {
+                order_controller_list_by_subaccount_id(config, params)
             })
-            .await?
-            .data;
-        let open_orders = orders
-            .into_iter()
-            .filter(|order| order.status == OrderStatus::New);
-        Ok(open_orders.collect())
+            .try_collect()
+            .await?;
+        orders.extend(
+            page.into_iter()
+                .filter(|order| is_working(order.status) && seen.insert(order.id)),
+        );
     }
+    Ok(orders)
 }
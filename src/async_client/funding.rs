@@ -8,8 +8,12 @@ use crate::{
         },
         Error,
     },
-    models::{PageOfFundingDtos, PageOfProjectedFundingDtos},
+    models::{FundingDto, PageOfFundingDtos, PageOfProjectedFundingDtos},
 };
+
+use crate::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use futures_util::{Stream, TryStreamExt};
+
 pub struct FundingClient<'a> {
     pub config: &'a Configuration,
 }
@@ -22,6 +26,26 @@ impl<'a> FundingClient<'a> {
         funding_controller_list_by_product_id(self.config, params).await
     }
 
+    pub fn list_by_product_id_stream(
+        &self,
+        params: FundingControllerListByProductIdParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<FundingDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            funding_controller_list_by_product_id(self.config, params)
+        })
+    }
+
+    pub async fn list_by_product_id_all(
+        &self,
+        params: FundingControllerListByProductIdParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<FundingDto>, crate::Error> {
+        self.list_by_product_id_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_projected_rates(
         &self,
         params: FundingControllerListProjectedRatesParams,
@@ -29,3 +53,17 @@ impl<'a> FundingClient<'a> {
         funding_controller_list_projected_rates(self.config, params).await
     }
 }
+
+impl CursorParams for FundingControllerListByProductIdParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfFundingDtos {
+    type Item = FundingDto;
+
+    fn into_parts(self) -> (Vec<FundingDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
@@ -21,6 +21,10 @@ use crate::{
     },
     models::{AccountSignerQuotaDto, PageOfSignersDto, SignerDto},
 };
+
+use crate::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use futures_util::{Stream, TryStreamExt};
+
 pub struct LinkedSignerClient<'a> {
     pub config: &'a Configuration,
 }
@@ -68,6 +72,26 @@ impl<'a> LinkedSignerClient<'a> {
         linked_signer_controller_list_by_subaccount_id(self.config, params).await
     }
 
+    pub fn list_by_subaccount_id_stream(
+        &self,
+        params: LinkedSignerControllerListBySubaccountIdParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<SignerDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            linked_signer_controller_list_by_subaccount_id(self.config, params)
+        })
+    }
+
+    pub async fn list_by_subaccount_id_all(
+        &self,
+        params: LinkedSignerControllerListBySubaccountIdParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<SignerDto>, crate::Error> {
+        self.list_by_subaccount_id_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn refresh_signer(
         &self,
         params: LinkedSignerControllerRefreshSignerParams,
@@ -82,3 +106,17 @@ impl<'a> LinkedSignerClient<'a> {
         linked_signer_controller_revoke_signer(self.config, params).await
     }
 }
+
+impl CursorParams for LinkedSignerControllerListBySubaccountIdParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfSignersDto {
+    type Item = SignerDto;
+
+    fn into_parts(self) -> (Vec<SignerDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
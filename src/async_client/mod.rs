@@ -1,3 +1,4 @@
+pub mod builder;
 pub mod client;
 mod funding;
 mod linked_signer;
@@ -8,6 +9,7 @@ mod position;
 mod product;
 mod referral;
 mod rpc;
+pub mod session;
 mod subaccount;
 mod subaccount_archive;
 mod time;
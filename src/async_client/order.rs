@@ -17,10 +17,14 @@ use crate::{
         Error,
     },
     models::{
-        DryRunOrderCreatedDto, ListOfCancelOrderResultDtos, OrderDto, PageOfOrderDtos,
-        PageOfOrderFillDtos, PageOfTradeDtos, SubmitOrderCreatedDto,
+        DryRunOrderCreatedDto, ListOfCancelOrderResultDtos, OrderDto, OrderFillDto,
+        PageOfOrderDtos, PageOfOrderFillDtos, PageOfTradeDtos, SubmitOrderCreatedDto, TradeDto,
     },
 };
+
+use crate::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use futures_util::{Stream, TryStreamExt};
+
 pub struct OrderClient<'a> {
     pub config: &'a Configuration,
 }
@@ -54,6 +58,26 @@ impl<'a> OrderClient<'a> {
         order_controller_list_by_subaccount_id(self.config, params).await
     }
 
+    pub fn list_by_subaccount_id_stream(
+        &self,
+        params: OrderControllerListBySubaccountIdParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<OrderDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            order_controller_list_by_subaccount_id(self.config, params)
+        })
+    }
+
+    pub async fn list_by_subaccount_id_all(
+        &self,
+        params: OrderControllerListBySubaccountIdParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<OrderDto>, crate::Error> {
+        self.list_by_subaccount_id_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_fills_by_subaccount_id(
         &self,
         params: OrderControllerListFillsBySubaccountIdParams,
@@ -61,6 +85,26 @@ impl<'a> OrderClient<'a> {
         order_controller_list_fills_by_subaccount_id(self.config, params).await
     }
 
+    pub fn list_fills_by_subaccount_id_stream(
+        &self,
+        params: OrderControllerListFillsBySubaccountIdParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<OrderFillDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            order_controller_list_fills_by_subaccount_id(self.config, params)
+        })
+    }
+
+    pub async fn list_fills_by_subaccount_id_all(
+        &self,
+        params: OrderControllerListFillsBySubaccountIdParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<OrderFillDto>, crate::Error> {
+        self.list_fills_by_subaccount_id_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_group_by_order_id(
         &self,
         params: OrderControllerListGroupByOrderIdParams,
@@ -68,6 +112,26 @@ impl<'a> OrderClient<'a> {
         order_controller_list_group_by_order_id(self.config, params).await
     }
 
+    pub fn list_group_by_order_id_stream(
+        &self,
+        params: OrderControllerListGroupByOrderIdParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<OrderDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            order_controller_list_group_by_order_id(self.config, params)
+        })
+    }
+
+    pub async fn list_group_by_order_id_all(
+        &self,
+        params: OrderControllerListGroupByOrderIdParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<OrderDto>, crate::Error> {
+        self.list_group_by_order_id_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_trades(
         &self,
         params: OrderControllerListTradesParams,
@@ -75,6 +139,24 @@ impl<'a> OrderClient<'a> {
         order_controller_list_trades(self.config, params).await
     }
 
+    pub fn list_trades_stream(
+        &self,
+        params: OrderControllerListTradesParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<TradeDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            order_controller_list_trades(self.config, params)
+        })
+    }
+
+    pub async fn list_trades_all(
+        &self,
+        params: OrderControllerListTradesParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<TradeDto>, crate::Error> {
+        self.list_trades_stream(params, options).try_collect().await
+    }
+
     pub async fn submit(
         &self,
         params: OrderControllerSubmitParams,
@@ -82,3 +164,51 @@ impl<'a> OrderClient<'a> {
         order_controller_submit(self.config, params).await
     }
 }
+
+impl CursorParams for OrderControllerListBySubaccountIdParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfOrderDtos {
+    type Item = OrderDto;
+
+    fn into_parts(self) -> (Vec<OrderDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
+
+impl CursorParams for OrderControllerListFillsBySubaccountIdParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfOrderFillDtos {
+    type Item = OrderFillDto;
+
+    fn into_parts(self) -> (Vec<OrderFillDto>, bool, Option<String>) {
+        (self.data, self.has_next.unwrap_or(false), self.next_cursor)
+    }
+}
+
+impl CursorParams for OrderControllerListGroupByOrderIdParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl CursorParams for OrderControllerListTradesParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfTradeDtos {
+    type Item = TradeDto;
+
+    fn into_parts(self) -> (Vec<TradeDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
@@ -18,8 +18,13 @@ use crate::{
     },
     models::{
         PageOfPositionDtos, PageOfPositionFillDtos, PageOfPositionLiquidationsDto, PositionDto,
+        PositionFillDto, PositionLiquidationDto,
     },
 };
+
+use crate::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use futures_util::{Stream, TryStreamExt};
+
 pub struct PositionClient<'a> {
     pub config: &'a Configuration,
 }
@@ -46,6 +51,26 @@ impl<'a> PositionClient<'a> {
         position_controller_list_by_subaccount_id(self.config, params).await
     }
 
+    pub fn list_by_subaccount_id_stream(
+        &self,
+        params: PositionControllerListBySubaccountIdParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<PositionDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            position_controller_list_by_subaccount_id(self.config, params)
+        })
+    }
+
+    pub async fn list_by_subaccount_id_all(
+        &self,
+        params: PositionControllerListBySubaccountIdParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<PositionDto>, crate::Error> {
+        self.list_by_subaccount_id_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_fills_by_position_id(
         &self,
         params: PositionControllerListFillsByPositionIdParams,
@@ -53,6 +78,26 @@ impl<'a> PositionClient<'a> {
         position_controller_list_fills_by_position_id(self.config, params).await
     }
 
+    pub fn list_fills_by_position_id_stream(
+        &self,
+        params: PositionControllerListFillsByPositionIdParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<PositionFillDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            position_controller_list_fills_by_position_id(self.config, params)
+        })
+    }
+
+    pub async fn list_fills_by_position_id_all(
+        &self,
+        params: PositionControllerListFillsByPositionIdParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<PositionFillDto>, crate::Error> {
+        self.list_fills_by_position_id_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_liquidations_by_subaccount_id(
         &self,
         params: PositionControllerListLiquidationsBySubaccountIdParams,
@@ -62,4 +107,66 @@ impl<'a> PositionClient<'a> {
     > {
         position_controller_list_liquidations_by_subaccount_id(self.config, params).await
     }
+
+    pub fn list_liquidations_by_subaccount_id_stream(
+        &self,
+        params: PositionControllerListLiquidationsBySubaccountIdParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<PositionLiquidationDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            position_controller_list_liquidations_by_subaccount_id(self.config, params)
+        })
+    }
+
+    pub async fn list_liquidations_by_subaccount_id_all(
+        &self,
+        params: PositionControllerListLiquidationsBySubaccountIdParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<PositionLiquidationDto>, crate::Error> {
+        self.list_liquidations_by_subaccount_id_stream(params, options)
+            .try_collect()
+            .await
+    }
+}
+
+impl CursorParams for PositionControllerListBySubaccountIdParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfPositionDtos {
+    type Item = PositionDto;
+
+    fn into_parts(self) -> (Vec<PositionDto>, bool, Option<String>) {
+        (self.data, self.has_next.unwrap_or(false), self.next_cursor)
+    }
+}
+
+impl CursorParams for PositionControllerListFillsByPositionIdParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfPositionFillDtos {
+    type Item = PositionFillDto;
+
+    fn into_parts(self) -> (Vec<PositionFillDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
+
+impl CursorParams for PositionControllerListLiquidationsBySubaccountIdParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfPositionLiquidationsDto {
+    type Item = PositionLiquidationDto;
+
+    fn into_parts(self) -> (Vec<PositionLiquidationDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
 }
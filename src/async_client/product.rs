@@ -13,6 +13,10 @@ use crate::{
     },
     models::{ListOfMarketPriceDtos, MarketLiquidityDto, PageOfProductDtos, ProductDto},
 };
+
+use crate::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use futures_util::{Stream, TryStreamExt};
+
 pub struct ProductClient<'a> {
     pub config: &'a Configuration,
 }
@@ -45,4 +49,36 @@ impl<'a> ProductClient<'a> {
     ) -> Result<PageOfProductDtos, Error<ProductControllerListError>> {
         product_controller_list(self.config, params).await
     }
+
+    pub fn list_stream(
+        &self,
+        params: ProductControllerListParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<ProductDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            product_controller_list(self.config, params)
+        })
+    }
+
+    pub async fn list_all(
+        &self,
+        params: ProductControllerListParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<ProductDto>, crate::Error> {
+        self.list_stream(params, options).try_collect().await
+    }
+}
+
+impl CursorParams for ProductControllerListParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfProductDtos {
+    type Item = ProductDto;
+
+    fn into_parts(self) -> (Vec<ProductDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
 }
@@ -15,6 +15,10 @@ use crate::{
     },
     models::{PageOfReferralDtos, ReferralCodeUsageDto, ReferralDto},
 };
+
+use crate::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use futures_util::{Stream, TryStreamExt};
+
 pub struct ReferralClient<'a> {
     pub config: &'a Configuration,
 }
@@ -54,4 +58,38 @@ impl<'a> ReferralClient<'a> {
     ) -> Result<PageOfReferralDtos, Error<ReferralControllerListReferralsError>> {
         referral_controller_list_referrals(self.config, params).await
     }
+
+    pub fn list_referrals_stream(
+        &self,
+        params: ReferralControllerListReferralsParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<ReferralDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            referral_controller_list_referrals(self.config, params)
+        })
+    }
+
+    pub async fn list_referrals_all(
+        &self,
+        params: ReferralControllerListReferralsParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<ReferralDto>, crate::Error> {
+        self.list_referrals_stream(params, options)
+            .try_collect()
+            .await
+    }
+}
+
+impl CursorParams for ReferralControllerListReferralsParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfReferralDtos {
+    type Item = ReferralDto;
+
+    fn into_parts(self) -> (Vec<ReferralDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
 }
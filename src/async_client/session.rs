@@ -0,0 +1,206 @@
+use ethers::utils::hex;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    async_client::client::{HttpClient, SubmitRetryPolicy},
+    cancel::{CancelFilter, CancelReport},
+    error::Error,
+    managed_order::{ManagedOrder, OrderTracker},
+    models::{
+        CancelOrderResultDto, OrderDto, OrderSide, StopType, SubaccountDto, SubmitOrderCreatedDto,
+    },
+    order_builder::OrderBuilder,
+    order_group::{BracketOrder, OrderGroup},
+    replace_order::{ReplaceError, ReplaceOptions, ReplaceReport},
+};
+
+/// Identifies one of the subaccounts loaded into [`HttpClient::subaccounts`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubaccountSelector {
+    /// The first subaccount registered to the account.
+    First,
+    /// Subaccount name, either plain (e.g. `primary`) or bytes32 hex encoded.
+    Name(String),
+    Id(Uuid),
+}
+
+impl SubaccountSelector {
+    pub fn matches(&self, subaccount: &SubaccountDto) -> bool {
+        match self {
+            SubaccountSelector::First => true,
+            SubaccountSelector::Id(id) => subaccount.id == *id,
+            SubaccountSelector::Name(name) => {
+                subaccount.name.eq_ignore_ascii_case(name)
+                    || subaccount
+                        .name
+                        .eq_ignore_ascii_case(&encode_subaccount_name(name))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SubaccountSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubaccountSelector::First => write!(f, "first subaccount"),
+            SubaccountSelector::Name(name) => write!(f, "subaccount named {name}"),
+            SubaccountSelector::Id(id) => write!(f, "subaccount {id}"),
+        }
+    }
+}
+
+impl From<&str> for SubaccountSelector {
+    fn from(name: &str) -> Self {
+        SubaccountSelector::Name(name.to_string())
+    }
+}
+
+impl From<String> for SubaccountSelector {
+    fn from(name: String) -> Self {
+        SubaccountSelector::Name(name)
+    }
+}
+
+impl From<Uuid> for SubaccountSelector {
+    fn from(id: Uuid) -> Self {
+        SubaccountSelector::Id(id)
+    }
+}
+
+impl From<&SubaccountDto> for SubaccountSelector {
+    fn from(subaccount: &SubaccountDto) -> Self {
+        SubaccountSelector::Id(subaccount.id)
+    }
+}
+
+/// Encodes a plain subaccount name as zero padded bytes32 hex, e.g. `primary` -> `0x7072...00`.
+pub fn encode_subaccount_name(name: &str) -> String {
+    let mut bytes = [0u8; 32];
+    let n = name.len().min(32);
+    bytes[..n].copy_from_slice(&name.as_bytes()[..n]);
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Trading methods bound to a single subaccount, for strategies which own one subaccount.
+pub struct SubaccountSession<'a> {
+    pub client: &'a HttpClient,
+    pub subaccount: &'a SubaccountDto,
+}
+
+impl<'a> SubaccountSession<'a> {
+    pub fn id(&self) -> Uuid {
+        self.subaccount.id
+    }
+
+    pub async fn submit_order(&self, order: OrderBuilder) -> Result<SubmitOrderCreatedDto, Error> {
+        self.client.submit_order(self.subaccount, order).await
+    }
+
+    /// Like [`SubaccountSession::submit_order`], returning a handle following the order until
+    /// it is filled or ends otherwise.
+    pub async fn submit_order_tracked(
+        &self,
+        tracker: &OrderTracker,
+        order: OrderBuilder,
+    ) -> Result<ManagedOrder, Error> {
+        let created = self.submit_order(order).await?;
+        Ok(tracker.track(&created))
+    }
+
+    pub async fn replace_order(
+        &self,
+        tracker: &OrderTracker,
+        order_id: Uuid,
+        replacement: OrderBuilder,
+        options: ReplaceOptions,
+    ) -> Result<ReplaceReport, ReplaceError> {
+        self.client
+            .replace_order(self.subaccount, tracker, order_id, replacement, options)
+            .await
+    }
+
+    pub async fn amend_order(
+        &self,
+        tracker: &OrderTracker,
+        order_id: Uuid,
+        price: Decimal,
+        quantity: Decimal,
+        options: ReplaceOptions,
+    ) -> Result<ReplaceReport, ReplaceError> {
+        self.client
+            .amend_order(self.subaccount, tracker, order_id, price, quantity, options)
+            .await
+    }
+
+    pub async fn submit_order_with_retry(
+        &self,
+        order: OrderBuilder,
+        policy: SubmitRetryPolicy,
+    ) -> Result<SubmitOrderCreatedDto, Error> {
+        self.client
+            .submit_order_with_retry(self.subaccount, order, policy)
+            .await
+    }
+
+    pub async fn get_order_by_client_id(
+        &self,
+        client_order_id: &str,
+    ) -> Result<Option<OrderDto>, Error> {
+        self.client
+            .get_order_by_client_id(self.subaccount, client_order_id)
+            .await
+    }
+
+    pub async fn submit_bracket(&self, bracket: BracketOrder) -> Result<OrderGroup, Error> {
+        self.client.submit_bracket(self.subaccount, bracket).await
+    }
+
+    pub async fn cancel_orders(
+        &self,
+        order_ids: Vec<String>,
+    ) -> Result<Vec<CancelOrderResultDto>, Error> {
+        self.client.cancel_orders(self.subaccount, order_ids).await
+    }
+
+    pub async fn cancel_where(&self, filter: CancelFilter) -> Result<CancelReport, Error> {
+        self.client.cancel_where(self.subaccount, filter).await
+    }
+
+    pub async fn cancel_all(&self) -> Result<CancelReport, Error> {
+        self.client.cancel_all(self.subaccount).await
+    }
+
+    pub async fn cancel_by_product(&self, ticker: &str) -> Result<CancelReport, Error> {
+        self.client.cancel_by_product(self.subaccount, ticker).await
+    }
+
+    pub async fn cancel_by_side(&self, side: OrderSide) -> Result<CancelReport, Error> {
+        self.client.cancel_by_side(self.subaccount, side).await
+    }
+
+    pub async fn cancel_order_ids(&self, order_ids: &[Uuid]) -> Result<CancelReport, Error> {
+        self.client
+            .cancel_order_ids(self.subaccount, order_ids)
+            .await
+    }
+
+    pub async fn cancel_by_client_order_ids(
+        &self,
+        client_order_ids: &[String],
+    ) -> Result<CancelReport, Error> {
+        self.client
+            .cancel_by_client_order_ids(self.subaccount, client_order_ids)
+            .await
+    }
+
+    pub async fn get_open_orders(&self) -> Result<Vec<OrderDto>, Error> {
+        self.client.get_open_orders(self.subaccount).await
+    }
+
+    pub async fn get_stop_orders(&self, stop_types: Vec<StopType>) -> Result<Vec<OrderDto>, Error> {
+        self.client
+            .get_stop_orders(self.subaccount, stop_types)
+            .await
+    }
+}
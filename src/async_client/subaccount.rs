@@ -12,8 +12,14 @@ use crate::{
         },
         Error,
     },
-    models::{PageOfSubaccountBalanceDtos, PageOfSubaccountDtos, SubaccountDto},
+    models::{
+        PageOfSubaccountBalanceDtos, PageOfSubaccountDtos, SubaccountBalanceDto, SubaccountDto,
+    },
 };
+
+use crate::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use futures_util::{Stream, TryStreamExt};
+
 pub struct SubaccountClient<'a> {
     pub config: &'a Configuration,
 }
@@ -33,6 +39,24 @@ impl<'a> SubaccountClient<'a> {
         subaccount_controller_list(self.config, params).await
     }
 
+    pub fn list_stream(
+        &self,
+        params: SubaccountControllerListParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<SubaccountDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            subaccount_controller_list(self.config, params)
+        })
+    }
+
+    pub async fn list_all(
+        &self,
+        params: SubaccountControllerListParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<SubaccountDto>, crate::Error> {
+        self.list_stream(params, options).try_collect().await
+    }
+
     pub async fn list_by_account(
         &self,
         params: SubaccountControllerListByAccountParams,
@@ -40,6 +64,26 @@ impl<'a> SubaccountClient<'a> {
         subaccount_controller_list_by_account(self.config, params).await
     }
 
+    pub fn list_by_account_stream(
+        &self,
+        params: SubaccountControllerListByAccountParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<SubaccountDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            subaccount_controller_list_by_account(self.config, params)
+        })
+    }
+
+    pub async fn list_by_account_all(
+        &self,
+        params: SubaccountControllerListByAccountParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<SubaccountDto>, crate::Error> {
+        self.list_by_account_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_subaccount_balances(
         &self,
         params: SubaccountControllerListSubaccountBalancesParams,
@@ -47,4 +91,58 @@ impl<'a> SubaccountClient<'a> {
     {
         subaccount_controller_list_subaccount_balances(self.config, params).await
     }
+
+    pub fn list_subaccount_balances_stream(
+        &self,
+        params: SubaccountControllerListSubaccountBalancesParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<SubaccountBalanceDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            subaccount_controller_list_subaccount_balances(self.config, params)
+        })
+    }
+
+    pub async fn list_subaccount_balances_all(
+        &self,
+        params: SubaccountControllerListSubaccountBalancesParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<SubaccountBalanceDto>, crate::Error> {
+        self.list_subaccount_balances_stream(params, options)
+            .try_collect()
+            .await
+    }
+}
+
+impl CursorParams for SubaccountControllerListParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfSubaccountDtos {
+    type Item = SubaccountDto;
+
+    fn into_parts(self) -> (Vec<SubaccountDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
+
+impl CursorParams for SubaccountControllerListByAccountParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl CursorParams for SubaccountControllerListSubaccountBalancesParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfSubaccountBalanceDtos {
+    type Item = SubaccountBalanceDto;
+
+    fn into_parts(self) -> (Vec<SubaccountBalanceDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
 }
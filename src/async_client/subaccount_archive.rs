@@ -21,11 +21,16 @@ use crate::{
         Error,
     },
     archive_models::{
-        PageOfBalanceHistoryDtos, PageOfPositionFundingHistoryDtos,
+        BalanceHistoryDto, PageOfBalanceHistoryDtos, PageOfPositionFundingHistoryDtos,
         PageOfSubaccountVolumeHistoryDtos, PageOfUnrealizedPnlHistoryDtos,
-        TotalSubaccountVolumeDto,
+        PositionFundingHistoryDto, SubaccountVolumeHistoryDto, TotalSubaccountVolumeDto,
+        UnrealizedPnlHistoryDto,
     },
 };
+
+use crate::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use futures_util::{Stream, TryStreamExt};
+
 pub struct SubaccountArchiveClient<'a> {
     pub config: &'a Configuration,
 }
@@ -47,6 +52,26 @@ impl<'a> SubaccountArchiveClient<'a> {
         subaccount_archive_controller_list_balance_history(self.config, params).await
     }
 
+    pub fn list_balance_history_stream(
+        &self,
+        params: SubaccountArchiveControllerListBalanceHistoryParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<BalanceHistoryDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            subaccount_archive_controller_list_balance_history(self.config, params)
+        })
+    }
+
+    pub async fn list_balance_history_all(
+        &self,
+        params: SubaccountArchiveControllerListBalanceHistoryParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<BalanceHistoryDto>, crate::Error> {
+        self.list_balance_history_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_position_funding_history(
         &self,
         params: SubaccountArchiveControllerListPositionFundingHistoryParams,
@@ -57,6 +82,26 @@ impl<'a> SubaccountArchiveClient<'a> {
         subaccount_archive_controller_list_position_funding_history(self.config, params).await
     }
 
+    pub fn list_position_funding_history_stream(
+        &self,
+        params: SubaccountArchiveControllerListPositionFundingHistoryParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<PositionFundingHistoryDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            subaccount_archive_controller_list_position_funding_history(self.config, params)
+        })
+    }
+
+    pub async fn list_position_funding_history_all(
+        &self,
+        params: SubaccountArchiveControllerListPositionFundingHistoryParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<PositionFundingHistoryDto>, crate::Error> {
+        self.list_position_funding_history_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_unrealized_pnl_history(
         &self,
         params: SubaccountArchiveControllerListUnrealizedPnlHistoryParams,
@@ -67,6 +112,26 @@ impl<'a> SubaccountArchiveClient<'a> {
         subaccount_archive_controller_list_unrealized_pnl_history(self.config, params).await
     }
 
+    pub fn list_unrealized_pnl_history_stream(
+        &self,
+        params: SubaccountArchiveControllerListUnrealizedPnlHistoryParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<UnrealizedPnlHistoryDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            subaccount_archive_controller_list_unrealized_pnl_history(self.config, params)
+        })
+    }
+
+    pub async fn list_unrealized_pnl_history_all(
+        &self,
+        params: SubaccountArchiveControllerListUnrealizedPnlHistoryParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<UnrealizedPnlHistoryDto>, crate::Error> {
+        self.list_unrealized_pnl_history_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_volume_history(
         &self,
         params: SubaccountArchiveControllerListVolumeHistoryParams,
@@ -76,4 +141,80 @@ impl<'a> SubaccountArchiveClient<'a> {
     > {
         subaccount_archive_controller_list_volume_history(self.config, params).await
     }
+
+    pub fn list_volume_history_stream(
+        &self,
+        params: SubaccountArchiveControllerListVolumeHistoryParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<SubaccountVolumeHistoryDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            subaccount_archive_controller_list_volume_history(self.config, params)
+        })
+    }
+
+    pub async fn list_volume_history_all(
+        &self,
+        params: SubaccountArchiveControllerListVolumeHistoryParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<SubaccountVolumeHistoryDto>, crate::Error> {
+        self.list_volume_history_stream(params, options)
+            .try_collect()
+            .await
+    }
+}
+
+impl CursorParams for SubaccountArchiveControllerListBalanceHistoryParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfBalanceHistoryDtos {
+    type Item = BalanceHistoryDto;
+
+    fn into_parts(self) -> (Vec<BalanceHistoryDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
+
+impl CursorParams for SubaccountArchiveControllerListPositionFundingHistoryParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfPositionFundingHistoryDtos {
+    type Item = PositionFundingHistoryDto;
+
+    fn into_parts(self) -> (Vec<PositionFundingHistoryDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
+
+impl CursorParams for SubaccountArchiveControllerListUnrealizedPnlHistoryParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfUnrealizedPnlHistoryDtos {
+    type Item = UnrealizedPnlHistoryDto;
+
+    fn into_parts(self) -> (Vec<UnrealizedPnlHistoryDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
+
+impl CursorParams for SubaccountArchiveControllerListVolumeHistoryParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfSubaccountVolumeHistoryDtos {
+    type Item = SubaccountVolumeHistoryDto;
+
+    fn into_parts(self) -> (Vec<SubaccountVolumeHistoryDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
 }
@@ -12,8 +12,15 @@ use crate::{
         },
         Error,
     },
-    models::{PageOfTokensDtos, PageOfTransfersDtos, PageOfWithdrawDtos, TokenDto, WithdrawDto},
+    models::{
+        PageOfTokensDtos, PageOfTransfersDtos, PageOfWithdrawDtos, TokenDto, TransferDto,
+        WithdrawDto,
+    },
 };
+
+use crate::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use futures_util::{Stream, TryStreamExt};
+
 pub struct TokenClient<'a> {
     pub config: &'a Configuration,
 }
@@ -40,6 +47,24 @@ impl<'a> TokenClient<'a> {
         token_controller_list(self.config, params).await
     }
 
+    pub fn list_stream(
+        &self,
+        params: TokenControllerListParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<TokenDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            token_controller_list(self.config, params)
+        })
+    }
+
+    pub async fn list_all(
+        &self,
+        params: TokenControllerListParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<TokenDto>, crate::Error> {
+        self.list_stream(params, options).try_collect().await
+    }
+
     pub async fn list_transfers(
         &self,
         params: TokenControllerListTransfersParams,
@@ -47,10 +72,92 @@ impl<'a> TokenClient<'a> {
         token_controller_list_transfers(self.config, params).await
     }
 
+    pub fn list_transfers_stream(
+        &self,
+        params: TokenControllerListTransfersParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<TransferDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            token_controller_list_transfers(self.config, params)
+        })
+    }
+
+    pub async fn list_transfers_all(
+        &self,
+        params: TokenControllerListTransfersParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<TransferDto>, crate::Error> {
+        self.list_transfers_stream(params, options)
+            .try_collect()
+            .await
+    }
+
     pub async fn list_withdraws(
         &self,
         params: TokenControllerListWithdrawsParams,
     ) -> Result<PageOfWithdrawDtos, Error<TokenControllerListWithdrawsError>> {
         token_controller_list_withdraws(self.config, params).await
     }
+
+    pub fn list_withdraws_stream(
+        &self,
+        params: TokenControllerListWithdrawsParams,
+        options: PaginationOptions,
+    ) -> impl Stream<Item = Result<WithdrawDto, crate::Error>> + '_ {
+        paginate(params, options, move |params| {
+            token_controller_list_withdraws(self.config, params)
+        })
+    }
+
+    pub async fn list_withdraws_all(
+        &self,
+        params: TokenControllerListWithdrawsParams,
+        options: PaginationOptions,
+    ) -> Result<Vec<WithdrawDto>, crate::Error> {
+        self.list_withdraws_stream(params, options)
+            .try_collect()
+            .await
+    }
+}
+
+impl CursorParams for TokenControllerListParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfTokensDtos {
+    type Item = TokenDto;
+
+    fn into_parts(self) -> (Vec<TokenDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
+
+impl CursorParams for TokenControllerListTransfersParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfTransfersDtos {
+    type Item = TransferDto;
+
+    fn into_parts(self) -> (Vec<TransferDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
+
+impl CursorParams for TokenControllerListWithdrawsParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+impl Page for PageOfWithdrawDtos {
+    type Item = WithdrawDto;
+
+    fn into_parts(self) -> (Vec<WithdrawDto>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
 }
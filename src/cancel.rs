@@ -0,0 +1,146 @@
+use std::collections::{BTreeMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::{
+    error::Error,
+    models::{CancelOrderResultCode, CancelOrderResultDto, OrderDto, OrderSide, OrderStatus},
+};
+
+/// Most order ids, and separately client order ids, a single cancel request may carry.
+pub const MAX_CANCEL_BATCH: usize = 200;
+
+/// Whether an order with this status is on the book or still to be placed there.
+pub fn is_working(status: OrderStatus) -> bool {
+    matches!(
+        status,
+        OrderStatus::New | OrderStatus::Pending | OrderStatus::FilledPartial
+    )
+}
+
+/// Selects the working orders of a subaccount to cancel; the default selects all of them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CancelFilter {
+    /// Display ticker, e.g. `BTC-USD`.
+    pub product: Option<String>,
+    pub side: Option<OrderSide>,
+}
+
+impl CancelFilter {
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    pub fn product(mut self, ticker: impl Into<String>) -> Self {
+        self.product = Some(ticker.into());
+        self
+    }
+
+    pub fn side(mut self, side: OrderSide) -> Self {
+        self.side = Some(side);
+        self
+    }
+
+    /// Whether the order is working and matches the filter. `product_id` is the id of
+    /// [`CancelFilter::product`], if any.
+    pub fn matches(&self, order: &OrderDto, product_id: Option<Uuid>) -> bool {
+        is_working(order.status)
+            && product_id.is_none_or(|id| order.product_id == id)
+            && self.side.is_none_or(|side| order.side == side)
+    }
+}
+
+/// The orders of one cancel request.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CancelBatch {
+    pub order_ids: Vec<Uuid>,
+    pub client_order_ids: Vec<String>,
+}
+
+impl CancelBatch {
+    /// Splits the ids into batches the server accepts, dropping repeated ids.
+    pub fn split(order_ids: &[Uuid], client_order_ids: &[String]) -> Vec<CancelBatch> {
+        let order_ids = unique(order_ids);
+        let client_order_ids = unique(client_order_ids);
+        let by_id = order_ids.chunks(MAX_CANCEL_BATCH).map(|ids| CancelBatch {
+            order_ids: ids.to_vec(),
+            ..Default::default()
+        });
+        let by_client_id = client_order_ids
+            .chunks(MAX_CANCEL_BATCH)
+            .map(|ids| CancelBatch {
+                client_order_ids: ids.to_vec(),
+                ..Default::default()
+            });
+        by_id.chain(by_client_id).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.order_ids.len() + self.client_order_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn unique<T: Clone + Eq + std::hash::Hash>(ids: &[T]) -> Vec<T> {
+    let mut seen = HashSet::new();
+    ids.iter().filter(|id| seen.insert(*id)).cloned().collect()
+}
+
+/// A cancel request which failed as a whole; its orders may still be working.
+#[derive(Debug)]
+pub struct FailedCancelBatch {
+    pub batch: CancelBatch,
+    pub error: Error,
+}
+
+/// Outcome of a bulk cancel across all of its requests.
+#[derive(Debug, Default)]
+pub struct CancelReport {
+    /// Result per order of every request which got a response.
+    pub results: Vec<CancelOrderResultDto>,
+    pub failed: Vec<FailedCancelBatch>,
+    /// Client order ids which were not sent because the server would reject them.
+    pub invalid_client_order_ids: Vec<String>,
+}
+
+impl CancelReport {
+    /// Ids of the orders this cancel took off the book.
+    pub fn cancelled(&self) -> Vec<Uuid> {
+        self.results
+            .iter()
+            .filter(|r| r.result == CancelOrderResultCode::Ok)
+            .map(|r| r.id)
+            .collect()
+    }
+
+    pub fn count(&self, code: CancelOrderResultCode) -> usize {
+        self.results.iter().filter(|r| r.result == code).count()
+    }
+
+    pub fn by_code(&self) -> BTreeMap<CancelOrderResultCode, usize> {
+        let mut counts = BTreeMap::new();
+        for result in &self.results {
+            *counts.entry(result.result).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Whether no order targeted by the cancel can still be working: every request was sent
+    /// and every order was cancelled or had already been cancelled, filled or expired.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+            && self.invalid_client_order_ids.is_empty()
+            && self.results.iter().all(|r| {
+                matches!(
+                    r.result,
+                    CancelOrderResultCode::Ok
+                        | CancelOrderResultCode::AlreadyCanceled
+                        | CancelOrderResultCode::AlreadyFilled
+                        | CancelOrderResultCode::AlreadyExpired
+                )
+            })
+    }
+}
@@ -0,0 +1,219 @@
+use ethers::types::transaction::eip712::Eip712Error;
+use thiserror::Error;
+
+use crate::{
+    apis, archive_apis,
+    async_client::builder::ClientBuildError,
+    models::{RateLimitErrorType, SubmitFailedCode, TooManyRequestsDto},
+    order_builder::OrderBuilderError,
+    ws_auth::AuthError,
+    ws_client::ClientError,
+};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Classified SDK failure, shared by the HTTP client, the WebSocket client and signing.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Connection, timeout or socket failure before a response was received.
+    #[error("Transport error: {source}")]
+    Transport { source: BoxError, retryable: bool },
+    #[error("Rate limited ({kind:?}): {message}")]
+    RateLimited {
+        kind: Option<RateLimitErrorType>,
+        message: String,
+    },
+    #[error("Validation error: {0}")]
+    Validation(String),
+    #[error("Authentication error: {0}")]
+    Auth(String),
+    /// Order submission refused with a result code, the `message` of `SubmitOrderFailedDto`.
+    #[error("Order rejected: {code:?}")]
+    OrderRejected { code: SubmitFailedCode },
+    #[error("Signing error: {0}")]
+    Signing(String),
+    #[error("Deserialization error: {0}")]
+    Deserialization(String),
+    /// Any other non-success response, e.g. 404 or 5xx.
+    #[error("API error {status}: {message}")]
+    Api { status: u16, message: String },
+}
+
+impl Error {
+    /// Whether the same request may succeed if sent again later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Transport { retryable, .. } => *retryable,
+            Error::RateLimited { .. } => true,
+            Error::Api { status, .. } => *status >= 500,
+            Error::Validation(_)
+            | Error::Auth(_)
+            | Error::OrderRejected { .. }
+            | Error::Signing(_)
+            | Error::Deserialization(_) => false,
+        }
+    }
+
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self, Error::RateLimited { .. })
+    }
+
+    /// An order rejection when `message` is a result code, otherwise a validation error.
+    fn rejection(message: String) -> Self {
+        match serde_json::from_value(serde_json::Value::String(message.clone())) {
+            Ok(code) => Error::OrderRejected { code },
+            Err(_) => Error::Validation(message),
+        }
+    }
+
+    fn from_response(status: u16, content: &str) -> Self {
+        let body: Option<serde_json::Value> = serde_json::from_str(content).ok();
+        let message = body
+            .as_ref()
+            .and_then(|body| body.get("message"))
+            .map(|message| match message {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .unwrap_or_else(|| content.to_string());
+        match status {
+            429 => Error::RateLimited {
+                kind: serde_json::from_str::<TooManyRequestsDto>(content)
+                    .ok()
+                    .map(|dto| dto.r#type),
+                message,
+            },
+            401 | 403 => Error::Auth(message),
+            // Order submission reports its rejection reason as the message of a 422.
+            422 => Error::rejection(message),
+            400 => Error::Validation(message),
+            _ => Error::Api { status, message },
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            return Error::Deserialization(e.to_string());
+        }
+        let retryable = e.is_timeout() || e.is_connect();
+        Error::Transport {
+            source: Box::new(e),
+            retryable,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Deserialization(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Transport {
+            source: Box::new(e),
+            retryable: true,
+        }
+    }
+}
+
+impl<T> From<apis::Error<T>> for Error {
+    fn from(e: apis::Error<T>) -> Self {
+        match e {
+            apis::Error::Reqwest(e) => e.into(),
+            apis::Error::Serde(e) => e.into(),
+            apis::Error::Io(e) => e.into(),
+            apis::Error::ResponseError(response) => {
+                Error::from_response(response.status.as_u16(), &response.content)
+            }
+        }
+    }
+}
+
+impl<T> From<archive_apis::Error<T>> for Error {
+    fn from(e: archive_apis::Error<T>) -> Self {
+        match e {
+            archive_apis::Error::Reqwest(e) => e.into(),
+            archive_apis::Error::Serde(e) => e.into(),
+            archive_apis::Error::Io(e) => e.into(),
+            archive_apis::Error::ResponseError(response) => {
+                Error::from_response(response.status.as_u16(), &response.content)
+            }
+        }
+    }
+}
+
+impl From<ClientError> for Error {
+    fn from(e: ClientError) -> Self {
+        match e {
+            ClientError::WebsocketError(e) => Error::Transport {
+                source: Box::new(e),
+                retryable: true,
+            },
+            ClientError::Io(e) => e.into(),
+            ClientError::Transport(source) => Error::Transport {
+                source,
+                retryable: true,
+            },
+            ClientError::SubscriptionError(message) | ClientError::InvalidRequest(message) => {
+                Error::Validation(message)
+            }
+            ClientError::Deserialization(e) => e.into(),
+            e @ (ClientError::Timeout(_) | ClientError::Disconnected | ClientError::Gap) => {
+                Error::Transport {
+                    source: Box::new(e),
+                    retryable: true,
+                }
+            }
+            ClientError::Auth(e) => e.into(),
+            // Order rejections carry their result code as the message, as over HTTP.
+            ClientError::RequestRejected(message) => Error::rejection(message),
+        }
+    }
+}
+
+impl From<AuthError> for Error {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::Signing(message) => Error::Signing(message),
+            AuthError::Rejected(message) => Error::Auth(message),
+            e @ AuthError::NotConfigured => Error::Auth(e.to_string()),
+            e @ AuthError::Timeout(_) => Error::Transport {
+                source: Box::new(e),
+                retryable: true,
+            },
+        }
+    }
+}
+
+impl From<Eip712Error> for Error {
+    fn from(e: Eip712Error) -> Self {
+        Error::Signing(e.to_string())
+    }
+}
+
+impl From<OrderBuilderError> for Error {
+    fn from(e: OrderBuilderError) -> Self {
+        match e {
+            OrderBuilderError::Signing(e) => e.into(),
+            other => Error::Validation(other.to_string()),
+        }
+    }
+}
+
+impl From<ClientBuildError> for Error {
+    fn from(e: ClientBuildError) -> Self {
+        match e {
+            ClientBuildError::InvalidPrivateKey(e) => Error::Auth(e.to_string()),
+            ClientBuildError::Http(e) => Error::Transport {
+                source: Box::new(e),
+                retryable: false,
+            },
+            ClientBuildError::Subaccounts(e) => e.into(),
+            ClientBuildError::Products(e) => e.into(),
+        }
+    }
+}
@@ -1,15 +1,37 @@
 pub mod apis;
 pub mod archive_apis;
 pub mod archive_models;
+pub mod account_state;
 pub mod async_client;
+pub mod cancel;
 pub mod channels;
 pub mod domain_config;
 pub mod enums;
+pub mod error;
+pub mod managed_order;
 pub mod models;
+pub mod order_book;
+pub mod order_builder;
+pub mod order_group;
+pub mod pagination;
+pub mod replace_order;
 pub mod routing;
 pub mod signable_messages;
 pub mod signing;
+pub mod stop_orders;
+pub mod subscription_stream;
 pub mod subscriptions;
 pub mod types;
 pub mod utils;
+#[cfg(feature = "experimental-ws-auth")]
+pub mod ws_auth;
+#[cfg(not(feature = "experimental-ws-auth"))]
+#[allow(dead_code)]
+mod ws_auth;
 pub mod ws_client;
+pub mod ws_config;
+#[cfg(feature = "experimental-ws-orders")]
+pub mod ws_orders;
+pub mod ws_pool;
+
+pub use error::Error;
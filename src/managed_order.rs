@@ -0,0 +1,525 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use futures_util::StreamExt;
+use log::warn;
+use rust_decimal::Decimal;
+use thiserror::Error;
+use tokio::{sync::watch, task::JoinHandle};
+use uuid::Uuid;
+
+use crate::{
+    apis::{
+        configuration::Configuration,
+        order_api::{order_controller_get_by_id, OrderControllerGetByIdParams},
+    },
+    async_client::{client::HttpClient, session::SubaccountSelector},
+    error::Error,
+    models::{
+        OrderDto, OrderFillMessageDataDInner, OrderStatus, OrderUpdateMessageDataDInner,
+        SubmitOrderCreatedDto,
+    },
+    ws_client::{ClientError, WsClient},
+};
+
+/// Finished orders kept by an [`OrderTracker`] for [`OrderTracker::track`] calls which arrive
+/// after the order's last update, e.g. for immediately filled orders.
+pub const FINISHED_ORDERS_KEPT: usize = 1024;
+
+/// How long a tracked order may go without an update before it is looked up over REST, e.g.
+/// as it finished and was dropped from the [`FINISHED_ORDERS_KEPT`] before being tracked.
+pub const UNSEEN_ORDER_LOOKUP_DELAY: Duration = Duration::from_secs(1);
+
+/// An update which was not applied, or a fill which left the order in an impossible state.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum TransitionError {
+    #[error("Illegal transition from {from} to {to}")]
+    Illegal { from: OrderStatus, to: OrderStatus },
+    #[error("Update from {received} is older than the last one from {last}")]
+    OutOfOrder { received: i64, last: i64 },
+    #[error("Filled {filled} of an order for {quantity}")]
+    Overfilled { filled: Decimal, quantity: Decimal },
+}
+
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum WaitError {
+    #[error("Order still {0} after the timeout")]
+    Timeout(OrderStatus),
+    #[error("Order ended as {0}")]
+    Ended(OrderStatus),
+    #[error("Order tracker was dropped")]
+    TrackerClosed,
+}
+
+pub fn is_terminal(status: OrderStatus) -> bool {
+    matches!(
+        status,
+        OrderStatus::Filled | OrderStatus::Canceled | OrderStatus::Expired | OrderStatus::Rejected
+    )
+}
+
+/// Whether an order may go from `from` to `to`: pending, then new, then partially filled, then
+/// filled, cancelled, expired or rejected, skipping any of the intermediate states.
+pub fn is_legal_transition(from: OrderStatus, to: OrderStatus) -> bool {
+    use OrderStatus::*;
+    match (from, to) {
+        (from, to) if from == to => !is_terminal(from),
+        (Pending, _) => true,
+        (New, FilledPartial | Filled | Canceled | Expired | Rejected) => true,
+        (FilledPartial, Filled | Canceled | Expired) => true,
+        _ => false,
+    }
+}
+
+/// Status and fill progress of one order, merged from `OrderUpdate` and `OrderFill` messages.
+///
+/// Fills are counted once each and can run ahead of the updates, so the status is advanced to
+/// partially filled or filled from the fills alone. Updates which were not applied and fills
+/// beyond the order's quantity are kept in [`OrderLifecycle::anomalies`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderLifecycle {
+    id: Uuid,
+    client_order_id: Option<String>,
+    status: OrderStatus,
+    quantity: Decimal,
+    filled: Decimal,
+    available_quantity: Decimal,
+    updated_at: i64,
+    fills: HashSet<Uuid>,
+    filled_by_fills: Decimal,
+    anomalies: Vec<TransitionError>,
+}
+
+impl OrderLifecycle {
+    /// A submitted order for which no update arrived yet.
+    pub fn new(id: Uuid, client_order_id: Option<String>) -> Self {
+        Self {
+            id,
+            client_order_id,
+            status: OrderStatus::Pending,
+            quantity: Decimal::ZERO,
+            filled: Decimal::ZERO,
+            available_quantity: Decimal::ZERO,
+            updated_at: 0,
+            fills: HashSet::new(),
+            filled_by_fills: Decimal::ZERO,
+            anomalies: Vec::new(),
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn client_order_id(&self) -> Option<&str> {
+        self.client_order_id.as_deref()
+    }
+
+    pub fn status(&self) -> OrderStatus {
+        self.status
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        is_terminal(self.status)
+    }
+
+    /// Zero until the first update for the order.
+    pub fn quantity(&self) -> Decimal {
+        self.quantity
+    }
+
+    pub fn filled(&self) -> Decimal {
+        self.filled
+    }
+
+    pub fn available_quantity(&self) -> Decimal {
+        self.available_quantity
+    }
+
+    /// Timestamp (ms) of the last applied update.
+    pub fn updated_at(&self) -> i64 {
+        self.updated_at
+    }
+
+    pub fn anomalies(&self) -> &[TransitionError] {
+        &self.anomalies
+    }
+
+    /// Applies an update, returning the new status if it changed.
+    pub fn apply_update(
+        &mut self,
+        update: &OrderUpdateMessageDataDInner,
+    ) -> Result<Option<OrderStatus>, TransitionError> {
+        if update.t < self.updated_at {
+            return self.flag(TransitionError::OutOfOrder {
+                received: update.t,
+                last: self.updated_at,
+            });
+        }
+        let previous = self.status;
+        if update.st != previous && !is_legal_transition(previous, update.st) {
+            if update.fill < self.filled && !is_terminal(previous) {
+                // Sent before fills which were already merged, but may be the first to tell the
+                // order's quantity.
+                if self.quantity.is_zero() {
+                    self.quantity = update.qty;
+                    self.available_quantity = (self.quantity - self.filled).max(Decimal::ZERO);
+                    self.settle();
+                }
+                return Ok((self.status != previous).then_some(self.status));
+            }
+            return self.flag(TransitionError::Illegal {
+                from: previous,
+                to: update.st,
+            });
+        }
+        if self.client_order_id.is_none() {
+            self.client_order_id = update.cloid.clone();
+        }
+        self.status = update.st;
+        self.quantity = update.qty;
+        self.filled = update.fill.max(self.filled_by_fills);
+        self.available_quantity = update
+            .aqty
+            .min(self.quantity - self.filled)
+            .max(Decimal::ZERO);
+        self.updated_at = update.t;
+        self.settle();
+        Ok((self.status != previous).then_some(self.status))
+    }
+
+    /// Applies the order as listed by the REST API, e.g. after updates were missed, returning
+    /// the new status if it changed. A listing older than the last update is ignored.
+    pub fn apply_snapshot(
+        &mut self,
+        order: &OrderDto,
+    ) -> Result<Option<OrderStatus>, TransitionError> {
+        if order.updated_at < self.updated_at {
+            return Ok(None);
+        }
+        self.apply_update(&OrderUpdateMessageDataDInner {
+            id: order.id,
+            cloid: order.client_order_id.clone(),
+            qty: order.quantity,
+            aqty: order.available_quantity,
+            fill: order.filled,
+            sid: order.subaccount_id,
+            st: order.status,
+            t: order.updated_at,
+            ..Default::default()
+        })
+    }
+
+    /// Applies a fill, returning the new status if it changed. Repeated fills are ignored.
+    pub fn apply_fill(
+        &mut self,
+        fill: &OrderFillMessageDataDInner,
+    ) -> Result<Option<OrderStatus>, TransitionError> {
+        if !self.fills.insert(fill.id) {
+            return Ok(None);
+        }
+        let previous = self.status;
+        self.filled_by_fills += fill.sz;
+        self.filled = self.filled.max(self.filled_by_fills);
+        if !self.quantity.is_zero() {
+            self.available_quantity = (self.quantity - self.filled).max(Decimal::ZERO);
+            if self.filled > self.quantity {
+                self.settle();
+                return self.flag(TransitionError::Overfilled {
+                    filled: self.filled,
+                    quantity: self.quantity,
+                });
+            }
+        }
+        self.settle();
+        Ok((self.status != previous).then_some(self.status))
+    }
+
+    /// Advances the status to what the filled quantity implies.
+    fn settle(&mut self) {
+        if self.is_terminal() || self.filled.is_zero() {
+            return;
+        }
+        if !self.quantity.is_zero() && self.filled >= self.quantity {
+            self.status = OrderStatus::Filled;
+        } else if matches!(self.status, OrderStatus::Pending | OrderStatus::New) {
+            self.status = OrderStatus::FilledPartial;
+        }
+    }
+
+    fn flag(&mut self, error: TransitionError) -> Result<Option<OrderStatus>, TransitionError> {
+        warn!("Order {}: {error}", self.id);
+        self.anomalies.push(error.clone());
+        Err(error)
+    }
+}
+
+/// Handle to an order followed by an [`OrderTracker`], cheap to clone across tasks.
+#[derive(Clone, Debug)]
+pub struct ManagedOrder {
+    rx: watch::Receiver<OrderLifecycle>,
+}
+
+impl ManagedOrder {
+    pub fn id(&self) -> Uuid {
+        self.rx.borrow().id
+    }
+
+    pub fn status(&self) -> OrderStatus {
+        self.rx.borrow().status
+    }
+
+    pub fn state(&self) -> OrderLifecycle {
+        self.rx.borrow().clone()
+    }
+
+    /// Waits until the order is filled, cancelled, expired or rejected.
+    pub async fn wait_terminal(&self, timeout: Duration) -> Result<OrderLifecycle, WaitError> {
+        let mut rx = self.rx.clone();
+        let terminal = tokio::time::timeout(timeout, async {
+            rx.wait_for(OrderLifecycle::is_terminal)
+                .await
+                .map(|state| state.clone())
+        })
+        .await;
+        match terminal {
+            Ok(Ok(state)) => Ok(state),
+            Ok(Err(_)) => Err(WaitError::TrackerClosed),
+            Err(_) => Err(WaitError::Timeout(self.status())),
+        }
+    }
+
+    /// Waits until the order is filled, failing with [`WaitError::Ended`] if it ends otherwise.
+    pub async fn wait_filled(&self, timeout: Duration) -> Result<OrderLifecycle, WaitError> {
+        let state = self.wait_terminal(timeout).await?;
+        match state.status {
+            OrderStatus::Filled => Ok(state),
+            status => Err(WaitError::Ended(status)),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Orders {
+    by_id: HashMap<Uuid, watch::Sender<OrderLifecycle>>,
+    finished: VecDeque<Uuid>,
+}
+
+impl Orders {
+    fn entry(
+        &mut self,
+        id: Uuid,
+        client_order_id: Option<String>,
+    ) -> &watch::Sender<OrderLifecycle> {
+        self.by_id
+            .entry(id)
+            .or_insert_with(|| watch::Sender::new(OrderLifecycle::new(id, client_order_id)))
+    }
+
+    fn apply(
+        &mut self,
+        id: Uuid,
+        client_order_id: Option<String>,
+        apply: impl FnOnce(&mut OrderLifecycle) -> Result<Option<OrderStatus>, TransitionError>,
+    ) {
+        let mut terminal = false;
+        self.entry(id, client_order_id).send_modify(|order| {
+            let was_terminal = order.is_terminal();
+            let _ = apply(order);
+            terminal = !was_terminal && order.is_terminal();
+        });
+        if terminal {
+            self.finished.push_back(id);
+            while self.finished.len() > FINISHED_ORDERS_KEPT {
+                if let Some(id) = self.finished.pop_front() {
+                    self.by_id.remove(&id);
+                }
+            }
+        }
+    }
+}
+
+/// Looks orders up over REST when the channels did not tell their state.
+#[derive(Clone)]
+struct Lookup {
+    config: Arc<Configuration>,
+    // Weak, so a pending lookup does not keep the waits of a dropped tracker open.
+    orders: Weak<Mutex<Orders>>,
+}
+
+impl Lookup {
+    /// Applies each order still tracked as listed by `GET /v1/order/{id}`.
+    async fn refresh(self, ids: Vec<Uuid>) {
+        for id in ids {
+            let params = OrderControllerGetByIdParams { id: id.to_string() };
+            let order = match order_controller_get_by_id(&self.config, params).await {
+                Ok(order) => order,
+                Err(e) => {
+                    warn!("Failed to look up order {id}: {}", Error::from(e));
+                    continue;
+                }
+            };
+            let Some(orders) = self.orders.upgrade() else {
+                return;
+            };
+            let mut orders = orders.lock().unwrap_or_else(|e| e.into_inner());
+            if orders.by_id.contains_key(&id) {
+                orders.apply(id, order.client_order_id.clone(), |lifecycle| {
+                    lifecycle.apply_snapshot(&order)
+                });
+            }
+        }
+    }
+
+    /// Looks the order up unless an update arrives within [`UNSEEN_ORDER_LOOKUP_DELAY`].
+    async fn check_unseen(self, id: Uuid) {
+        tokio::time::sleep(UNSEEN_ORDER_LOOKUP_DELAY).await;
+        let unseen = match self.orders.upgrade() {
+            Some(orders) => orders
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .by_id
+                .get(&id)
+                .is_some_and(|order| order.borrow().updated_at() == 0),
+            None => false,
+        };
+        if unseen {
+            self.refresh(vec![id]).await;
+        }
+    }
+}
+
+/// Follows the orders of one subaccount over the `OrderUpdate` and `OrderFill` channels, handing
+/// out a [`ManagedOrder`] per order.
+///
+/// Every order of the subaccount is followed from the moment of subscribing, so updates which
+/// arrive before [`OrderTracker::track`] is called are not lost. After a reconnect the orders
+/// which were not finished are looked up over REST, as their updates meanwhile were missed, and
+/// so is a tracked order without any update after [`UNSEEN_ORDER_LOOKUP_DELAY`]. Dropping the
+/// tracker unsubscribes and ends every wait.
+pub struct OrderTracker {
+    orders: Arc<Mutex<Orders>>,
+    lookup: Lookup,
+    task: JoinHandle<()>,
+}
+
+impl OrderTracker {
+    pub async fn subscribe(
+        http_client: &HttpClient,
+        ws_client: &WsClient,
+        subaccount: impl Into<SubaccountSelector>,
+    ) -> Result<Self, Error> {
+        let subaccount_id = http_client.get_subaccount(subaccount)?.id;
+        let subscriptions = ws_client.subscriptions();
+        let ids = vec![subaccount_id.to_string()];
+        let updates = subscriptions
+            .order_update_stream(ids.clone())
+            .await?
+            .map(|msg| match msg {
+                Ok(msg) => Ok(Message::Updates(msg.data.d)),
+                Err(ClientError::Gap) => Ok(Message::Gap),
+                Err(e) => Err(e),
+            });
+        let fills = subscriptions
+            .order_fill_stream(ids)
+            .await?
+            .map(|msg| msg.map(|msg| Message::Fills(msg.data.d)));
+
+        let orders = Arc::new(Mutex::new(Orders::default()));
+        let lookup = Lookup {
+            config: Arc::new(http_client.config.clone()),
+            orders: Arc::downgrade(&orders),
+        };
+        let routes = orders.clone();
+        let refresh = lookup.clone();
+        let mut messages = futures_util::stream::select(updates, fills);
+        let task = tokio::spawn(async move {
+            while let Some(msg) = messages.next().await {
+                let msg = match msg {
+                    Ok(msg) => msg,
+                    // Both streams mark the same reconnect; the updates' gap is acted on.
+                    Err(ClientError::Gap) => continue,
+                    Err(e) => {
+                        warn!("Failed to parse order message: {e}");
+                        continue;
+                    }
+                };
+                let mut orders = routes.lock().unwrap_or_else(|e| e.into_inner());
+                match msg {
+                    Message::Gap => {
+                        let ids: Vec<Uuid> = orders
+                            .by_id
+                            .iter()
+                            .filter(|(_, order)| !order.borrow().is_terminal())
+                            .map(|(id, _)| *id)
+                            .collect();
+                        warn!(
+                            "Order updates may have been missed, looking up {} orders",
+                            ids.len()
+                        );
+                        tokio::spawn(refresh.clone().refresh(ids));
+                    }
+                    Message::Updates(updates) => {
+                        for update in updates.iter().filter(|u| u.sid == subaccount_id) {
+                            orders.apply(update.id, update.cloid.clone(), |order| {
+                                order.apply_update(update)
+                            });
+                        }
+                    }
+                    Message::Fills(fills) => {
+                        for fill in fills.iter().filter(|f| f.sid == subaccount_id) {
+                            orders.apply(fill.oid, fill.cloid.clone(), |order| {
+                                order.apply_fill(fill)
+                            });
+                        }
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            orders,
+            lookup,
+            task,
+        })
+    }
+
+    /// Follows a submitted order, including any updates received before the call.
+    ///
+    /// An order without any update by [`UNSEEN_ORDER_LOOKUP_DELAY`] is looked up over REST.
+    pub fn track(&self, created: &SubmitOrderCreatedDto) -> ManagedOrder {
+        let mut orders = self.orders.lock().unwrap_or_else(|e| e.into_inner());
+        if !orders.by_id.contains_key(&created.id) {
+            tokio::spawn(self.lookup.clone().check_unseen(created.id));
+        }
+        ManagedOrder {
+            rx: orders
+                .entry(created.id, created.client_order_id.clone())
+                .subscribe(),
+        }
+    }
+
+    /// An order seen since subscribing, unless it finished long ago.
+    pub fn get(&self, id: &Uuid) -> Option<ManagedOrder> {
+        let orders = self.orders.lock().unwrap_or_else(|e| e.into_inner());
+        orders.by_id.get(id).map(|sender| ManagedOrder {
+            rx: sender.subscribe(),
+        })
+    }
+}
+
+impl Drop for OrderTracker {
+    fn drop(&mut self) {
+        // Drops the subscription streams, which unsubscribes.
+        self.task.abort();
+    }
+}
+
+enum Message {
+    Updates(Vec<OrderUpdateMessageDataDInner>),
+    /// Messages may have been missed while reconnecting.
+    Gap,
+    Fills(Vec<OrderFillMessageDataDInner>),
+}
@@ -24,18 +24,18 @@ pub struct L2BookMessageData {
     pub pt: Option<i64>,
     /// Ask levels as [price, quantity] string tuples.
     #[serde(rename = "a")]
-    pub a: Vec<Vec<rust_decimal::Decimal>>,
+    pub a: Vec<crate::types::PriceLevel>,
     /// Bid levels as [price, quantity] string tuples.
     #[serde(rename = "b")]
-    pub b: Vec<Vec<rust_decimal::Decimal>>,
+    pub b: Vec<crate::types::PriceLevel>,
 }
 
 impl L2BookMessageData {
     pub fn new(
         s: String,
         t: i64,
-        a: Vec<Vec<rust_decimal::Decimal>>,
-        b: Vec<Vec<rust_decimal::Decimal>>,
+        a: Vec<crate::types::PriceLevel>,
+        b: Vec<crate::types::PriceLevel>,
     ) -> L2BookMessageData {
         L2BookMessageData {
             s,
@@ -15,10 +15,10 @@ use serde::{Deserialize, Serialize};
 pub struct MarketLiquidityDto {
     /// An array of ask tuple pairs (price, quantity) ordered in asc
     #[serde(rename = "asks")]
-    pub asks: Vec<Vec<String>>,
+    pub asks: Vec<crate::types::PriceLevel>,
     /// An array of bid tuple pairs (price, quantity) ordered in desc
     #[serde(rename = "bids")]
-    pub bids: Vec<Vec<String>>,
+    pub bids: Vec<crate::types::PriceLevel>,
     /// Previous book update, undefined if never updated (ms since Unix Epoch)
     #[serde(rename = "previousTimestamp", skip_serializing_if = "Option::is_none")]
     pub previous_timestamp: Option<i64>,
@@ -32,8 +32,8 @@ pub struct MarketLiquidityDto {
 
 impl MarketLiquidityDto {
     pub fn new(
-        asks: Vec<Vec<String>>,
-        bids: Vec<Vec<String>>,
+        asks: Vec<crate::types::PriceLevel>,
+        bids: Vec<crate::types::PriceLevel>,
         product_id: uuid::Uuid,
         timestamp: i64,
     ) -> MarketLiquidityDto {
@@ -0,0 +1,489 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    sync::{Arc, RwLock, RwLockReadGuard},
+};
+
+use futures_util::StreamExt;
+use log::{error, warn};
+use rust_decimal::Decimal;
+use tokio::{
+    sync::mpsc,
+    task::{JoinHandle, JoinSet},
+    time::sleep,
+};
+use uuid::Uuid;
+
+use crate::{
+    apis::{
+        configuration::Configuration,
+        product_api::{
+            product_controller_get_market_liquidity, ProductControllerGetMarketLiquidityParams,
+        },
+    },
+    async_client::client::HttpClient,
+    channels::Channels,
+    error::Error,
+    models::{L2BookMessageData, MarketLiquidityDto, OrderSide},
+    types::PriceLevel,
+    ws_client::{ClientError, Connection, WsClient},
+    ws_config::WsConfig,
+};
+
+/// Diffs kept while a book is stale, to bridge the gap between a REST snapshot and the stream.
+const STALE_DIFFS_KEPT: usize = 1024;
+
+/// Diff which does not follow on from the last applied update (`pt != last t`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookGap {
+    pub expected: i64,
+    pub previous: i64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BookDepth {
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncState {
+    /// The next message is taken as the full book, as after subscribing.
+    #[default]
+    AwaitingSnapshot,
+    /// Up to date as of this timestamp (ms).
+    Synced(i64),
+    /// Seeded from a REST snapshot taken at this timestamp (ms). The first diff after it is
+    /// applied without checking `pt`, which refers to the previous diff rather than the snapshot.
+    Bridging(i64),
+    /// A gap was detected; diffs are kept aside until a snapshot without `pt` or a REST seed.
+    Stale,
+}
+
+/// Local L2 book for one symbol, built from a snapshot followed by absolute quantity diffs.
+#[derive(Clone, Debug, Default)]
+pub struct OrderBook {
+    symbol: String,
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+    state: SyncState,
+    /// Diffs received while stale, replayed onto a REST seed.
+    stale_diffs: VecDeque<L2BookMessageData>,
+}
+
+impl OrderBook {
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn state(&self) -> SyncState {
+        self.state
+    }
+
+    pub fn is_synced(&self) -> bool {
+        matches!(self.state, SyncState::Synced(_) | SyncState::Bridging(_))
+    }
+
+    /// Timestamp (ms) of the last applied update, if synced.
+    pub fn timestamp(&self) -> Option<i64> {
+        match self.state {
+            SyncState::Synced(t) | SyncState::Bridging(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Applies a snapshot or diff, returning the gap if `pt` does not match the last `t`.
+    ///
+    /// On a gap the book is cleared and marked [`SyncState::Stale`].
+    pub fn apply(&mut self, data: &L2BookMessageData) -> Result<(), BookGap> {
+        match (self.state, data.pt) {
+            (SyncState::AwaitingSnapshot, _) | (_, None) => {
+                self.bids.clear();
+                self.asks.clear();
+                self.stale_diffs.clear();
+            }
+            (SyncState::Stale, Some(_)) => {
+                if self.stale_diffs.len() == STALE_DIFFS_KEPT {
+                    self.stale_diffs.pop_front();
+                }
+                self.stale_diffs.push_back(data.clone());
+                return Ok(());
+            }
+            (SyncState::Bridging(seeded), Some(_)) if data.t <= seeded => {
+                // Already part of the REST snapshot.
+                return Ok(());
+            }
+            (SyncState::Bridging(_), Some(_)) => {}
+            (SyncState::Synced(last), Some(_)) if data.t <= last => {
+                // Already covered by a newer snapshot.
+                return Ok(());
+            }
+            (SyncState::Synced(last), Some(previous)) if previous != last => {
+                self.bids.clear();
+                self.asks.clear();
+                self.state = SyncState::Stale;
+                return Err(BookGap {
+                    expected: last,
+                    previous,
+                });
+            }
+            (SyncState::Synced(_), Some(_)) => {}
+        }
+        apply_levels(&mut self.bids, &data.b);
+        apply_levels(&mut self.asks, &data.a);
+        self.state = SyncState::Synced(data.t);
+        Ok(())
+    }
+
+    /// Replaces the book with a REST snapshot from `ProductClient::get_market_liquidity`, then
+    /// applies the diffs received since it was taken.
+    ///
+    /// Returns the gap if those diffs do not follow on from each other, leaving the book stale.
+    pub fn seed(&mut self, liquidity: &MarketLiquidityDto) -> Result<(), BookGap> {
+        self.bids.clear();
+        self.asks.clear();
+        apply_levels(&mut self.bids, &liquidity.bids);
+        apply_levels(&mut self.asks, &liquidity.asks);
+        self.state = SyncState::Bridging(liquidity.timestamp);
+        for diff in std::mem::take(&mut self.stale_diffs) {
+            self.apply(&diff)?;
+        }
+        Ok(())
+    }
+
+    /// Clears the book and keeps diffs aside until a snapshot without `pt` or a REST seed, e.g.
+    /// while resubscribing, when diffs already in flight would otherwise be taken as the full book.
+    pub fn mark_stale(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.stale_diffs.clear();
+        self.state = SyncState::Stale;
+    }
+
+    /// Clears the book and takes the next message as the full book, e.g. before resubscribing.
+    pub fn await_snapshot(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.stale_diffs.clear();
+        self.state = SyncState::AwaitingSnapshot;
+    }
+
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids().next()
+    }
+
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks().next()
+    }
+
+    pub fn mid_price(&self) -> Option<Decimal> {
+        let bid = self.best_bid()?;
+        let ask = self.best_ask()?;
+        Some((bid.price + ask.price) / Decimal::TWO)
+    }
+
+    pub fn spread(&self) -> Option<Decimal> {
+        let bid = self.best_bid()?;
+        let ask = self.best_ask()?;
+        Some(ask.price - bid.price)
+    }
+
+    /// Bids best first.
+    pub fn bids(&self) -> impl Iterator<Item = PriceLevel> + '_ {
+        self.bids.iter().rev().map(|(p, q)| PriceLevel::new(*p, *q))
+    }
+
+    /// Asks best first.
+    pub fn asks(&self) -> impl Iterator<Item = PriceLevel> + '_ {
+        self.asks.iter().map(|(p, q)| PriceLevel::new(*p, *q))
+    }
+
+    /// The best `levels` price levels on each side.
+    pub fn depth(&self, levels: usize) -> BookDepth {
+        BookDepth {
+            bids: self.bids().take(levels).collect(),
+            asks: self.asks().take(levels).collect(),
+        }
+    }
+
+    /// Average price to fill `size` by taking liquidity, or `None` if the book is too thin.
+    pub fn vwap(&self, side: OrderSide, size: Decimal) -> Option<Decimal> {
+        if size <= Decimal::ZERO {
+            return None;
+        }
+        let levels: Box<dyn Iterator<Item = PriceLevel>> = match side {
+            OrderSide::BUY => Box::new(self.asks()),
+            OrderSide::SELL => Box::new(self.bids()),
+        };
+        let mut remaining = size;
+        let mut notional = Decimal::ZERO;
+        for level in levels {
+            let filled = remaining.min(level.quantity);
+            notional += filled * level.price;
+            remaining -= filled;
+            if remaining.is_zero() {
+                return Some(notional / size);
+            }
+        }
+        None
+    }
+}
+
+fn apply_levels(book: &mut BTreeMap<Decimal, Decimal>, levels: &[PriceLevel]) {
+    for level in levels {
+        if level.quantity.is_zero() {
+            book.remove(&level.price);
+        } else {
+            book.insert(level.price, level.quantity);
+        }
+    }
+}
+
+/// Read-only handle to a book maintained by an [`OrderBookFeed`], cheap to clone across tasks.
+#[derive(Clone, Debug)]
+pub struct OrderBookView {
+    inner: Arc<RwLock<OrderBook>>,
+}
+
+impl OrderBookView {
+    pub fn read(&self) -> RwLockReadGuard<'_, OrderBook> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn snapshot(&self) -> OrderBook {
+        self.read().clone()
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.read().is_synced()
+    }
+
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.read().best_bid()
+    }
+
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.read().best_ask()
+    }
+
+    pub fn mid_price(&self) -> Option<Decimal> {
+        self.read().mid_price()
+    }
+
+    pub fn depth(&self, levels: usize) -> BookDepth {
+        self.read().depth(levels)
+    }
+
+    pub fn vwap(&self, side: OrderSide, size: Decimal) -> Option<Decimal> {
+        self.read().vwap(side, size)
+    }
+}
+
+/// How an [`OrderBookFeed`] recovers a book after a gap. Either is retried with the
+/// [`WsConfig`] backoff until the book is synced again.
+pub enum ResyncStrategy {
+    /// Unsubscribe from the symbol and subscribe again, so the server sends a fresh snapshot.
+    /// Other subscriptions to the symbol's book are told of the gap this leaves them.
+    Resubscribe,
+    /// Seed the book from `ProductClient::get_market_liquidity`, keyed by product ticker.
+    Rest {
+        config: Box<Configuration>,
+        product_ids: HashMap<String, Uuid>,
+    },
+}
+
+impl ResyncStrategy {
+    pub fn rest(client: &HttpClient) -> Self {
+        ResyncStrategy::Rest {
+            config: Box::new(client.config.clone()),
+            product_ids: client
+                .product_hashmap
+                .values()
+                .map(|p| (p.ticker.clone(), p.id))
+                .collect(),
+        }
+    }
+}
+
+/// Local books for a set of symbols, kept up to date from the `L2Book` channel.
+///
+/// After a reconnect every book is stale until the snapshot sent for the replayed subscription,
+/// and resynced as after a gap if none arrives. Dropping the feed unsubscribes.
+pub struct OrderBookFeed {
+    books: HashMap<String, Arc<RwLock<OrderBook>>>,
+    task: JoinHandle<()>,
+    resync: JoinHandle<()>,
+}
+
+impl OrderBookFeed {
+    pub async fn subscribe(
+        ws_client: &WsClient,
+        symbols: Vec<String>,
+        resync: ResyncStrategy,
+    ) -> Result<Self, ClientError> {
+        let books: HashMap<String, Arc<RwLock<OrderBook>>> = symbols
+            .iter()
+            .map(|s| (s.clone(), Arc::new(RwLock::new(OrderBook::new(s.clone())))))
+            .collect();
+        let mut messages = ws_client.subscriptions().l2_book_stream(symbols).await?;
+        let (resync_tx, resync_rx) = mpsc::unbounded_channel::<String>();
+        let resync = tokio::spawn(run_resync(
+            books.clone(),
+            resync_rx,
+            Resync {
+                strategy: Arc::new(resync),
+                connection: ws_client.connection(),
+                subscription_id: messages.handle().subscription_id(),
+                config: ws_client.config().clone(),
+            },
+        ));
+        let routes = books.clone();
+        let task = tokio::spawn(async move {
+            while let Some(msg) = messages.next().await {
+                let msg = match msg {
+                    Ok(msg) => msg,
+                    Err(ClientError::Gap) => {
+                        for (symbol, book) in &routes {
+                            book.write().unwrap_or_else(|e| e.into_inner()).mark_stale();
+                            let _ = resync_tx.send(symbol.clone());
+                        }
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("Failed to parse L2 book message: {e}");
+                        continue;
+                    }
+                };
+                let Some(book) = routes.get(&msg.data.s) else {
+                    continue;
+                };
+                let result = book
+                    .write()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .apply(&msg.data);
+                if let Err(gap) = result {
+                    warn!("Gap in {} book: {gap:?}, resyncing", msg.data.s);
+                    let _ = resync_tx.send(msg.data.s);
+                }
+            }
+        });
+        Ok(Self {
+            books,
+            task,
+            resync,
+        })
+    }
+
+    pub fn book(&self, symbol: &str) -> Option<OrderBookView> {
+        self.books.get(symbol).map(|inner| OrderBookView {
+            inner: inner.clone(),
+        })
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &String> {
+        self.books.keys()
+    }
+}
+
+impl Drop for OrderBookFeed {
+    fn drop(&mut self) {
+        // Drops the subscription stream, which unsubscribes.
+        self.task.abort();
+        self.resync.abort();
+    }
+}
+
+/// What a book's resync needs, shared by the resyncs of every book of a feed.
+#[derive(Clone)]
+struct Resync {
+    strategy: Arc<ResyncStrategy>,
+    connection: Connection,
+    /// The feed's own subscription, which is not told of the gaps its resubscribes leave.
+    subscription_id: u64,
+    config: WsConfig,
+}
+
+/// Resyncs each book sent on `resync_rx`, one resync per book at a time. Dropping the task ends
+/// the resyncs in progress.
+async fn run_resync(
+    books: HashMap<String, Arc<RwLock<OrderBook>>>,
+    mut resync_rx: mpsc::UnboundedReceiver<String>,
+    resync: Resync,
+) {
+    let mut resyncing = HashSet::new();
+    let mut tasks = JoinSet::new();
+    loop {
+        tokio::select! {
+            symbol = resync_rx.recv() => {
+                let Some(symbol) = symbol else { return };
+                let Some(book) = books.get(&symbol) else { continue };
+                if resyncing.insert(symbol.clone()) {
+                    tasks.spawn(resync_book(symbol, book.clone(), resync.clone()));
+                }
+            }
+            Some(Ok(symbol)) = tasks.join_next() => {
+                resyncing.remove(&symbol);
+            }
+        }
+    }
+}
+
+/// Retries with backoff until the book is synced. Each wait comes first, so a snapshot already
+/// on its way, e.g. for the subscription replayed after a reconnect, makes the resync needless.
+async fn resync_book(symbol: String, book: Arc<RwLock<OrderBook>>, resync: Resync) -> String {
+    let is_synced = || book.read().unwrap_or_else(|e| e.into_inner()).is_synced();
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        sleep(resync.config.backoff_delay(attempt)).await;
+        if is_synced() {
+            return symbol;
+        }
+        if let Err(e) = resync_once(&symbol, &book, &resync).await {
+            error!("Failed to resync {symbol} book, attempt {attempt}: {e}");
+        }
+    }
+}
+
+async fn resync_once(symbol: &str, book: &RwLock<OrderBook>, resync: &Resync) -> Result<(), Error> {
+    match resync.strategy.as_ref() {
+        ResyncStrategy::Resubscribe => {
+            // The book stays stale through the round trip, so diffs still in flight are kept
+            // aside and only the snapshot sent for the subscribe is installed.
+            book.write().unwrap_or_else(|e| e.into_inner()).mark_stale();
+            resync
+                .connection
+                .resubscribe(Channels::L2Book, symbol, resync.subscription_id)
+                .await?;
+        }
+        ResyncStrategy::Rest {
+            config,
+            product_ids,
+        } => {
+            let product_id = product_ids
+                .get(symbol)
+                .ok_or_else(|| Error::Validation(format!("No product id for {symbol}")))?;
+            let liquidity = product_controller_get_market_liquidity(
+                config,
+                ProductControllerGetMarketLiquidityParams {
+                    product_id: product_id.to_string(),
+                },
+            )
+            .await?;
+            if let Err(gap) = book
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .seed(&liquidity)
+            {
+                warn!("Gap in {symbol} book after seeding it: {gap:?}");
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,423 @@
+use std::convert::TryFrom;
+
+use ethers::{types::transaction::eip712::Eip712Error, utils::hex};
+use rust_decimal::Decimal;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    enums::Environment,
+    models::{
+        GroupContingencyType, OrderSide, OrderTimeInForce, OrderType, ProductDto, StopType,
+        SubmitOrderData, SubmitOrderDto, SubmitOrderLimitDtoData, SubmitOrderMarketDtoData,
+    },
+    order_group::BracketLeg,
+    signable_messages::TradeOrder,
+    signing::{to_scaled_e9, SigningContext},
+    with_signing_fields,
+};
+
+const MAX_CLIENT_ORDER_ID_LEN: usize = 32;
+/// Random characters following a [`ClientOrderIdStrategy::Prefixed`] prefix, at the least.
+const MIN_CLIENT_ORDER_ID_RANDOM_LEN: usize = 8;
+
+#[derive(Error, Debug)]
+pub enum OrderBuilderError {
+    #[error("Quantity must be positive, got {0}")]
+    InvalidQuantity(Decimal),
+    #[error("Limit orders require a positive price, got {0}")]
+    InvalidPrice(Decimal),
+    #[error("Closing orders require zero quantity and reduce_only")]
+    InvalidClose,
+    #[error("Market orders do not support {0}")]
+    UnsupportedForMarket(&'static str),
+    #[error("Stop price and stop type must be set together")]
+    IncompleteStop,
+    #[error("Stop price must be positive, got {0}")]
+    InvalidStopPrice(Decimal),
+    #[error("Invalid client order id: {0}")]
+    InvalidClientOrderId(String),
+    #[error("Bracket orders need a take profit or stop loss leg")]
+    IncompleteBracket,
+    #[error("{0:?} leg must be on the opposite side to the entry")]
+    BracketLegSide(BracketLeg),
+    #[error("{leg:?} leg quantity {quantity} does not fit the entry quantity {entry}")]
+    BracketLegQuantity {
+        leg: BracketLeg,
+        quantity: Decimal,
+        entry: Decimal,
+    },
+    #[error("{leg:?} leg stop price {stop_price} is on the wrong side of {reference}")]
+    MisplacedBracketLeg {
+        leg: BracketLeg,
+        stop_price: Decimal,
+        reference: Decimal,
+    },
+    #[error("Ticker {expected} does not match product {actual}")]
+    ProductMismatch { expected: String, actual: String },
+    #[error("Signing error: {0}")]
+    Signing(#[from] Eip712Error),
+    #[error("Conversion error: {0}")]
+    Conversion(#[from] anyhow::Error),
+}
+
+fn round_to_tick(value: Decimal, tick_size: Decimal) -> Decimal {
+    if tick_size.is_zero() {
+        return value;
+    }
+    let ticks = (value / tick_size).round();
+    ticks * tick_size
+}
+
+/// Fluent builder for limit and market orders.
+///
+/// All fields supported by `SubmitOrderLimitDtoData` and `SubmitOrderMarketDtoData` can be
+/// set here; the combination is validated and signed in [`OrderBuilder::build`].
+#[derive(Clone, Debug)]
+pub struct OrderBuilder {
+    ticker: String,
+    side: OrderSide,
+    quantity: Decimal,
+    r#type: OrderType,
+    price: Option<Decimal>,
+    time_in_force: Option<OrderTimeInForce>,
+    post_only: bool,
+    reduce_only: bool,
+    close: bool,
+    expires_at: Option<i64>,
+    client_order_id: Option<String>,
+    stop_price: Option<Decimal>,
+    stop_type: Option<StopType>,
+    group_id: Option<Uuid>,
+    group_contingency_type: Option<GroupContingencyType>,
+}
+
+impl OrderBuilder {
+    fn new(ticker: &str, side: OrderSide, quantity: Decimal, r#type: OrderType) -> Self {
+        Self {
+            ticker: ticker.to_string(),
+            side,
+            quantity,
+            r#type,
+            price: None,
+            time_in_force: None,
+            post_only: false,
+            reduce_only: false,
+            close: false,
+            expires_at: None,
+            client_order_id: None,
+            stop_price: None,
+            stop_type: None,
+            group_id: None,
+            group_contingency_type: None,
+        }
+    }
+
+    pub fn limit(ticker: &str, side: OrderSide, quantity: Decimal, price: Decimal) -> Self {
+        let mut order = Self::new(ticker, side, quantity, OrderType::Limit);
+        order.price = Some(price);
+        order
+    }
+
+    pub fn market(ticker: &str, side: OrderSide, quantity: Decimal) -> Self {
+        Self::new(ticker, side, quantity, OrderType::Market)
+    }
+
+    /// A market order which is released once `stop_price` is crossed.
+    ///
+    /// The submit API takes no [`crate::models::StopPriceType`], so whether the last or the mark
+    /// price triggers the order is up to the exchange; see [`OrderDto::stop_price_type`].
+    ///
+    /// [`OrderDto::stop_price_type`]: crate::models::OrderDto::stop_price_type
+    pub fn stop_market(
+        ticker: &str,
+        side: OrderSide,
+        quantity: Decimal,
+        stop_price: Decimal,
+        stop_type: StopType,
+    ) -> Self {
+        Self::market(ticker, side, quantity).stop(stop_price, stop_type)
+    }
+
+    /// A limit order at `price` which is released once `stop_price` is crossed.
+    ///
+    /// Triggers on the exchange's default price, as for [`OrderBuilder::stop_market`].
+    pub fn stop_limit(
+        ticker: &str,
+        side: OrderSide,
+        quantity: Decimal,
+        price: Decimal,
+        stop_price: Decimal,
+        stop_type: StopType,
+    ) -> Self {
+        Self::limit(ticker, side, quantity, price).stop(stop_price, stop_type)
+    }
+
+    /// A reduce-only market order closing the entire position on `ticker`.
+    pub fn close_position(ticker: &str, side: OrderSide) -> Self {
+        Self::market(ticker, side, Decimal::ZERO)
+            .reduce_only(true)
+            .close(true)
+    }
+
+    pub fn time_in_force(mut self, time_in_force: OrderTimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    pub fn post_only(mut self, post_only: bool) -> Self {
+        self.post_only = post_only;
+        self
+    }
+
+    pub fn reduce_only(mut self, reduce_only: bool) -> Self {
+        self.reduce_only = reduce_only;
+        self
+    }
+
+    pub fn close(mut self, close: bool) -> Self {
+        self.close = close;
+        self
+    }
+
+    pub fn expires_at(mut self, expires_at: i64) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    pub fn client_order_id(mut self, client_order_id: impl Into<String>) -> Self {
+        self.client_order_id = Some(client_order_id.into());
+        self
+    }
+
+    pub fn stop(mut self, stop_price: Decimal, stop_type: StopType) -> Self {
+        self.stop_price = Some(stop_price);
+        self.stop_type = Some(stop_type);
+        self
+    }
+
+    pub fn group(mut self, group_id: Uuid, contingency_type: GroupContingencyType) -> Self {
+        self.group_id = Some(group_id);
+        self.group_contingency_type = Some(contingency_type);
+        self
+    }
+
+    pub fn ticker(&self) -> &str {
+        &self.ticker
+    }
+
+    pub fn side(&self) -> OrderSide {
+        self.side
+    }
+
+    pub fn quantity(&self) -> Decimal {
+        self.quantity
+    }
+
+    pub fn order_type(&self) -> OrderType {
+        self.r#type
+    }
+
+    pub fn price(&self) -> Option<Decimal> {
+        self.price
+    }
+
+    pub fn stop_price(&self) -> Option<Decimal> {
+        self.stop_price
+    }
+
+    pub fn get_client_order_id(&self) -> Option<&str> {
+        self.client_order_id.as_deref()
+    }
+
+    fn validate(&self) -> Result<(), OrderBuilderError> {
+        if self.close {
+            if !self.quantity.is_zero() || !self.reduce_only {
+                return Err(OrderBuilderError::InvalidClose);
+            }
+        } else if self.quantity <= Decimal::ZERO {
+            return Err(OrderBuilderError::InvalidQuantity(self.quantity));
+        }
+        match self.r#type {
+            OrderType::Limit => {
+                let price = self.price.unwrap_or_default();
+                if price <= Decimal::ZERO {
+                    return Err(OrderBuilderError::InvalidPrice(price));
+                }
+            }
+            OrderType::Market => {
+                if self.post_only {
+                    return Err(OrderBuilderError::UnsupportedForMarket("post_only"));
+                }
+                if self.time_in_force.is_some() {
+                    return Err(OrderBuilderError::UnsupportedForMarket("time_in_force"));
+                }
+            }
+        }
+        match (self.stop_price, self.stop_type) {
+            (Some(stop_price), Some(_)) if stop_price <= Decimal::ZERO => {
+                return Err(OrderBuilderError::InvalidStopPrice(stop_price));
+            }
+            (Some(_), None) | (None, Some(_)) => return Err(OrderBuilderError::IncompleteStop),
+            _ => {}
+        }
+        if let Some(cloid) = &self.client_order_id {
+            validate_client_order_id(cloid)?;
+        }
+        Ok(())
+    }
+
+    /// Validates the order, rounds it to the product's tick and lot sizes and signs it.
+    pub fn build(
+        self,
+        product: &ProductDto,
+        ctx: &SigningContext,
+        env: Environment,
+    ) -> Result<SubmitOrderDto, OrderBuilderError> {
+        if product.display_ticker != self.ticker && product.ticker != self.ticker {
+            return Err(OrderBuilderError::ProductMismatch {
+                expected: self.ticker,
+                actual: product.display_ticker.clone(),
+            });
+        }
+        self.validate()?;
+
+        let quantity = round_to_tick(self.quantity, product.lot_size);
+        if !self.close && quantity.is_zero() {
+            return Err(OrderBuilderError::InvalidQuantity(quantity));
+        }
+        // Market orders carry no limit price, so the signed price is zero.
+        let price = self
+            .price
+            .map(|price| round_to_tick(price, product.tick_size))
+            .unwrap_or_default();
+        let stop_price = self
+            .stop_price
+            .map(|stop_price| round_to_tick(stop_price, product.tick_size));
+
+        let message = with_signing_fields!(
+            eip_signing_fields,
+            ctx,
+            TradeOrder {
+                quantity: to_scaled_e9(quantity)?,
+                price: to_scaled_e9(price)?,
+                reduce_only: self.reduce_only,
+                side: self.side as u8,
+                engine_type: product.engine_type as u8,
+                product_id: u32::try_from(product.onchain_id)
+                    .map_err(|e| anyhow::anyhow!("Invalid onchain id: {e}"))?,
+            }
+        );
+        let signature = ctx.sign(env, &message)?;
+
+        let data = match self.r#type {
+            OrderType::Limit => SubmitOrderData::SubmitOrderLimitDtoData(with_signing_fields!(
+                dto_signing_fields,
+                ctx,
+                SubmitOrderLimitDtoData {
+                    quantity,
+                    price,
+                    side: self.side,
+                    onchain_id: product.onchain_id,
+                    engine_type: product.engine_type,
+                    reduce_only: Some(self.reduce_only),
+                    close: Some(self.close),
+                    post_only: self.post_only,
+                    expires_at: self.expires_at,
+                    time_in_force: self.time_in_force.unwrap_or_default(),
+                    client_order_id: self.client_order_id,
+                    stop_price,
+                    stop_type: self.stop_type,
+                    group_id: self.group_id,
+                    group_contingency_type: self.group_contingency_type,
+                    r#type: OrderType::Limit,
+                }
+            )),
+            OrderType::Market => SubmitOrderData::SubmitOrderMarketDtoData(with_signing_fields!(
+                dto_signing_fields,
+                ctx,
+                SubmitOrderMarketDtoData {
+                    quantity,
+                    side: self.side,
+                    onchain_id: product.onchain_id,
+                    engine_type: product.engine_type,
+                    reduce_only: Some(self.reduce_only),
+                    close: Some(self.close),
+                    expires_at: self.expires_at,
+                    client_order_id: self.client_order_id,
+                    stop_price,
+                    stop_type: self.stop_type,
+                    group_id: self.group_id,
+                    group_contingency_type: self.group_contingency_type,
+                    r#type: OrderType::Market,
+                }
+            )),
+        };
+
+        Ok(SubmitOrderDto {
+            data,
+            signature: "0x".to_string() + &hex::encode(signature.to_vec()),
+        })
+    }
+}
+
+/// Client order ids are either a UUID or an alphanumeric string of up to 32 characters.
+pub fn validate_client_order_id(client_order_id: &str) -> Result<(), OrderBuilderError> {
+    let is_uuid = Uuid::parse_str(client_order_id).is_ok();
+    let is_alphanumeric = !client_order_id.is_empty()
+        && client_order_id.len() <= MAX_CLIENT_ORDER_ID_LEN
+        && client_order_id.chars().all(|c| c.is_ascii_alphanumeric());
+    if is_uuid || is_alphanumeric {
+        Ok(())
+    } else {
+        Err(OrderBuilderError::InvalidClientOrderId(
+            client_order_id.to_string(),
+        ))
+    }
+}
+
+/// How client order ids are filled in for orders which do not set one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ClientOrderIdStrategy {
+    /// Leave the client order id unset.
+    Disabled,
+    /// A random UUID.
+    #[default]
+    Uuid,
+    /// The prefix followed by random alphanumerics, 32 characters in all.
+    Prefixed(ClientOrderIdPrefix),
+}
+
+/// A prefix checked by [`ClientOrderIdStrategy::prefixed`], which is the only way to make one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientOrderIdPrefix(String);
+
+impl ClientOrderIdPrefix {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl ClientOrderIdStrategy {
+    /// Checks that the prefix is alphanumeric and leaves room for at least 8 random characters.
+    pub fn prefixed(prefix: impl Into<String>) -> Result<Self, OrderBuilderError> {
+        let prefix = prefix.into();
+        let fits = prefix.len() + MIN_CLIENT_ORDER_ID_RANDOM_LEN <= MAX_CLIENT_ORDER_ID_LEN;
+        if !fits || !prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(OrderBuilderError::InvalidClientOrderId(prefix));
+        }
+        Ok(ClientOrderIdStrategy::Prefixed(ClientOrderIdPrefix(prefix)))
+    }
+
+    pub fn generate(&self) -> Option<String> {
+        match self {
+            ClientOrderIdStrategy::Disabled => None,
+            ClientOrderIdStrategy::Uuid => Some(Uuid::new_v4().to_string()),
+            ClientOrderIdStrategy::Prefixed(ClientOrderIdPrefix(prefix)) => {
+                let random = Uuid::new_v4().simple().to_string();
+                let len = MAX_CLIENT_ORDER_ID_LEN - prefix.len();
+                Some(format!("{prefix}{}", &random[..len]))
+            }
+        }
+    }
+}
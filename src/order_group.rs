@@ -0,0 +1,206 @@
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::{
+    models::{GroupContingencyType, OrderSide, StopType, SubmitOrderCreatedDto},
+    order_builder::{OrderBuilder, OrderBuilderError},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BracketLeg {
+    Entry,
+    TakeProfit,
+    StopLoss,
+}
+
+#[derive(Clone, Debug)]
+struct ExitLeg {
+    stop_price: Decimal,
+    limit_price: Option<Decimal>,
+}
+
+/// An entry order with linked take-profit and/or stop-loss legs.
+///
+/// The entry triggers the exits (OTO) and the exits cancel each other (OCO); all legs share one
+/// `group_id`.
+#[derive(Clone, Debug)]
+pub struct BracketOrder {
+    entry: OrderBuilder,
+    group_id: Uuid,
+    take_profit: Option<ExitLeg>,
+    stop_loss: Option<ExitLeg>,
+}
+
+impl BracketOrder {
+    pub fn new(entry: OrderBuilder) -> Self {
+        Self {
+            entry,
+            group_id: Uuid::new_v4(),
+            take_profit: None,
+            stop_loss: None,
+        }
+    }
+
+    pub fn group_id(mut self, group_id: Uuid) -> Self {
+        self.group_id = group_id;
+        self
+    }
+
+    /// Take profit with a market exit once `stop_price` is reached.
+    pub fn take_profit(mut self, stop_price: Decimal) -> Self {
+        self.take_profit = Some(ExitLeg {
+            stop_price,
+            limit_price: None,
+        });
+        self
+    }
+
+    /// Take profit with a limit exit at `limit_price` once `stop_price` is reached.
+    pub fn take_profit_limit(mut self, stop_price: Decimal, limit_price: Decimal) -> Self {
+        self.take_profit = Some(ExitLeg {
+            stop_price,
+            limit_price: Some(limit_price),
+        });
+        self
+    }
+
+    /// Stop loss with a market exit once `stop_price` is reached.
+    pub fn stop_loss(mut self, stop_price: Decimal) -> Self {
+        self.stop_loss = Some(ExitLeg {
+            stop_price,
+            limit_price: None,
+        });
+        self
+    }
+
+    /// Stop loss with a limit exit at `limit_price` once `stop_price` is reached.
+    pub fn stop_loss_limit(mut self, stop_price: Decimal, limit_price: Decimal) -> Self {
+        self.stop_loss = Some(ExitLeg {
+            stop_price,
+            limit_price: Some(limit_price),
+        });
+        self
+    }
+
+    pub fn get_group_id(&self) -> Uuid {
+        self.group_id
+    }
+
+    fn exit_order(&self, leg: &ExitLeg, stop_type: StopType) -> OrderBuilder {
+        let ticker = self.entry.ticker();
+        let side = match self.entry.side() {
+            OrderSide::BUY => OrderSide::SELL,
+            OrderSide::SELL => OrderSide::BUY,
+        };
+        let quantity = self.entry.quantity();
+        let order = match leg.limit_price {
+            Some(price) => {
+                OrderBuilder::stop_limit(ticker, side, quantity, price, leg.stop_price, stop_type)
+            }
+            None => OrderBuilder::stop_market(ticker, side, quantity, leg.stop_price, stop_type),
+        };
+        order
+            .reduce_only(true)
+            .group(self.group_id, GroupContingencyType::OCO)
+    }
+
+    /// Checks that the exits close no more than the entry opens, with the take profit beyond the
+    /// entry price and the stop loss short of it. Without an entry price, e.g. for a market
+    /// entry, the take profit and stop loss are only checked against each other.
+    fn validate(&self, exits: &[(BracketLeg, OrderBuilder)]) -> Result<(), OrderBuilderError> {
+        let entry = self.entry.quantity();
+        for (leg, order) in exits {
+            if order.side() == self.entry.side() {
+                return Err(OrderBuilderError::BracketLegSide(*leg));
+            }
+            if order.quantity() <= Decimal::ZERO || order.quantity() > entry {
+                return Err(OrderBuilderError::BracketLegQuantity {
+                    leg: *leg,
+                    quantity: order.quantity(),
+                    entry,
+                });
+            }
+        }
+        // Signed so that the take profit must come out above the reference.
+        let direction = match self.entry.side() {
+            OrderSide::BUY => Decimal::ONE,
+            OrderSide::SELL => -Decimal::ONE,
+        };
+        let misplaced = |leg, stop_price: Decimal, reference: Decimal| {
+            let beyond = (stop_price - reference) * direction > Decimal::ZERO;
+            let wanted = leg == BracketLeg::TakeProfit;
+            (beyond != wanted).then_some(OrderBuilderError::MisplacedBracketLeg {
+                leg,
+                stop_price,
+                reference,
+            })
+        };
+        let reference = self.entry.price().or_else(|| self.entry.stop_price());
+        if let Some(reference) = reference {
+            let legs = [
+                (BracketLeg::TakeProfit, &self.take_profit),
+                (BracketLeg::StopLoss, &self.stop_loss),
+            ];
+            for (leg, exit) in legs {
+                if let Some(error) = exit
+                    .as_ref()
+                    .and_then(|exit| misplaced(leg, exit.stop_price, reference))
+                {
+                    return Err(error);
+                }
+            }
+        } else if let (Some(take_profit), Some(stop_loss)) = (&self.take_profit, &self.stop_loss) {
+            if let Some(error) = misplaced(
+                BracketLeg::TakeProfit,
+                take_profit.stop_price,
+                stop_loss.stop_price,
+            ) {
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Expands the bracket into its individual orders, entry first, once the legs are validated.
+    pub fn into_orders(self) -> Result<Vec<(BracketLeg, OrderBuilder)>, OrderBuilderError> {
+        if self.take_profit.is_none() && self.stop_loss.is_none() {
+            return Err(OrderBuilderError::IncompleteBracket);
+        }
+        let mut orders = Vec::with_capacity(3);
+        if let Some(leg) = &self.take_profit {
+            orders.push((BracketLeg::TakeProfit, self.exit_order(leg, StopType::GAIN)));
+        }
+        if let Some(leg) = &self.stop_loss {
+            orders.push((BracketLeg::StopLoss, self.exit_order(leg, StopType::LOSS)));
+        }
+        self.validate(&orders)?;
+        let entry = self.entry.group(self.group_id, GroupContingencyType::OTO);
+        orders.insert(0, (BracketLeg::Entry, entry));
+        Ok(orders)
+    }
+}
+
+/// Handle to a submitted order group, used to query or cancel it as a unit.
+#[derive(Clone, Debug)]
+pub struct OrderGroup {
+    pub group_id: Uuid,
+    pub subaccount_id: Uuid,
+    pub orders: Vec<(BracketLeg, SubmitOrderCreatedDto)>,
+}
+
+impl OrderGroup {
+    pub fn order_ids(&self) -> Vec<Uuid> {
+        self.orders.iter().map(|(_, order)| order.id).collect()
+    }
+
+    pub fn leg(&self, leg: BracketLeg) -> Option<&SubmitOrderCreatedDto> {
+        self.orders
+            .iter()
+            .find(|(kind, _)| *kind == leg)
+            .map(|(_, order)| order)
+    }
+
+    pub fn entry_id(&self) -> Option<Uuid> {
+        self.leg(BracketLeg::Entry).map(|order| order.id)
+    }
+}
@@ -0,0 +1,157 @@
+use std::{collections::VecDeque, future::Future, time::Duration};
+
+use futures_util::{stream, Stream};
+use log::warn;
+
+use crate::error::Error;
+
+/// A `PageOf*` response: one page of items plus the cursor to the next page.
+pub trait Page {
+    type Item;
+
+    fn into_parts(self) -> (Vec<Self::Item>, bool, Option<String>);
+}
+
+/// Request parameters of a cursor paginated endpoint.
+pub trait CursorParams: Clone {
+    fn set_cursor(&mut self, cursor: Option<String>);
+}
+
+#[derive(Clone, Debug)]
+pub struct PaginationOptions {
+    /// Stop after this many items, even if more pages are available.
+    pub max_items: Option<usize>,
+    /// Pause before requesting each page after the first.
+    pub page_delay: Option<Duration>,
+    /// Wait before retrying a rate limited page; doubled on every retry.
+    pub rate_limit_backoff: Duration,
+    pub max_rate_limit_retries: u32,
+}
+
+impl Default for PaginationOptions {
+    fn default() -> Self {
+        Self {
+            max_items: None,
+            page_delay: None,
+            rate_limit_backoff: Duration::from_secs(1),
+            max_rate_limit_retries: 5,
+        }
+    }
+}
+
+impl PaginationOptions {
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    pub fn page_delay(mut self, page_delay: Duration) -> Self {
+        self.page_delay = Some(page_delay);
+        self
+    }
+
+    pub fn rate_limit_backoff(mut self, backoff: Duration, max_retries: u32) -> Self {
+        self.rate_limit_backoff = backoff;
+        self.max_rate_limit_retries = max_retries;
+        self
+    }
+}
+
+struct PaginationState<P, T, F> {
+    params: P,
+    options: PaginationOptions,
+    fetch: F,
+    buffer: VecDeque<T>,
+    pages: usize,
+    yielded: usize,
+    finished: bool,
+}
+
+/// Streams every item of a cursor paginated endpoint, following `has_next`/`next_cursor`.
+///
+/// The stream ends after the last page, after `max_items`, or after the first error.
+pub fn paginate<'a, P, R, F, Fut, E>(
+    params: P,
+    options: PaginationOptions,
+    fetch: F,
+) -> impl Stream<Item = Result<R::Item, Error>> + 'a
+where
+    P: CursorParams + 'a,
+    R: Page + 'a,
+    R::Item: 'a,
+    F: Fn(P) -> Fut + 'a,
+    Fut: Future<Output = Result<R, E>> + 'a,
+    E: Into<Error>,
+{
+    let state = PaginationState {
+        params,
+        options,
+        fetch,
+        buffer: VecDeque::new(),
+        pages: 0,
+        yielded: 0,
+        finished: false,
+    };
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(max_items) = state.options.max_items {
+                if state.yielded >= max_items {
+                    return None;
+                }
+            }
+            if let Some(item) = state.buffer.pop_front() {
+                state.yielded += 1;
+                return Some((Ok(item), state));
+            }
+            if state.finished {
+                return None;
+            }
+            if state.pages > 0 {
+                if let Some(delay) = state.options.page_delay {
+                    tokio::time::sleep(delay).await;
+                }
+            }
+            match fetch_page(&state.fetch, &state.params, &state.options).await {
+                Ok(page) => {
+                    let (data, has_next, next_cursor) = page.into_parts();
+                    state.buffer.extend(data);
+                    state.pages += 1;
+                    match next_cursor.filter(|_| has_next) {
+                        Some(cursor) => state.params.set_cursor(Some(cursor)),
+                        None => state.finished = true,
+                    }
+                }
+                Err(e) => {
+                    state.finished = true;
+                    return Some((Err(e), state));
+                }
+            }
+        }
+    })
+}
+
+async fn fetch_page<P, R, F, Fut, E>(
+    fetch: &F,
+    params: &P,
+    options: &PaginationOptions,
+) -> Result<R, Error>
+where
+    P: CursorParams,
+    F: Fn(P) -> Fut,
+    Fut: Future<Output = Result<R, E>>,
+    E: Into<Error>,
+{
+    let mut backoff = options.rate_limit_backoff;
+    let mut retries = 0;
+    loop {
+        match fetch(params.clone()).await.map_err(Into::into) {
+            Err(e) if e.is_rate_limited() && retries < options.max_rate_limit_retries => {
+                warn!("Rate limited while paginating, retrying in {backoff:?}: {e}");
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
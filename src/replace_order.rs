@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    error::Error,
+    managed_order::{ManagedOrder, WaitError},
+    models::{CancelOrderResultCode, OrderStatus},
+};
+
+/// Order in which a replace sends its two requests.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplaceMode {
+    /// Places the replacement only once the original is confirmed cancelled, so both are never
+    /// working at once but the subaccount has no order in between. The replacement takes over
+    /// the original's client order id unless it has its own.
+    #[default]
+    CancelThenPlace,
+    /// Places the replacement first, so there is no gap but both orders may fill until the
+    /// original is cancelled. The client order id is not carried over, as both orders are
+    /// working at the same time.
+    PlaceThenCancel,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplaceOptions {
+    pub mode: ReplaceMode,
+    /// How long to wait for the update which closes the original before asking the REST API.
+    pub confirm_timeout: Duration,
+}
+
+impl Default for ReplaceOptions {
+    fn default() -> Self {
+        Self {
+            mode: ReplaceMode::default(),
+            confirm_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ReplaceOptions {
+    pub fn mode(mut self, mode: ReplaceMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn confirm_timeout(mut self, confirm_timeout: Duration) -> Self {
+        self.confirm_timeout = confirm_timeout;
+        self
+    }
+}
+
+/// Outcome of a replace in which the original order was closed.
+#[derive(Clone, Debug)]
+pub struct ReplaceReport {
+    pub original_id: Uuid,
+    pub cancel_result: CancelOrderResultCode,
+    /// Status the original ended with; `Filled` if it filled before the cancel reached it.
+    pub original_status: OrderStatus,
+    /// Quantity of the original filled between the start of the replace and its close.
+    pub filled_during_replace: Decimal,
+    /// `None` in [`ReplaceMode::CancelThenPlace`] when the original closed other than by this
+    /// cancel, in which case nothing is placed.
+    pub replacement: Option<ManagedOrder>,
+}
+
+impl ReplaceReport {
+    pub fn is_replaced(&self) -> bool {
+        self.replacement.is_some() && self.original_status == OrderStatus::Canceled
+    }
+}
+
+/// A replace which did not complete. Variants carrying a `replacement` are those of
+/// [`ReplaceMode::PlaceThenCancel`] after the replacement was placed; it is still working.
+#[derive(Error, Debug)]
+pub enum ReplaceError {
+    #[error("Order {id} is already {status}")]
+    NotWorking { id: Uuid, status: OrderStatus },
+    #[error("Cancelling order {id} failed with {code}")]
+    CancelRejected {
+        id: Uuid,
+        code: CancelOrderResultCode,
+        replacement: Option<ManagedOrder>,
+    },
+    #[error("Order {id} was not confirmed closed: {source}")]
+    Unconfirmed {
+        id: Uuid,
+        source: WaitError,
+        replacement: Option<ManagedOrder>,
+    },
+    #[error("{source}")]
+    Request {
+        source: Error,
+        replacement: Option<ManagedOrder>,
+    },
+    /// The original was cancelled but the replacement was not placed.
+    #[error("Order {id} was cancelled but its replacement failed: {source}")]
+    PlaceFailed {
+        id: Uuid,
+        filled_during_replace: Decimal,
+        source: Error,
+    },
+}
+
+impl ReplaceError {
+    /// The replacement placed before the failure, which is still working.
+    pub fn replacement(&self) -> Option<&ManagedOrder> {
+        match self {
+            ReplaceError::CancelRejected { replacement, .. }
+            | ReplaceError::Unconfirmed { replacement, .. }
+            | ReplaceError::Request { replacement, .. } => replacement.as_ref(),
+            ReplaceError::NotWorking { .. } | ReplaceError::PlaceFailed { .. } => None,
+        }
+    }
+
+    pub(crate) fn with_replacement(mut self, placed: ManagedOrder) -> Self {
+        if let ReplaceError::CancelRejected { replacement, .. }
+        | ReplaceError::Unconfirmed { replacement, .. }
+        | ReplaceError::Request { replacement, .. } = &mut self
+        {
+            *replacement = Some(placed);
+        }
+        self
+    }
+}
+
+impl From<Error> for ReplaceError {
+    fn from(source: Error) -> Self {
+        ReplaceError::Request {
+            source,
+            replacement: None,
+        }
+    }
+}
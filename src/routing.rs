@@ -1,3 +1,5 @@
+use serde::Deserialize;
+
 #[inline(always)]
 pub fn extract_event(bytes: &[u8]) -> Option<&str> {
     if !bytes.starts_with(b"{\"e\":\"") {
@@ -16,3 +18,65 @@ pub fn extract_event(bytes: &[u8]) -> Option<&str> {
 
     unsafe { Some(std::str::from_utf8_unchecked(&bytes[start..i])) }
 }
+
+#[derive(Deserialize)]
+struct ResponseId {
+    id: serde_json::Value,
+}
+
+/// Id of a request/response message, as a number or a numeric string.
+pub fn extract_request_id(bytes: &[u8]) -> Option<u64> {
+    let response: ResponseId = serde_json::from_slice(bytes).ok()?;
+    match response.id {
+        serde_json::Value::Number(id) => id.as_u64(),
+        serde_json::Value::String(id) => id.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Value of the first `"<key>":"..."` string field in the frame, found without parsing it.
+#[inline(always)]
+fn extract_string_field<'a>(bytes: &'a [u8], pattern: &[u8]) -> Option<&'a str> {
+    let start = bytes
+        .windows(pattern.len())
+        .position(|window| window == pattern)?
+        + pattern.len();
+    let len = bytes[start..].iter().position(|b| *b == b'"')?;
+    std::str::from_utf8(&bytes[start..start + len]).ok()
+}
+
+/// Symbol (`"s"`) of a product channel message.
+#[inline(always)]
+pub fn extract_symbol(bytes: &[u8]) -> Option<&str> {
+    extract_string_field(bytes, b"\"s\":\"")
+}
+
+/// Subaccount id (`"sid"`) of a subaccount channel message.
+#[inline(always)]
+pub fn extract_subaccount_id(bytes: &[u8]) -> Option<&str> {
+    extract_string_field(bytes, b"\"sid\":\"")
+}
+
+/// Key routing a channel's messages for one symbol or subaccount id to its subscribers.
+#[inline(always)]
+pub fn route_key(channel: &str, target: &str) -> String {
+    format!("{channel}:{target}")
+}
+
+/// Server timestamp (`"t"`, ms) of a channel message, taken from the first `t` in the frame.
+#[inline(always)]
+pub fn extract_timestamp(bytes: &[u8]) -> Option<u64> {
+    let pattern = b"\"t\":";
+    let start = bytes
+        .windows(pattern.len())
+        .position(|window| window == pattern)?
+        + pattern.len();
+    let len = bytes[start..]
+        .iter()
+        .position(|b| !b.is_ascii_digit())
+        .unwrap_or(bytes.len() - start);
+    std::str::from_utf8(&bytes[start..start + len])
+        .ok()?
+        .parse()
+        .ok()
+}
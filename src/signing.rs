@@ -7,6 +7,7 @@ use ethers::{
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use ethers::{types::U256, utils::keccak256};
@@ -21,14 +22,23 @@ pub fn to_scaled_e9(value: Decimal) -> Result<u128> {
         .expect("Value too large to fit in u128"))
 }
 
+static LAST_NONCE: AtomicU64 = AtomicU64::new(0);
+
 pub fn get_nonce() -> u64 {
-    // Get the current ns timestamp to use as a nonce
+    // Get the current ns timestamp to use as a nonce, bumped past the last one handed out so
+    // requests signed in quick succession never share a nonce
 
     let start = SystemTime::now();
     let since_epoch = start
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards");
-    since_epoch.as_nanos() as u64
+    let now = since_epoch.as_nanos() as u64;
+    let last = LAST_NONCE
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+            Some(now.max(last + 1))
+        })
+        .unwrap_or_default();
+    now.max(last + 1)
 }
 
 pub fn get_now() -> i64 {
@@ -128,6 +138,9 @@ impl<'a> SigningContext<'a> {
             signed_at: self.signed_at as i64,
         }
     }
+    pub fn sign<M: Eip712>(&self, env: Environment, message: &M) -> Result<Signature, Eip712Error> {
+        message.sign(env, self.wallet)
+    }
     pub fn new(wallet: &'a LocalWallet, subaccount: &'a SubaccountDto) -> Self {
         SigningContext {
             wallet,
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::models::{
+    OrderStatus, OrderUpdateMessage, OrderUpdateMessageDataDInner, StopPriceType, StopType,
+    TriggeredEnum,
+};
+
+pub fn stop_type_from_code(code: i32) -> Option<StopType> {
+    match code {
+        0 => Some(StopType::GAIN),
+        1 => Some(StopType::LOSS),
+        _ => None,
+    }
+}
+
+pub fn stop_price_type_from_code(code: i32) -> Option<StopPriceType> {
+    match code {
+        0 => Some(StopPriceType::LAST_PRICE),
+        1 => Some(StopPriceType::MARK_PRICE),
+        _ => None,
+    }
+}
+
+pub fn triggered_from_str(value: &str) -> Option<TriggeredEnum> {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+}
+
+/// Stop-specific view of an order update (`spx`/`styp`/`spxtyp`/`tr`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct StopOrderUpdate {
+    pub order_id: Uuid,
+    pub client_order_id: Option<String>,
+    pub symbol: String,
+    pub subaccount_id: Uuid,
+    pub status: OrderStatus,
+    pub stop_price: Decimal,
+    pub stop_type: StopType,
+    pub stop_price_type: Option<StopPriceType>,
+    pub triggered: TriggeredEnum,
+    pub timestamp: i64,
+}
+
+impl StopOrderUpdate {
+    /// Returns `None` for updates on orders which are not stop orders.
+    pub fn from_update(update: &OrderUpdateMessageDataDInner) -> Option<Self> {
+        let stop_price = update.spx.filter(|spx| !spx.is_zero())?;
+        let stop_type = update.styp.and_then(stop_type_from_code)?;
+        Some(Self {
+            order_id: update.id,
+            client_order_id: update.cloid.clone(),
+            symbol: update.s.clone(),
+            subaccount_id: update.sid,
+            status: update.st,
+            stop_price,
+            stop_type,
+            stop_price_type: update.spxtyp.and_then(stop_price_type_from_code),
+            triggered: triggered_from_str(&update.tr).unwrap_or_default(),
+            timestamp: update.t,
+        })
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.triggered != TriggeredEnum::NotTriggered
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StopTransition {
+    pub previous: Option<TriggeredEnum>,
+    pub update: StopOrderUpdate,
+}
+
+/// Tracks the trigger state of working stop orders and reports changes as they arrive.
+#[derive(Default)]
+pub struct StopOrderTracker {
+    states: HashMap<Uuid, StopOrderUpdate>,
+}
+
+impl StopOrderTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies every stop order in the message, returning the ones whose trigger state changed.
+    pub fn apply_message(&mut self, msg: &OrderUpdateMessage) -> Vec<StopTransition> {
+        msg.data
+            .d
+            .iter()
+            .filter_map(|update| self.apply(update))
+            .collect()
+    }
+
+    pub fn apply(&mut self, update: &OrderUpdateMessageDataDInner) -> Option<StopTransition> {
+        let stop = StopOrderUpdate::from_update(update)?;
+        let previous = self.states.get(&stop.order_id).map(|s| s.triggered);
+        if is_final(stop.status) {
+            self.states.remove(&stop.order_id);
+        } else {
+            self.states.insert(stop.order_id, stop.clone());
+        }
+        if previous == Some(stop.triggered) {
+            return None;
+        }
+        Some(StopTransition {
+            previous,
+            update: stop,
+        })
+    }
+
+    pub fn get(&self, order_id: &Uuid) -> Option<&StopOrderUpdate> {
+        self.states.get(order_id)
+    }
+
+    pub fn working(&self) -> impl Iterator<Item = &StopOrderUpdate> {
+        self.states.values()
+    }
+}
+
+fn is_final(status: OrderStatus) -> bool {
+    matches!(
+        status,
+        OrderStatus::Filled | OrderStatus::Canceled | OrderStatus::Expired | OrderStatus::Rejected
+    )
+}
@@ -0,0 +1,540 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use futures_util::{stream, Stream};
+use log::warn;
+use serde::de::DeserializeOwned;
+use tokio::sync::{mpsc::UnboundedSender, Notify};
+
+use crate::{
+    channels::Channels,
+    routing::extract_timestamp,
+    ws_client::{deserialise_to_type, ClientError, SubscriptionHandle, WsEvent},
+};
+
+/// What a bounded subscription queue does with a new message when it is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard the oldest queued message to make room.
+    #[default]
+    DropOldest,
+    /// Discard the new message.
+    DropNewest,
+    /// Wait for the consumer, which holds up dispatch for the whole connection: nothing is read
+    /// and no pings are sent meanwhile, so a wait longer than [`crate::ws_config::WsConfig`]'s
+    /// `read_timeout` ends in a reconnect. Use it only for consumers which never fall far behind.
+    Block,
+    /// Keep only the latest message per symbol or subaccount, replacing it in place.
+    ConflateLatest,
+}
+
+/// Queue settings of a callback or stream subscription.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamConfig {
+    /// Messages queued before the overflow policy applies.
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+    /// A message delivered this long after its server timestamp `t` marks the consumer as slow.
+    pub lag_warning: Duration,
+    /// Minimum time between two slow consumer warnings of the same subscription.
+    pub warning_interval: Duration,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            overflow: OverflowPolicy::default(),
+            lag_warning: Duration::from_secs(5),
+            warning_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl StreamConfig {
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn lag_warning(mut self, lag_warning: Duration) -> Self {
+        self.lag_warning = lag_warning;
+        self
+    }
+
+    pub fn warning_interval(mut self, warning_interval: Duration) -> Self {
+        self.warning_interval = warning_interval;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SubscriptionMetrics {
+    /// Messages waiting for the consumer.
+    pub queue_depth: usize,
+    /// Messages handed to the consumer.
+    pub delivered: u64,
+    /// Messages discarded or conflated by the overflow policy.
+    pub dropped: u64,
+    /// Largest delay between a message's server timestamp `t` and its dispatch to the consumer.
+    pub max_lag: Duration,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlowConsumerReason {
+    /// The queue was full and the overflow policy discarded a message.
+    Dropped,
+    /// The queue was full and dispatch is waiting for the consumer.
+    Blocked,
+    /// A message reached the consumer this long after its server timestamp.
+    Lagging(Duration),
+}
+
+/// Raised through `WsClient::run_till_event` when a consumer falls behind.
+#[derive(Clone, Debug)]
+pub struct SlowConsumerWarning {
+    pub channel: Channels,
+    pub subscription_id: u64,
+    /// Symbol or subaccount id of the message which triggered the warning.
+    pub target: String,
+    pub reason: SlowConsumerReason,
+    pub metrics: SubscriptionMetrics,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SlowConsumerAction {
+    /// Keep the subscription, leaving it to the overflow policy.
+    #[default]
+    Continue,
+    /// Close the queue: the stream or callback task ends, and the server subscription is
+    /// released once the handle is dropped.
+    Evict,
+}
+
+/// Decides what happens to a subscription whose consumer falls behind.
+pub type SlowConsumerPolicy = Arc<dyn Fn(&SlowConsumerWarning) -> SlowConsumerAction + Send + Sync>;
+
+/// Where a queue reports its slow consumer warnings.
+#[derive(Clone)]
+pub struct SlowConsumerMonitor {
+    pub channel: Channels,
+    pub subscription_id: u64,
+    pub events: UnboundedSender<WsEvent>,
+    /// Without a policy the subscription is kept.
+    pub policy: Option<SlowConsumerPolicy>,
+}
+
+pub(crate) enum QueueItem {
+    /// A message with the symbol or subaccount id it was routed by.
+    Message(String, Bytes),
+    /// Messages may have been missed before the next one, e.g. while reconnecting.
+    Gap,
+}
+
+#[derive(Default)]
+struct QueueState {
+    items: VecDeque<QueueItem>,
+    receiver_closed: bool,
+    senders_closed: bool,
+    last_warning: Option<Instant>,
+}
+
+struct Shared {
+    state: Mutex<QueueState>,
+    config: StreamConfig,
+    monitor: Option<SlowConsumerMonitor>,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    max_lag_ms: AtomicU64,
+    item_ready: Notify,
+    space_ready: Notify,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn metrics(&self, state: &QueueState) -> SubscriptionMetrics {
+        SubscriptionMetrics {
+            queue_depth: state.items.len(),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            max_lag: Duration::from_millis(self.max_lag_ms.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// The slow consumer warning to report, at most one per `warning_interval`.
+    fn slow_consumer_warning(
+        &self,
+        state: &mut QueueState,
+        target: &str,
+        reason: SlowConsumerReason,
+    ) -> Option<SlowConsumerWarning> {
+        let monitor = self.monitor.as_ref()?;
+        let now = Instant::now();
+        if state
+            .last_warning
+            .is_some_and(|last| now.duration_since(last) < self.config.warning_interval)
+        {
+            return None;
+        }
+        state.last_warning = Some(now);
+        Some(SlowConsumerWarning {
+            channel: monitor.channel,
+            subscription_id: monitor.subscription_id,
+            target: target.to_string(),
+            reason,
+            metrics: self.metrics(state),
+        })
+    }
+
+    /// Reports a warning from [`Shared::slow_consumer_warning`], closing the queue if the policy
+    /// evicts the consumer. Called without the queue locked, since the policy may read the
+    /// subscription's metrics.
+    fn report_slow_consumer(&self, warning: Option<SlowConsumerWarning>) {
+        let (Some(warning), Some(monitor)) = (warning, &self.monitor) else {
+            return;
+        };
+        warn!(
+            "Slow consumer on {:?}: {:?}",
+            warning.channel, warning.reason
+        );
+        let action = monitor
+            .policy
+            .as_ref()
+            .map(|policy| policy(&warning))
+            .unwrap_or_default();
+        let _ = monitor.events.send(WsEvent::SlowConsumer(warning));
+        if action == SlowConsumerAction::Evict {
+            let mut state = self.lock();
+            state.items.clear();
+            state.receiver_closed = true;
+            state.senders_closed = true;
+            self.item_ready.notify_one();
+            self.space_ready.notify_waiters();
+        }
+    }
+
+    /// Counts a message discarded by the overflow policy, returning the warning to report.
+    fn drop_message(&self, state: &mut QueueState, target: &str) -> Option<SlowConsumerWarning> {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        self.slow_consumer_warning(state, target, SlowConsumerReason::Dropped)
+    }
+
+    /// Reports a dropped message; `false` if the consumer is gone or got evicted.
+    fn report_drop(&self, warning: Option<SlowConsumerWarning>) -> bool {
+        self.report_slow_consumer(warning);
+        !self.lock().receiver_closed
+    }
+}
+
+/// Feeds a bounded subscription queue; the queue ends once every sender is dropped.
+pub struct QueueSender {
+    shared: Arc<Shared>,
+}
+
+impl QueueSender {
+    pub fn metrics(&self) -> SubscriptionMetrics {
+        self.shared.metrics(&self.shared.lock())
+    }
+
+    /// Queues a message routed by `target`, applying the overflow policy if the queue is full.
+    ///
+    /// Returns `false` once the receiving end has been dropped or evicted.
+    pub async fn send(&self, target: &str, msg: Bytes) -> bool {
+        let capacity = self.shared.config.capacity.max(1);
+        loop {
+            let (space_ready, warning) = {
+                let mut state = self.shared.lock();
+                if state.receiver_closed {
+                    return false;
+                }
+                let policy = self.shared.config.overflow;
+                if policy == OverflowPolicy::ConflateLatest {
+                    let queued = state.items.iter_mut().find_map(|item| match item {
+                        QueueItem::Message(t, queued) if t == target => Some(queued),
+                        _ => None,
+                    });
+                    if let Some(queued) = queued {
+                        *queued = msg;
+                        self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                        return true;
+                    }
+                }
+                if state.items.len() < capacity {
+                    state
+                        .items
+                        .push_back(QueueItem::Message(target.to_string(), msg));
+                    self.shared.item_ready.notify_one();
+                    return true;
+                }
+                match policy {
+                    OverflowPolicy::DropOldest | OverflowPolicy::ConflateLatest => {
+                        // The oldest message goes, but never a gap marker.
+                        if let Some(oldest) = state
+                            .items
+                            .iter()
+                            .position(|item| matches!(item, QueueItem::Message(..)))
+                        {
+                            state.items.remove(oldest);
+                        }
+                        state
+                            .items
+                            .push_back(QueueItem::Message(target.to_string(), msg));
+                        let warning = self.shared.drop_message(&mut state, target);
+                        drop(state);
+                        return self.shared.report_drop(warning);
+                    }
+                    OverflowPolicy::DropNewest => {
+                        let warning = self.shared.drop_message(&mut state, target);
+                        drop(state);
+                        return self.shared.report_drop(warning);
+                    }
+                    OverflowPolicy::Block => {
+                        // Created under the lock, so a message taken in between still wakes us.
+                        let space_ready = self.shared.space_ready.notified();
+                        let warning = self.shared.slow_consumer_warning(
+                            &mut state,
+                            target,
+                            SlowConsumerReason::Blocked,
+                        );
+                        (space_ready, warning)
+                    }
+                }
+            };
+            self.shared.report_slow_consumer(warning);
+            space_ready.await;
+        }
+    }
+}
+
+impl QueueSender {
+    /// Queues a gap marker, which the stream yields as [`ClientError::Gap`], unless the last
+    /// queued item is one already. Markers never wait and are never dropped.
+    pub fn mark_gap(&self) {
+        let mut state = self.shared.lock();
+        if state.receiver_closed || matches!(state.items.back(), Some(QueueItem::Gap)) {
+            return;
+        }
+        state.items.push_back(QueueItem::Gap);
+        self.shared.item_ready.notify_one();
+    }
+
+    /// Ends the queue once the messages already queued are taken, e.g. when the connection
+    /// feeding it is given up.
+    pub fn close(&self) {
+        self.shared.lock().senders_closed = true;
+        self.shared.item_ready.notify_one();
+    }
+}
+
+impl Drop for QueueSender {
+    fn drop(&mut self) {
+        self.shared.lock().senders_closed = true;
+        self.shared.item_ready.notify_one();
+    }
+}
+
+pub(crate) struct QueueReceiver {
+    shared: Arc<Shared>,
+}
+
+impl QueueReceiver {
+    pub(crate) async fn recv(&self) -> Option<QueueItem> {
+        loop {
+            let item_ready = {
+                let mut state = self.shared.lock();
+                if let Some(item) = state.items.pop_front() {
+                    self.shared.space_ready.notify_waiters();
+                    let mut warning = None;
+                    if let QueueItem::Message(target, msg) = &item {
+                        self.shared.delivered.fetch_add(1, Ordering::Relaxed);
+                        warning = self.record_lag(&mut state, target, msg);
+                    }
+                    drop(state);
+                    self.shared.report_slow_consumer(warning);
+                    return Some(item);
+                }
+                if state.senders_closed {
+                    return None;
+                }
+                self.shared.item_ready.notified()
+            };
+            item_ready.await;
+        }
+    }
+
+    /// Records the lag of a delivered message, returning the warning to report if it is late.
+    fn record_lag(
+        &self,
+        state: &mut QueueState,
+        target: &str,
+        msg: &Bytes,
+    ) -> Option<SlowConsumerWarning> {
+        let t = extract_timestamp(msg)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let lag = now.saturating_sub(t);
+        self.shared.max_lag_ms.fetch_max(lag, Ordering::Relaxed);
+        let lag = Duration::from_millis(lag);
+        if lag < self.shared.config.lag_warning {
+            return None;
+        }
+        self.shared
+            .slow_consumer_warning(state, target, SlowConsumerReason::Lagging(lag))
+    }
+}
+
+impl Drop for QueueReceiver {
+    fn drop(&mut self) {
+        self.shared.lock().receiver_closed = true;
+        self.shared.space_ready.notify_waiters();
+    }
+}
+
+/// Reads the metrics of a queue without keeping it open.
+#[derive(Clone)]
+pub(crate) struct QueueMonitor {
+    shared: Arc<Shared>,
+}
+
+impl QueueMonitor {
+    pub(crate) fn metrics(&self) -> SubscriptionMetrics {
+        self.shared.metrics(&self.shared.lock())
+    }
+}
+
+pub(crate) fn queue(
+    config: StreamConfig,
+    monitor: Option<SlowConsumerMonitor>,
+) -> (Arc<QueueSender>, QueueReceiver, QueueMonitor) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(QueueState::default()),
+        config,
+        monitor,
+        delivered: AtomicU64::new(0),
+        dropped: AtomicU64::new(0),
+        max_lag_ms: AtomicU64::new(0),
+        item_ready: Notify::new(),
+        space_ready: Notify::new(),
+    });
+    (
+        Arc::new(QueueSender {
+            shared: shared.clone(),
+        }),
+        QueueReceiver {
+            shared: shared.clone(),
+        },
+        QueueMonitor { shared },
+    )
+}
+
+pub(crate) fn parse_messages<T>(
+    receiver: QueueReceiver,
+) -> impl Stream<Item = Result<T, ClientError>> + Send + 'static
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    stream::unfold(receiver, |receiver| async move {
+        let parsed = match receiver.recv().await? {
+            QueueItem::Message(_, msg) => deserialise_to_type(&msg).map_err(ClientError::from),
+            QueueItem::Gap => Err(ClientError::Gap),
+        };
+        Some((parsed, receiver))
+    })
+}
+
+/// Creates a bounded queue and the stream of parsed messages read from it.
+pub fn subscription_queue<T>(
+    config: StreamConfig,
+) -> (
+    Arc<QueueSender>,
+    impl Stream<Item = Result<T, ClientError>> + Send + 'static,
+)
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let (sender, receiver, _) = queue(config, None);
+    (sender, parse_messages(receiver))
+}
+
+/// Like [`subscription_queue`], but reports slow consumers to `monitor`.
+pub fn monitored_subscription_queue<T>(
+    config: StreamConfig,
+    monitor: SlowConsumerMonitor,
+) -> (
+    Arc<QueueSender>,
+    impl Stream<Item = Result<T, ClientError>> + Send + 'static,
+)
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let (sender, receiver, _) = queue(config, Some(monitor));
+    (sender, parse_messages(receiver))
+}
+
+/// Messages of a subscription, read from a bounded queue.
+///
+/// The stream ends once all its targets are unsubscribed; dropping it unsubscribes. After a
+/// reconnect it yields [`ClientError::Gap`] before the messages of the new connection, since any
+/// sent in between were missed.
+pub struct SubscriptionStream<T> {
+    // Dropped first, so the routes are removed and unsubscribed while the queue is still open.
+    handle: SubscriptionHandle,
+    messages: Pin<Box<dyn Stream<Item = Result<T, ClientError>> + Send>>,
+}
+
+impl<T> SubscriptionStream<T> {
+    pub(crate) fn new(
+        handle: SubscriptionHandle,
+        messages: impl Stream<Item = Result<T, ClientError>> + Send + 'static,
+    ) -> Self {
+        Self {
+            handle,
+            messages: Box::pin(messages),
+        }
+    }
+
+    pub fn handle(&self) -> &SubscriptionHandle {
+        &self.handle
+    }
+
+    /// For unsubscribing from single targets while still reading the others.
+    pub fn handle_mut(&mut self) -> &mut SubscriptionHandle {
+        &mut self.handle
+    }
+
+    pub fn metrics(&self) -> SubscriptionMetrics {
+        self.handle.metrics()
+    }
+
+    /// Unsubscribes from every target, waiting for the server's acknowledgements.
+    pub async fn unsubscribe(self) -> Result<(), ClientError> {
+        self.handle.unsubscribe().await
+    }
+}
+
+impl<T> Stream for SubscriptionStream<T> {
+    type Item = Result<T, ClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.messages.as_mut().poll_next(cx)
+    }
+}
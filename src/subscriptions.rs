@@ -1,214 +1,346 @@
 use std::future::Future;
 
-use bytes::Bytes;
-
 use crate::{
     channels::Channels,
     models::{
         L2BookMessage, OrderFillMessage, OrderUpdateMessage, PositionUpdateMessage,
         SubaccountLiquidationMessage, TickerMessage, TokenTransferMessage, TradeFillMessage,
     },
+    subscription_stream::{StreamConfig, SubscriptionStream},
     types::{ProductSubscriptionMessage, SubaccountSubscriptionMessage},
-    ws_client::{ClientError, WsClient},
+    ws_client::{ClientError, Subscriber, SubscriptionHandle, WsClient},
 };
 
-pub struct Subscriptions<'a> {
-    pub client: &'a WsClient,
+/// Typed subscriptions to every channel, made through a [`WsClient`] or a
+/// [`crate::ws_pool::WsPool`].
+pub struct Subscriptions<'a, C = WsClient> {
+    pub client: &'a C,
+    pub stream_config: StreamConfig,
 }
-impl<'a> Subscriptions<'a> {
+impl<'a, C: Subscriber> Subscriptions<'a, C> {
+    /// Queue settings of the callback and `*_stream` subscriptions made from here.
+    pub fn stream_config(mut self, stream_config: StreamConfig) -> Self {
+        self.stream_config = stream_config;
+        self
+    }
+
     pub async fn l2_book<F, Fut>(
         &self,
         symbols: Vec<String>,
         callback: F,
-    ) -> Result<(), ClientError>
+    ) -> Result<SubscriptionHandle, ClientError>
     where
         F: FnMut(L2BookMessage) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let payloads = symbols
+        let requests = symbols
+            .iter()
+            .map(|i| ProductSubscriptionMessage {
+                msg_type: Channels::L2Book,
+                symbol: i.to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.client
+            .subscribe_channels(Channels::L2Book, requests, self.stream_config, callback)
+            .await
+    }
+
+    pub async fn l2_book_stream(
+        &self,
+        symbols: Vec<String>,
+    ) -> Result<SubscriptionStream<L2BookMessage>, ClientError> {
+        let requests = symbols
             .iter()
-            .map(|i| {
-                ProductSubscriptionMessage {
-                    msg_type: Channels::L2Book,
-                    symbol: i.to_string(),
-                }
-                .into()
+            .map(|i| ProductSubscriptionMessage {
+                msg_type: Channels::L2Book,
+                symbol: i.to_string(),
             })
-            .collect::<Vec<Bytes>>();
+            .collect::<Vec<_>>();
         self.client
-            .subscribe_channels(Channels::L2Book, payloads, callback)
-            .await?;
-        Ok(())
+            .subscribe_stream(Channels::L2Book, requests, self.stream_config)
+            .await
     }
 
     pub async fn order_fill<F, Fut>(
         &self,
         subaccount_ids: Vec<String>,
         callback: F,
-    ) -> Result<(), ClientError>
+    ) -> Result<SubscriptionHandle, ClientError>
     where
         F: FnMut(OrderFillMessage) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let payloads = subaccount_ids
+        let requests = subaccount_ids
             .iter()
-            .map(|i| {
-                SubaccountSubscriptionMessage {
-                    msg_type: Channels::OrderFill,
-                    subaccount_id: i.to_string(),
-                }
-                .into()
+            .map(|i| SubaccountSubscriptionMessage {
+                msg_type: Channels::OrderFill,
+                subaccount_id: i.to_string(),
             })
-            .collect::<Vec<Bytes>>();
+            .collect::<Vec<_>>();
         self.client
-            .subscribe_channels(Channels::OrderFill, payloads, callback)
-            .await?;
-        Ok(())
+            .subscribe_channels(Channels::OrderFill, requests, self.stream_config, callback)
+            .await
+    }
+
+    pub async fn order_fill_stream(
+        &self,
+        subaccount_ids: Vec<String>,
+    ) -> Result<SubscriptionStream<OrderFillMessage>, ClientError> {
+        let requests = subaccount_ids
+            .iter()
+            .map(|i| SubaccountSubscriptionMessage {
+                msg_type: Channels::OrderFill,
+                subaccount_id: i.to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.client
+            .subscribe_stream(Channels::OrderFill, requests, self.stream_config)
+            .await
     }
 
     pub async fn order_update<F, Fut>(
         &self,
         subaccount_ids: Vec<String>,
         callback: F,
-    ) -> Result<(), ClientError>
+    ) -> Result<SubscriptionHandle, ClientError>
     where
         F: FnMut(OrderUpdateMessage) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let payloads = subaccount_ids
+        let requests = subaccount_ids
+            .iter()
+            .map(|i| SubaccountSubscriptionMessage {
+                msg_type: Channels::OrderUpdate,
+                subaccount_id: i.to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.client
+            .subscribe_channels(
+                Channels::OrderUpdate,
+                requests,
+                self.stream_config,
+                callback,
+            )
+            .await
+    }
+
+    pub async fn order_update_stream(
+        &self,
+        subaccount_ids: Vec<String>,
+    ) -> Result<SubscriptionStream<OrderUpdateMessage>, ClientError> {
+        let requests = subaccount_ids
             .iter()
-            .map(|i| {
-                SubaccountSubscriptionMessage {
-                    msg_type: Channels::OrderUpdate,
-                    subaccount_id: i.to_string(),
-                }
-                .into()
+            .map(|i| SubaccountSubscriptionMessage {
+                msg_type: Channels::OrderUpdate,
+                subaccount_id: i.to_string(),
             })
-            .collect::<Vec<Bytes>>();
+            .collect::<Vec<_>>();
         self.client
-            .subscribe_channels(Channels::OrderUpdate, payloads, callback)
-            .await?;
-        Ok(())
+            .subscribe_stream(Channels::OrderUpdate, requests, self.stream_config)
+            .await
     }
 
     pub async fn position_update<F, Fut>(
         &self,
         subaccount_ids: Vec<String>,
         callback: F,
-    ) -> Result<(), ClientError>
+    ) -> Result<SubscriptionHandle, ClientError>
     where
         F: FnMut(PositionUpdateMessage) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let payloads = subaccount_ids
+        let requests = subaccount_ids
+            .iter()
+            .map(|i| SubaccountSubscriptionMessage {
+                msg_type: Channels::PositionUpdate,
+                subaccount_id: i.to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.client
+            .subscribe_channels(
+                Channels::PositionUpdate,
+                requests,
+                self.stream_config,
+                callback,
+            )
+            .await
+    }
+
+    pub async fn position_update_stream(
+        &self,
+        subaccount_ids: Vec<String>,
+    ) -> Result<SubscriptionStream<PositionUpdateMessage>, ClientError> {
+        let requests = subaccount_ids
             .iter()
-            .map(|i| {
-                SubaccountSubscriptionMessage {
-                    msg_type: Channels::PositionUpdate,
-                    subaccount_id: i.to_string(),
-                }
-                .into()
+            .map(|i| SubaccountSubscriptionMessage {
+                msg_type: Channels::PositionUpdate,
+                subaccount_id: i.to_string(),
             })
-            .collect::<Vec<Bytes>>();
+            .collect::<Vec<_>>();
         self.client
-            .subscribe_channels(Channels::PositionUpdate, payloads, callback)
-            .await?;
-        Ok(())
+            .subscribe_stream(Channels::PositionUpdate, requests, self.stream_config)
+            .await
     }
 
     pub async fn subaccount_liquidation<F, Fut>(
         &self,
         subaccount_ids: Vec<String>,
         callback: F,
-    ) -> Result<(), ClientError>
+    ) -> Result<SubscriptionHandle, ClientError>
     where
         F: FnMut(SubaccountLiquidationMessage) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let payloads = subaccount_ids
+        let requests = subaccount_ids
             .iter()
-            .map(|i| {
-                SubaccountSubscriptionMessage {
-                    msg_type: Channels::SubaccountLiquidation,
-                    subaccount_id: i.to_string(),
-                }
-                .into()
+            .map(|i| SubaccountSubscriptionMessage {
+                msg_type: Channels::SubaccountLiquidation,
+                subaccount_id: i.to_string(),
             })
-            .collect::<Vec<Bytes>>();
+            .collect::<Vec<_>>();
         self.client
-            .subscribe_channels(Channels::SubaccountLiquidation, payloads, callback)
-            .await?;
-        Ok(())
+            .subscribe_channels(
+                Channels::SubaccountLiquidation,
+                requests,
+                self.stream_config,
+                callback,
+            )
+            .await
     }
 
-    pub async fn ticker<F, Fut>(&self, symbols: Vec<String>, callback: F) -> Result<(), ClientError>
+    pub async fn subaccount_liquidation_stream(
+        &self,
+        subaccount_ids: Vec<String>,
+    ) -> Result<SubscriptionStream<SubaccountLiquidationMessage>, ClientError> {
+        let requests = subaccount_ids
+            .iter()
+            .map(|i| SubaccountSubscriptionMessage {
+                msg_type: Channels::SubaccountLiquidation,
+                subaccount_id: i.to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.client
+            .subscribe_stream(
+                Channels::SubaccountLiquidation,
+                requests,
+                self.stream_config,
+            )
+            .await
+    }
+
+    pub async fn ticker<F, Fut>(
+        &self,
+        symbols: Vec<String>,
+        callback: F,
+    ) -> Result<SubscriptionHandle, ClientError>
     where
         F: FnMut(TickerMessage) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let payloads = symbols
+        let requests = symbols
             .iter()
-            .map(|i| {
-                ProductSubscriptionMessage {
-                    msg_type: Channels::Ticker,
-                    symbol: i.to_string(),
-                }
-                .into()
+            .map(|i| ProductSubscriptionMessage {
+                msg_type: Channels::Ticker,
+                symbol: i.to_string(),
             })
-            .collect::<Vec<Bytes>>();
+            .collect::<Vec<_>>();
         self.client
-            .subscribe_channels(Channels::Ticker, payloads, callback)
-            .await?;
-        Ok(())
+            .subscribe_channels(Channels::Ticker, requests, self.stream_config, callback)
+            .await
+    }
+
+    pub async fn ticker_stream(
+        &self,
+        symbols: Vec<String>,
+    ) -> Result<SubscriptionStream<TickerMessage>, ClientError> {
+        let requests = symbols
+            .iter()
+            .map(|i| ProductSubscriptionMessage {
+                msg_type: Channels::Ticker,
+                symbol: i.to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.client
+            .subscribe_stream(Channels::Ticker, requests, self.stream_config)
+            .await
     }
 
     pub async fn token_transfer<F, Fut>(
         &self,
         subaccount_ids: Vec<String>,
         callback: F,
-    ) -> Result<(), ClientError>
+    ) -> Result<SubscriptionHandle, ClientError>
     where
         F: FnMut(TokenTransferMessage) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let payloads = subaccount_ids
+        let requests = subaccount_ids
+            .iter()
+            .map(|i| SubaccountSubscriptionMessage {
+                msg_type: Channels::TokenTransfer,
+                subaccount_id: i.to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.client
+            .subscribe_channels(
+                Channels::TokenTransfer,
+                requests,
+                self.stream_config,
+                callback,
+            )
+            .await
+    }
+
+    pub async fn token_transfer_stream(
+        &self,
+        subaccount_ids: Vec<String>,
+    ) -> Result<SubscriptionStream<TokenTransferMessage>, ClientError> {
+        let requests = subaccount_ids
             .iter()
-            .map(|i| {
-                SubaccountSubscriptionMessage {
-                    msg_type: Channels::TokenTransfer,
-                    subaccount_id: i.to_string(),
-                }
-                .into()
+            .map(|i| SubaccountSubscriptionMessage {
+                msg_type: Channels::TokenTransfer,
+                subaccount_id: i.to_string(),
             })
-            .collect::<Vec<Bytes>>();
+            .collect::<Vec<_>>();
         self.client
-            .subscribe_channels(Channels::TokenTransfer, payloads, callback)
-            .await?;
-        Ok(())
+            .subscribe_stream(Channels::TokenTransfer, requests, self.stream_config)
+            .await
     }
 
     pub async fn trade_fill<F, Fut>(
         &self,
         symbols: Vec<String>,
         callback: F,
-    ) -> Result<(), ClientError>
+    ) -> Result<SubscriptionHandle, ClientError>
     where
         F: FnMut(TradeFillMessage) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let payloads = symbols
+        let requests = symbols
+            .iter()
+            .map(|i| ProductSubscriptionMessage {
+                msg_type: Channels::TradeFill,
+                symbol: i.to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.client
+            .subscribe_channels(Channels::TradeFill, requests, self.stream_config, callback)
+            .await
+    }
+
+    pub async fn trade_fill_stream(
+        &self,
+        symbols: Vec<String>,
+    ) -> Result<SubscriptionStream<TradeFillMessage>, ClientError> {
+        let requests = symbols
             .iter()
-            .map(|i| {
-                ProductSubscriptionMessage {
-                    msg_type: Channels::TradeFill,
-                    symbol: i.to_string(),
-                }
-                .into()
+            .map(|i| ProductSubscriptionMessage {
+                msg_type: Channels::TradeFill,
+                symbol: i.to_string(),
             })
-            .collect::<Vec<Bytes>>();
+            .collect::<Vec<_>>();
         self.client
-            .subscribe_channels(Channels::TradeFill, payloads, callback)
-            .await?;
-        Ok(())
+            .subscribe_stream(Channels::TradeFill, requests, self.stream_config)
+            .await
     }
 }
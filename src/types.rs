@@ -1,8 +1,9 @@
 use bytes::Bytes;
-use serde::Serialize;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use tokio::sync::oneshot;
 
-use crate::channels::Channels;
+use crate::{channels::Channels, ws_client::ClientError};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -13,12 +14,7 @@ pub struct ProductSubscriptionMessage {
 }
 impl From<ProductSubscriptionMessage> for Bytes {
     fn from(val: ProductSubscriptionMessage) -> Self {
-        let msg: SubscriptionMessage<ProductSubscriptionMessage> = SubscriptionMessage {
-            event: "subscribe".to_string(),
-            data: val,
-        };
-        let json = serde_json::to_string(&msg).expect("json");
-        Bytes::from(json)
+        val.payload("subscribe")
     }
 }
 impl ProductSubscriptionMessage {
@@ -40,12 +36,7 @@ impl SubaccountSubscriptionMessage {
 }
 impl From<SubaccountSubscriptionMessage> for Bytes {
     fn from(val: SubaccountSubscriptionMessage) -> Self {
-        let msg: SubscriptionMessage<SubaccountSubscriptionMessage> = SubscriptionMessage {
-            event: "subscribe".to_string(),
-            data: val,
-        };
-        let json = serde_json::to_string(&msg).expect("json");
-        Bytes::from(json)
+        val.payload("subscribe")
     }
 }
 
@@ -56,4 +47,96 @@ pub struct SubscriptionMessage<T> {
     pub data: T,
 }
 
+/// Data of a subscribe or unsubscribe request for one symbol or subaccount of a channel.
+pub trait SubscriptionRequest: Serialize + Sized {
+    /// The symbol or subaccount id subscribed to.
+    fn target(&self) -> &str;
+
+    /// The request envelope for `event`, i.e. `"subscribe"` or `"unsubscribe"`.
+    fn payload(&self, event: &str) -> Bytes {
+        let msg = SubscriptionMessage {
+            event: event.to_string(),
+            data: self,
+        };
+        let json = serde_json::to_string(&msg).expect("json");
+        Bytes::from(json)
+    }
+}
+
+impl SubscriptionRequest for ProductSubscriptionMessage {
+    fn target(&self) -> &str {
+        &self.symbol
+    }
+}
+
+impl SubscriptionRequest for SubaccountSubscriptionMessage {
+    fn target(&self) -> &str {
+        &self.subaccount_id
+    }
+}
+
+/// Server reply to a subscribe or unsubscribe request.
+#[derive(Debug, Default, Deserialize)]
+pub struct SubscriptionAck {
+    #[serde(default)]
+    pub ok: Option<bool>,
+    #[serde(default)]
+    pub error: Option<serde_json::Value>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl SubscriptionAck {
+    /// `Err` if the server reported an error or `"ok": false`.
+    pub fn into_result(self) -> Result<(), ClientError> {
+        match self.error {
+            None | Some(serde_json::Value::Null) => {}
+            Some(serde_json::Value::String(error)) => {
+                return Err(ClientError::SubscriptionError(error))
+            }
+            Some(error) => {
+                let message = error
+                    .get("message")
+                    .and_then(|m| m.as_str())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string());
+                return Err(ClientError::SubscriptionError(message));
+            }
+        }
+        if self.ok == Some(false) {
+            return Err(ClientError::SubscriptionError(
+                self.message
+                    .unwrap_or_else(|| "subscription rejected".to_string()),
+            ));
+        }
+        Ok(())
+    }
+}
+
 pub type ResponseSender = oneshot::Sender<Bytes>;
+
+/// One `[price, quantity]` level of an order book, (de)serialized as a two element array.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PriceLevel {
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+impl PriceLevel {
+    pub fn new(price: Decimal, quantity: Decimal) -> Self {
+        Self { price, quantity }
+    }
+}
+
+impl Serialize for PriceLevel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.price, self.quantity).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PriceLevel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (price, quantity) = <(Decimal, Decimal)>::deserialize(deserializer)?;
+        Ok(Self { price, quantity })
+    }
+}
@@ -7,7 +7,7 @@ pub async fn create_client(
     private_key: &str,
     owner_address: Option<String>,
 ) -> anyhow::Result<(HttpClient, WsClient)> {
-    let http_client = HttpClient::new(env, private_key, owner_address).await;
+    let http_client = HttpClient::new(env, private_key, owner_address).await?;
     let ws_client = WsClient::new(env);
     Ok((http_client, ws_client))
 }
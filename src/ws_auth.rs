@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use ethers::{
+    signers::{LocalWallet, Signer, WalletError},
+    utils::hex,
+};
+use thiserror::Error;
+
+use crate::{
+    enums::Environment,
+    signable_messages::EIP712Auth,
+    signing::{get_now, Eip712},
+    types::SubscriptionAck,
+};
+
+/// Why a WebSocket session could not be authenticated.
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum AuthError {
+    #[error("Failed to sign the auth message: {0}")]
+    Signing(String),
+    #[error("Authentication rejected: {0}")]
+    Rejected(String),
+    #[error("No auth response within {0:?}")]
+    Timeout(Duration),
+    #[error("The client was created without a signer")]
+    NotConfigured,
+}
+
+/// Authentication of the current connection, see
+/// [`crate::ws_client::WsClient::wait_for_authentication`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuthState {
+    /// Not connected yet, or waiting for the server to accept the auth request.
+    Unauthenticated,
+    Authenticated,
+    Failed(AuthError),
+}
+
+/// Signer used to authenticate a [`crate::ws_client::WsClient`] session for private channels.
+///
+/// The session is authenticated on every connect and again before `session_ttl` runs out.
+///
+/// **Unverified:** the API spec documents neither a WebSocket auth request nor an
+/// [`EIP712Auth`] intent for private streams; it only lists the referral and points intents (see
+/// [`crate::models::Eip712AuthIntent`]). The `{"event": "auth", "data": ...}` request sent here
+/// mirrors the `X-Ethereal-*` headers of the authenticated REST endpoints, and the intent is
+/// whatever the caller passes, so a server which expects another format rejects the session with
+/// [`AuthError::Rejected`] or ignores it.
+#[derive(Clone, Debug)]
+pub struct WsAuth {
+    pub wallet: LocalWallet,
+    /// Bytes32 encoded subaccount name, set when `wallet` is a linked signer of that subaccount.
+    pub subaccount: Option<String>,
+    /// Intent signed into the auth message, as assigned by the server for private streams.
+    pub intent: u8,
+    /// How long a signed auth message is accepted by the server.
+    pub session_ttl: Duration,
+    /// Re-authenticate this long before the session expires.
+    pub refresh_margin: Duration,
+}
+
+impl WsAuth {
+    pub fn new(private_key: &str, intent: u8) -> Result<Self, WalletError> {
+        Ok(Self::from_wallet(private_key.parse()?, intent))
+    }
+
+    pub fn from_wallet(wallet: LocalWallet, intent: u8) -> Self {
+        Self {
+            wallet,
+            subaccount: None,
+            intent,
+            session_ttl: Duration::from_secs(3600),
+            refresh_margin: Duration::from_secs(60),
+        }
+    }
+
+    /// Sign as a linked signer of the subaccount with this bytes32 encoded name.
+    pub fn linked_signer(mut self, subaccount: impl Into<String>) -> Self {
+        self.subaccount = Some(subaccount.into());
+        self
+    }
+
+    pub fn intent(mut self, intent: u8) -> Self {
+        self.intent = intent;
+        self
+    }
+
+    pub fn session_ttl(mut self, session_ttl: Duration, refresh_margin: Duration) -> Self {
+        self.session_ttl = session_ttl;
+        self.refresh_margin = refresh_margin;
+        self
+    }
+
+    /// Time after a successful authentication when the session is renewed.
+    pub fn refresh_interval(&self) -> Duration {
+        self.session_ttl
+            .saturating_sub(self.refresh_margin)
+            .max(Duration::from_secs(1))
+    }
+
+    /// Signs a fresh [`EIP712Auth`] message and wraps it in an `auth` request; see the
+    /// unverified format above.
+    pub fn request(&self, env: Environment) -> Result<Bytes, AuthError> {
+        let signed_at = get_now() as u64;
+        let message = EIP712Auth {
+            sender: self.wallet.address(),
+            intent: self.intent,
+            signed_at,
+        };
+        let signature = message
+            .sign(env, &self.wallet)
+            .map_err(|e| AuthError::Signing(e.to_string()))?;
+        let mut data = serde_json::json!({
+            "auth": "EIP712Auth",
+            "sender": format!("{:?}", self.wallet.address()),
+            "signature": "0x".to_string() + &hex::encode(signature.to_vec()),
+            "intent": self.intent,
+            "signedAt": signed_at.to_string(),
+        });
+        if let Some(subaccount) = &self.subaccount {
+            data["subaccount"] = subaccount.clone().into();
+        }
+        let request = serde_json::json!({ "event": "auth", "data": data });
+        Ok(Bytes::from(request.to_string()))
+    }
+}
+
+/// Reads the server's reply to an `auth` request.
+pub(crate) fn parse_auth_response(response: &Bytes) -> Result<(), AuthError> {
+    serde_json::from_slice::<SubscriptionAck>(response)
+        .map_err(|e| AuthError::Rejected(format!("unreadable response: {e}")))?
+        .into_result()
+        .map_err(|e| match e {
+            crate::ws_client::ClientError::SubscriptionError(message) => {
+                AuthError::Rejected(message)
+            }
+            e => AuthError::Rejected(e.to_string()),
+        })
+}
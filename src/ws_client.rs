@@ -2,6 +2,7 @@ use bytes::Bytes;
 use log::{debug, error, info, warn};
 use serde::de::DeserializeOwned;
 use std::{
+    collections::HashMap,
     future::Future,
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -17,7 +18,7 @@ use thiserror::Error;
 use tokio::{
     net::TcpStream,
     sync::{
-        mpsc::{self, UnboundedSender},
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
         oneshot, watch, Mutex,
     },
     task::JoinHandle,
@@ -25,17 +26,31 @@ use tokio::{
 };
 use yawc::{Frame, MaybeTlsStream, OpCode, Options, WebSocket};
 
+/// Re-exported, since `ws_auth` is only public with the `experimental-ws-auth` feature.
+pub use crate::ws_auth::{AuthError, AuthState};
 use crate::{
     // channels::public_channels,
     channels::Channels,
     enums::Environment,
-    routing::extract_event,
+    routing::{
+        extract_event, extract_request_id, extract_subaccount_id, extract_symbol, route_key,
+    },
+    subscription_stream::{
+        parse_messages, queue, QueueItem, QueueMonitor, QueueReceiver, QueueSender,
+        SlowConsumerAction, SlowConsumerMonitor, SlowConsumerPolicy, SlowConsumerWarning,
+        StreamConfig, SubscriptionMetrics, SubscriptionStream,
+    },
     subscriptions::Subscriptions,
-    types::ResponseSender, // utils::{get_server_url, get_typed_callback},
+    types::{ResponseSender, SubscriptionAck, SubscriptionRequest}, // utils::{get_server_url, get_typed_callback},
+    ws_auth::{parse_auth_response, WsAuth},
+    ws_config::WsConfig,
 };
 
-const PING_INTERVAL: Duration = Duration::from_secs(5);
-const READ_TIMEOUT: Duration = Duration::from_secs(7);
+pub const DEFAULT_RPC_TIMEOUT: Duration = Duration::from_secs(10);
+/// Requests and control commands queued for the connection before senders have to wait.
+pub const COMMAND_QUEUE_CAPACITY: usize = 1024;
+
+static NEXT_SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(1);
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum ConnectionState {
@@ -44,6 +59,25 @@ pub enum ConnectionState {
     Reconnecting,
     Exited,
 }
+
+/// What [`WsClient::run_till_event`] returns.
+#[derive(Clone, Debug)]
+pub enum WsEvent {
+    Connection(ConnectionState),
+    /// A subscription's consumer fell behind; see [`WsClient::with_slow_consumer_policy`].
+    SlowConsumer(SlowConsumerWarning),
+    /// The supervisor replayed the subscriptions after a reconnect; holds their
+    /// `"<channel>:<symbol or subaccount id>"` route keys.
+    Resubscribed {
+        restored: Vec<String>,
+        failed: Vec<String>,
+    },
+    /// The server accepted the session's auth request, on connect or when renewing it.
+    Authenticated,
+    /// The session could not be authenticated; private channels will not deliver messages.
+    AuthFailed(AuthError),
+}
+
 pub enum InternalCommand {
     Send(Frame),
     Close,
@@ -61,20 +95,424 @@ pub enum ClientError {
     Transport(#[from] Box<dyn std::error::Error + Send + Sync>),
     #[error("Deserialization error: {0}")]
     Deserialization(#[from] serde_json::Error),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("No response within {0:?}")]
+    Timeout(Duration),
+    #[error("Connection closed before a response was received")]
+    Disconnected,
+    /// Yielded by a subscription stream after a reconnect: messages sent while disconnected were
+    /// missed, so state built from the stream should be resynced.
+    #[error("Messages may have been missed while reconnecting")]
+    Gap,
+    #[error("Authentication error: {0}")]
+    Auth(#[from] AuthError),
+    /// The server answered a request, e.g. an order submission, with an error.
+    #[error("Request rejected: {0}")]
+    RequestRejected(String),
+}
+/// A symbol or subaccount subscribed to on a channel, with its subscribe and unsubscribe requests.
+#[derive(Clone, Debug)]
+pub struct SubscribedTarget {
+    pub target: String,
+    pub subscribe: Bytes,
+    pub unsubscribe: Bytes,
+}
+
+impl SubscribedTarget {
+    pub(crate) fn new<R: SubscriptionRequest>(request: &R) -> Self {
+        Self {
+            target: request.target().to_string(),
+            subscribe: request.payload("subscribe"),
+            unsubscribe: request.payload("unsubscribe"),
+        }
+    }
+}
+
+/// Delivers one subscription's messages for a route into its bounded queue, which feeds either
+/// a callback task or a [`SubscriptionStream`].
+#[derive(Clone)]
+pub struct RouteSender {
+    pub subscription_id: u64,
+    pub queue: Arc<QueueSender>,
+}
+
+/// Subscriptions to one symbol or subaccount of a channel.
+pub struct ChannelRoute {
+    pub senders: Vec<RouteSender>,
+    pub target: SubscribedTarget,
+    /// Whether the server acknowledged the subscribe request. Until then the request is queued
+    /// or in flight, so a reconnect leaves it to its subscriber instead of replaying it too.
+    pub confirmed: bool,
+}
+
+/// Routes keyed by [`route_key`], i.e. `"<channel>:<symbol or subaccount id>"`.
+type SubscriptionMap = Arc<DashMap<String, ChannelRoute>>;
+
+/// Sends requests over the connection and matches up their responses; cheap to clone across tasks.
+#[derive(Clone)]
+pub(crate) struct RpcSender {
+    write_tx: mpsc::Sender<InternalCommand>,
+    pending_requests: Arc<DashMap<u64, ResponseSender>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl RpcSender {
+    /// Queues a frame without waiting, failing if the command queue is full.
+    pub(crate) fn send_nowait(&self, msg: Bytes) -> Result<(), ClientError> {
+        self.write_tx
+            .try_send(InternalCommand::Send(Frame::text(msg)))
+            .map_err(|e| ClientError::Transport(e.to_string().into()))
+    }
+
+    /// Queues a frame, waiting for room in the command queue.
+    pub(crate) async fn send(&self, msg: Bytes) -> Result<(), ClientError> {
+        self.write_tx
+            .send(InternalCommand::Send(Frame::text(msg)))
+            .await
+            .map_err(|e| ClientError::Transport(e.to_string().into()))
+    }
+
+    pub(crate) async fn request<T>(&self, msg: Bytes, timeout: Duration) -> Result<T, ClientError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = with_request_id(&msg, id)?;
+
+        let (tx, rx) = oneshot::channel::<Bytes>();
+        self.pending_requests.insert(id, tx);
+        let _guard = PendingRequestGuard {
+            id,
+            pending_requests: &self.pending_requests,
+        };
+
+        self.send(request).await?;
+
+        let resp = match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(resp)) => resp,
+            Ok(Err(_)) => return Err(ClientError::Disconnected),
+            Err(_) => {
+                warn!("RPC request {id} timed out after {timeout:?}");
+                return Err(ClientError::Timeout(timeout));
+            }
+        };
+
+        let envelope: T = deserialise_to_type(&resp)?;
+        Ok(envelope)
+    }
+
+    /// Sends a subscribe or unsubscribe request and waits for the server to acknowledge it.
+    pub(crate) async fn subscription_request(&self, payload: Bytes) -> Result<(), ClientError> {
+        self.request::<SubscriptionAck>(payload, DEFAULT_RPC_TIMEOUT)
+            .await?
+            .into_result()
+    }
+}
+
+/// Removes the subscription `owner`, or every subscription without an owner, from each target's
+/// route, returning the targets left without any subscription, which should be unsubscribed.
+fn remove_targets(
+    subs: &SubscriptionMap,
+    channel: Channels,
+    owner: Option<u64>,
+    targets: &[String],
+) -> Vec<SubscribedTarget> {
+    let channel = channel.as_string();
+    let mut removed = Vec::new();
+    for target in targets {
+        let key = route_key(&channel, target);
+        let now_empty = match subs.get_mut(&key) {
+            Some(mut route) => {
+                match owner {
+                    Some(owner) => route.senders.retain(|s| s.subscription_id != owner),
+                    None => route.senders.clear(),
+                }
+                route.senders.is_empty()
+            }
+            None => false,
+        };
+        if now_empty {
+            // Dropping the last sender ends the subscription's callback task or stream.
+            if let Some((_, route)) = subs.remove_if(&key, |_, route| route.senders.is_empty()) {
+                removed.push(route.target);
+            }
+        }
+    }
+    removed
+}
+
+/// Unsubscribes from every target, returning the first error after trying them all.
+async fn send_unsubscribes(
+    rpc: &RpcSender,
+    channel: Channels,
+    targets: Vec<SubscribedTarget>,
+) -> Result<(), ClientError> {
+    let mut result = Ok(());
+    for target in targets {
+        debug!("Unsubscribing from {channel:?}: {}", target.target);
+        if let Err(e) = rpc.subscription_request(target.unsubscribe).await {
+            error!(
+                "Failed to unsubscribe from {channel:?} {}: {e}",
+                target.target
+            );
+            if result.is_ok() {
+                result = Err(e);
+            }
+        }
+    }
+    result
+}
+
+/// Anything the typed [`Subscriptions`] helpers subscribe through: a [`WsClient`] or a
+/// [`crate::ws_pool::WsPool`].
+pub trait Subscriber {
+    /// See [`WsClient::subscribe_channels`].
+    fn subscribe_channels<R, P, F, Fut>(
+        &self,
+        event: Channels,
+        requests: Vec<R>,
+        config: StreamConfig,
+        callback: F,
+    ) -> impl Future<Output = Result<SubscriptionHandle, ClientError>> + Send
+    where
+        R: SubscriptionRequest + Send,
+        P: DeserializeOwned + Send + 'static,
+        F: FnMut(P) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static;
+
+    /// See [`WsClient::subscribe_stream`].
+    fn subscribe_stream<R, P>(
+        &self,
+        event: Channels,
+        requests: Vec<R>,
+        config: StreamConfig,
+    ) -> impl Future<Output = Result<SubscriptionStream<P>, ClientError>> + Send
+    where
+        R: SubscriptionRequest + Send,
+        P: DeserializeOwned + Send + 'static;
+}
+
+impl Subscriber for WsClient {
+    fn subscribe_channels<R, P, F, Fut>(
+        &self,
+        event: Channels,
+        requests: Vec<R>,
+        config: StreamConfig,
+        callback: F,
+    ) -> impl Future<Output = Result<SubscriptionHandle, ClientError>> + Send
+    where
+        R: SubscriptionRequest + Send,
+        P: DeserializeOwned + Send + 'static,
+        F: FnMut(P) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        WsClient::subscribe_channels(self, event, requests, config, callback)
+    }
+
+    fn subscribe_stream<R, P>(
+        &self,
+        event: Channels,
+        requests: Vec<R>,
+        config: StreamConfig,
+    ) -> impl Future<Output = Result<SubscriptionStream<P>, ClientError>> + Send
+    where
+        R: SubscriptionRequest + Send,
+        P: DeserializeOwned + Send + 'static,
+    {
+        WsClient::subscribe_stream(self, event, requests, config)
+    }
+}
+
+/// The routes and request sender of one connection, through which a subscription's targets are
+/// routed.
+#[derive(Clone)]
+pub(crate) struct Connection {
+    subs: SubscriptionMap,
+    rpc: RpcSender,
+}
+
+impl Connection {
+    /// Unsubscribes a routed target and subscribes to it again, so the server sends a fresh
+    /// snapshot. Subscriptions other than `owner` sharing the target are told of the gap.
+    ///
+    /// The route is left as is. Returns `Ok(false)` without sending if nothing is subscribed to
+    /// the target any more.
+    pub(crate) async fn resubscribe(
+        &self,
+        channel: Channels,
+        target: &str,
+        owner: u64,
+    ) -> Result<bool, ClientError> {
+        let key = route_key(&channel.as_string(), target);
+        let target = match self.subs.get(&key) {
+            Some(route) => route.target.clone(),
+            None => return Ok(false),
+        };
+        self.rpc.subscription_request(target.unsubscribe).await?;
+        // The route is kept, so messages keep flowing to the other subscriptions once the
+        // subscribe below is acknowledged, but they miss whatever was sent in between.
+        if let Some(route) = self.subs.get(&key) {
+            for sender in route.senders.iter().filter(|s| s.subscription_id != owner) {
+                sender.queue.mark_gap();
+            }
+        }
+        self.rpc.subscription_request(target.subscribe).await?;
+        Ok(true)
+    }
+}
+
+/// Keeps a subscription made through [`Subscriptions`] alive.
+///
+/// Dropping the handle unsubscribes from its remaining targets without waiting for the server;
+/// use [`SubscriptionHandle::unsubscribe`] to wait for the acknowledgements instead.
+#[must_use = "dropping a SubscriptionHandle unsubscribes immediately"]
+pub struct SubscriptionHandle {
+    channel: Channels,
+    targets: Vec<String>,
+    subscription_id: u64,
+    /// Connections carrying the targets; a [`crate::ws_pool::WsPool`] spreads them over several.
+    connections: Vec<Connection>,
+    /// Index into `connections` of each target.
+    target_connections: HashMap<String, usize>,
+    queue: QueueMonitor,
+}
+
+impl SubscriptionHandle {
+    pub(crate) fn new(
+        channel: Channels,
+        subscription_id: u64,
+        queue: QueueMonitor,
+        connections: Vec<Connection>,
+        targets: Vec<(String, usize)>,
+    ) -> Self {
+        Self {
+            channel,
+            targets: targets.iter().map(|(target, _)| target.clone()).collect(),
+            subscription_id,
+            connections,
+            target_connections: targets.into_iter().collect(),
+            queue,
+        }
+    }
+
+    pub fn channel(&self) -> Channels {
+        self.channel
+    }
+
+    pub(crate) fn subscription_id(&self) -> u64 {
+        self.subscription_id
+    }
+
+    /// The symbols or subaccount ids still subscribed to through this handle.
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    /// Queue depth, drops and lag of the subscription's bounded queue.
+    pub fn metrics(&self) -> SubscriptionMetrics {
+        self.queue.metrics()
+    }
+
+    /// Unsubscribes from a single symbol or subaccount, keeping the others.
+    pub async fn unsubscribe_target(&mut self, target: &str) -> Result<(), ClientError> {
+        self.targets.retain(|t| t != target);
+        let target = target.to_string();
+        for (rpc, removed) in self.remove_routes(std::slice::from_ref(&target)) {
+            send_unsubscribes(&rpc, self.channel, removed).await?;
+        }
+        self.target_connections.remove(&target);
+        Ok(())
+    }
+
+    /// Unsubscribes from every target, waiting for the server's acknowledgements.
+    pub async fn unsubscribe(mut self) -> Result<(), ClientError> {
+        let targets = std::mem::take(&mut self.targets);
+        let mut result = Ok(());
+        for (rpc, removed) in self.remove_routes(&targets) {
+            let sent = send_unsubscribes(&rpc, self.channel, removed).await;
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result
+    }
+
+    /// Keeps the subscription until the client shuts down instead of ending it on drop.
+    pub fn detach(mut self) {
+        self.targets.clear();
+    }
+
+    /// Removes this subscription from the routes of `targets`, returning per connection the
+    /// targets left without any subscription, which should be unsubscribed.
+    fn remove_routes(&self, targets: &[String]) -> Vec<(RpcSender, Vec<SubscribedTarget>)> {
+        let mut removed = Vec::new();
+        for (index, connection) in self.connections.iter().enumerate() {
+            let own: Vec<String> = targets
+                .iter()
+                .filter(|target| self.target_connections.get(*target) == Some(&index))
+                .cloned()
+                .collect();
+            if own.is_empty() {
+                continue;
+            }
+            let emptied = remove_targets(
+                &connection.subs,
+                self.channel,
+                Some(self.subscription_id),
+                &own,
+            );
+            if !emptied.is_empty() {
+                removed.push((connection.rpc.clone(), emptied));
+            }
+        }
+        removed
+    }
+}
+
+impl Drop for SubscriptionHandle {
+    fn drop(&mut self) {
+        if self.targets.is_empty() {
+            return;
+        }
+        let removed = self.remove_routes(&self.targets);
+        if removed.is_empty() {
+            return;
+        }
+        let channel = self.channel;
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(async move {
+                    for (rpc, targets) in removed {
+                        let _ = send_unsubscribes(&rpc, channel, targets).await;
+                    }
+                });
+            }
+            Err(_) => {
+                for (rpc, targets) in removed {
+                    for target in targets {
+                        let _ = rpc.send_nowait(target.unsubscribe);
+                    }
+                }
+            }
+        }
+    }
 }
-type SubscriptionMap = Arc<DashMap<String, (UnboundedSender<bytes::Bytes>, Vec<bytes::Bytes>)>>;
 
 pub struct WsClient {
-    write_tx: UnboundedSender<InternalCommand>,
+    rpc: RpcSender,
     state_rx: watch::Receiver<ConnectionState>,
     pub environment: Environment,
     supervisor_handle: Arc<Mutex<JoinHandle<()>>>,
     subs: SubscriptionMap,
-    pending_requests: Arc<DashMap<u64, ResponseSender>>,
     shutdown_tx: watch::Sender<bool>,
-    next_id: Arc<AtomicU64>,
     subscription_tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
     current_connection_state: Arc<Mutex<ConnectionState>>,
+    events_tx: UnboundedSender<WsEvent>,
+    events_rx: Arc<Mutex<UnboundedReceiver<WsEvent>>>,
+    slow_consumer_policy: Option<SlowConsumerPolicy>,
+    auth_rx: Option<watch::Receiver<AuthState>>,
+    config: WsConfig,
 }
 
 #[inline(always)]
@@ -92,141 +530,385 @@ where
     }
 }
 
+/// Removes a pending request when its `send_rpc` call completes, times out or is dropped.
+struct PendingRequestGuard<'a> {
+    id: u64,
+    pending_requests: &'a DashMap<u64, ResponseSender>,
+}
+
+impl Drop for PendingRequestGuard<'_> {
+    fn drop(&mut self) {
+        self.pending_requests.remove(&self.id);
+    }
+}
+
+/// Adds `"id": id` to a JSON object request, so the response can be matched to it.
+pub fn with_request_id(msg: &Bytes, id: u64) -> Result<Bytes, ClientError> {
+    let mut request: serde_json::Value = serde_json::from_slice(msg)?;
+    match request.as_object_mut() {
+        Some(fields) => {
+            fields.insert("id".to_string(), id.into());
+        }
+        None => {
+            return Err(ClientError::InvalidRequest(format!(
+                "expected a JSON object, got: {}",
+                String::from_utf8_lossy(msg)
+            )))
+        }
+    }
+    Ok(Bytes::from(serde_json::to_vec(&request)?))
+}
+
 impl WsClient {
     pub fn new(environment: Environment) -> Self {
+        Self::with_config(environment, WsConfig::default())
+    }
+
+    /// Connects with custom reconnection, keep-alive and compression settings, or to
+    /// `config.url` instead of the environment's server.
+    pub fn with_config(environment: Environment, config: WsConfig) -> Self {
+        Self::spawn(environment, config, None)
+    }
+
+    /// Connects like [`WsClient::with_config`] and authenticates the session with `auth` on every
+    /// connect, renewing it before it expires, so private channels deliver messages.
+    ///
+    /// The outcome is reported as [`WsEvent::Authenticated`] or [`WsEvent::AuthFailed`]; a
+    /// rejected session stays connected for public channels. Unverified, see
+    /// [`crate::ws_auth::WsAuth`].
+    #[cfg(feature = "experimental-ws-auth")]
+    pub fn authenticated(environment: Environment, config: WsConfig, auth: WsAuth) -> Self {
+        Self::spawn(environment, config, Some(auth))
+    }
+
+    fn spawn(environment: Environment, config: WsConfig, auth: Option<WsAuth>) -> Self {
         let (state_tx, state_rx) = watch::channel::<ConnectionState>(ConnectionState::Disconnected);
         let pending_requests = Arc::new(DashMap::new());
         let subs: SubscriptionMap = Arc::new(DashMap::new());
 
-        let (cmd_tx, cmd_rx) = mpsc::unbounded_channel::<InternalCommand>();
+        let (cmd_tx, cmd_rx) = mpsc::channel::<InternalCommand>(COMMAND_QUEUE_CAPACITY);
+        let (events_tx, events_rx) = mpsc::unbounded_channel::<WsEvent>();
         let (shutdown_tx, shutdown_rx) = watch::channel(false);
 
+        let next_id = Arc::new(AtomicU64::new(1));
+        let (auth_tx, auth_rx) = watch::channel(AuthState::Unauthenticated);
+        let auth_rx = auth.is_some().then_some(auth_rx);
+
         let supervisor_handle = tokio::spawn(connection_supervisor(
-            environment.get_server_url().to_string(),
+            config.server_url(environment),
+            config.clone(),
             cmd_rx,
             shutdown_rx,
-            pending_requests.clone(),
-            subs.clone(),
+            SupervisorShared {
+                pending_requests: pending_requests.clone(),
+                subscriptions: subs.clone(),
+                next_id: next_id.clone(),
+                events_tx: events_tx.clone(),
+                environment,
+                auth,
+                auth_tx,
+            },
             state_tx.clone(),
         ));
-        let next_id = Arc::new(AtomicU64::new(1));
+        let rpc = RpcSender {
+            write_tx: cmd_tx,
+            pending_requests,
+            next_id,
+        };
 
         let subscription_tasks = Arc::new(Mutex::new(Vec::new()));
 
         Self {
-            write_tx: cmd_tx.clone(),
+            rpc,
             state_rx,
             environment,
             supervisor_handle: Arc::new(Mutex::new(supervisor_handle)),
             subs,
-            pending_requests,
             shutdown_tx,
-            next_id,
             subscription_tasks,
             current_connection_state: Arc::new(Mutex::new(ConnectionState::Disconnected)),
+            events_tx,
+            events_rx: Arc::new(Mutex::new(events_rx)),
+            slow_consumer_policy: None,
+            auth_rx,
+            config,
         }
     }
 
+    /// Decides what happens to subscriptions made from now on when their consumer falls behind.
+    ///
+    /// Every slow consumer is reported through [`WsClient::run_till_event`] either way, at most
+    /// once per [`StreamConfig::warning_interval`]; without a policy the subscription is kept.
+    pub fn with_slow_consumer_policy<F>(mut self, policy: F) -> Self
+    where
+        F: Fn(&SlowConsumerWarning) -> SlowConsumerAction + Send + Sync + 'static,
+    {
+        self.slow_consumer_policy = Some(Arc::new(policy));
+        self
+    }
+
     pub fn subscriptions(&self) -> Subscriptions<'_> {
-        Subscriptions { client: self }
+        Subscriptions {
+            client: self,
+            stream_config: StreamConfig::default(),
+        }
     }
-    pub async fn subscribe_channels<P, F, Fut>(
+
+    /// Subscribes `callback` to the channel for each request, waiting for every acknowledgement.
+    ///
+    /// Messages are routed by channel and symbol or subaccount id, so subscriptions to other
+    /// targets of the same channel are left alone. A target which is already subscribed is shared:
+    /// the new callback receives its messages from now on without a new subscribe request.
+    /// If the server rejects any request, the targets subscribed so far are unsubscribed again.
+    ///
+    /// The callback is fed from a bounded queue, which applies `config.overflow` when it falls
+    /// behind.
+    pub async fn subscribe_channels<R, P, F, Fut>(
         &self,
         event: Channels,
-        payloads: Vec<Bytes>,
-        mut callback: F,
-    ) -> Result<(), ClientError>
+        requests: Vec<R>,
+        config: StreamConfig,
+        callback: F,
+    ) -> Result<SubscriptionHandle, ClientError>
     where
+        R: SubscriptionRequest,
         P: DeserializeOwned + Send + 'static,
         F: FnMut(P) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let (tx, mut rx) = mpsc::unbounded_channel::<Bytes>();
-
-        self.subs.insert(event.as_string(), (tx, payloads.clone()));
+        // The routes own the queue's sender, so the task below ends once they are all removed.
+        let (subscription, new_targets, rx) = self.add_routes(event, &requests, config);
         debug!("Subscribing to public channel: {event:?}");
 
-        let handle = tokio::spawn(async move {
-            while let Some(msg) = rx.recv().await {
-                let parsed: P = match deserialise_to_type(&msg) {
-                    Ok(m) => m,
-                    Err(e) => {
-                        warn!("Failed to parse channel message: {e}; raw: {msg:?}");
-                        continue;
+        let handle = spawn_callback(event, rx, callback);
+        self.track_task(handle).await;
+
+        self.confirm_subscriptions(event, new_targets).await?;
+        Ok(subscription)
+    }
+
+    /// Like [`WsClient::subscribe_channels`], but delivers the messages as a stream read from a
+    /// bounded queue, which applies `config.overflow` when the consumer falls behind.
+    pub async fn subscribe_stream<R, P>(
+        &self,
+        event: Channels,
+        requests: Vec<R>,
+        config: StreamConfig,
+    ) -> Result<SubscriptionStream<P>, ClientError>
+    where
+        R: SubscriptionRequest,
+        P: DeserializeOwned + Send + 'static,
+    {
+        let (subscription, new_targets, rx) = self.add_routes(event, &requests, config);
+        debug!("Subscribing to public channel as a stream: {event:?}");
+
+        self.confirm_subscriptions(event, new_targets).await?;
+        Ok(SubscriptionStream::new(subscription, parse_messages(rx)))
+    }
+
+    /// Adds a new bounded queue to the route of every request, returning the handle owning those
+    /// routes, the targets which were not subscribed yet and the queue's receiving end.
+    fn add_routes<R: SubscriptionRequest>(
+        &self,
+        event: Channels,
+        requests: &[R],
+        config: StreamConfig,
+    ) -> (SubscriptionHandle, Vec<SubscribedTarget>, QueueReceiver) {
+        let (sender, rx, monitor) = self.subscription_queue(event, config);
+        let targets: Vec<SubscribedTarget> = requests.iter().map(SubscribedTarget::new).collect();
+        let subscription = SubscriptionHandle::new(
+            event,
+            sender.subscription_id,
+            monitor,
+            vec![self.connection()],
+            targets.iter().map(|t| (t.target.clone(), 0)).collect(),
+        );
+        let new_targets = self.route_targets(event, targets, &sender);
+        (subscription, new_targets, rx)
+    }
+
+    /// Creates the bounded queue of a new subscription, reporting slow consumers to this client.
+    pub(crate) fn subscription_queue(
+        &self,
+        event: Channels,
+        config: StreamConfig,
+    ) -> (RouteSender, QueueReceiver, QueueMonitor) {
+        // Unique across clients, since a pool routes one subscription through several.
+        let subscription_id = NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::Relaxed);
+        let (queue, rx, monitor) = queue(
+            config,
+            Some(SlowConsumerMonitor {
+                channel: event,
+                subscription_id,
+                events: self.events_tx.clone(),
+                policy: self.slow_consumer_policy.clone(),
+            }),
+        );
+        let sender = RouteSender {
+            subscription_id,
+            queue,
+        };
+        (sender, rx, monitor)
+    }
+
+    pub(crate) fn config(&self) -> &WsConfig {
+        &self.config
+    }
+
+    pub(crate) fn connection(&self) -> Connection {
+        Connection {
+            subs: self.subs.clone(),
+            rpc: self.rpc.clone(),
+        }
+    }
+
+    /// Adds `sender` to the route of every target, returning the targets which were not
+    /// subscribed yet.
+    pub(crate) fn route_targets(
+        &self,
+        event: Channels,
+        targets: Vec<SubscribedTarget>,
+        sender: &RouteSender,
+    ) -> Vec<SubscribedTarget> {
+        let channel = event.as_string();
+        let mut new_targets = Vec::new();
+        for target in targets {
+            let mut route = self
+                .subs
+                .entry(route_key(&channel, &target.target))
+                .or_insert_with(|| {
+                    new_targets.push(target.clone());
+                    ChannelRoute {
+                        senders: Vec::new(),
+                        target,
+                        confirmed: false,
                     }
-                };
-                callback(parsed).await;
+                });
+            if !route
+                .senders
+                .iter()
+                .any(|s| s.subscription_id == sender.subscription_id)
+            {
+                route.senders.push(sender.clone());
             }
-        });
+        }
+        new_targets
+    }
 
-        for payload in payloads {
-            let res = self.send_rpc_nowait(payload).await;
+    /// Sends the subscribe request of each new target and waits for its acknowledgement.
+    ///
+    /// A request left unacknowledged for [`WsConfig::subscribe_ack_timeout`] keeps its route,
+    /// with a warning, unless [`WsConfig::require_subscribe_ack`] is set.
+    pub(crate) async fn confirm_subscriptions(
+        &self,
+        event: Channels,
+        new_targets: Vec<SubscribedTarget>,
+    ) -> Result<(), ClientError> {
+        let confirm = |target: &str| {
+            if let Some(mut route) = self.subs.get_mut(&route_key(&event.as_string(), target)) {
+                route.confirmed = true;
+            }
+        };
+        for target in new_targets {
+            let res = self
+                .rpc
+                .request::<SubscriptionAck>(target.subscribe, self.config.subscribe_ack_timeout)
+                .await
+                .and_then(SubscriptionAck::into_result);
             match res {
-                Ok(_) => debug!("Subscription message sent for channel: {event:?}"),
+                Ok(_) => {
+                    debug!("Subscribed to {event:?}: {}", target.target);
+                    confirm(&target.target);
+                }
+                Err(ClientError::Timeout(timeout)) if !self.config.require_subscribe_ack => {
+                    warn!(
+                        "Subscription to {event:?} {} not acknowledged within {timeout:?}, keeping it",
+                        target.target
+                    );
+                    confirm(&target.target);
+                }
                 Err(e) => {
-                    error!("Failed to send subscription message for channel {event:?}: {e}");
+                    error!("Failed to subscribe to {event:?} {}: {e}", target.target);
                     return Err(e);
                 }
             }
         }
         info!("Subscription result: ok! Channel: {event:?}");
-        self.subscription_tasks.lock().await.push(handle);
         Ok(())
     }
 
+    /// Unsubscribes the given symbols or subaccount ids from a channel, waiting for the server's
+    /// acknowledgements. Targets which are not subscribed are ignored.
+    pub async fn unsubscribe(
+        &self,
+        channel: Channels,
+        targets: Vec<String>,
+    ) -> Result<(), ClientError> {
+        let removed = remove_targets(&self.subs, channel, None, &targets);
+        send_unsubscribes(&self.rpc, channel, removed).await
+    }
+
     pub async fn send_rpc_nowait(&self, msg: Bytes) -> Result<(), ClientError> {
-        self.write_tx
-            .send(InternalCommand::Send(Frame::text(msg)))
-            .map_err(|e| ClientError::Transport(Box::new(e)))
+        self.rpc.send_nowait(msg)
     }
+    /// Sends a request and waits up to [`DEFAULT_RPC_TIMEOUT`] for the response with its id.
     pub async fn send_rpc<T>(&self, msg: Bytes) -> Result<T, ClientError>
     where
         T: serde::de::DeserializeOwned + std::fmt::Debug,
     {
-        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
-
-        let (tx, rx) = oneshot::channel::<Bytes>();
-        self.pending_requests.insert(id, tx);
-
-        if let Err(e) = self.write_tx.send(InternalCommand::Send(Frame::text(msg))) {
-            self.pending_requests.remove(&id);
-            return Err(ClientError::Transport(Box::new(e)));
-        }
-
-        let response = rx.await;
-        let resp = match response {
-            Ok(r) => r,
-            Err(e) => {
-                return Err(ClientError::Transport(Box::new(e)));
-            }
-        };
+        self.send_rpc_with_timeout(msg, DEFAULT_RPC_TIMEOUT).await
+    }
 
-        let envelope: T = deserialise_to_type(&resp)?;
-        Ok(envelope)
+    /// Sends a request with a fresh `id` and waits for the response carrying the same id.
+    ///
+    /// Fails with [`ClientError::Timeout`] if no response arrives in time and with
+    /// [`ClientError::Disconnected`] if the connection drops first.
+    pub async fn send_rpc_with_timeout<T>(
+        &self,
+        msg: Bytes,
+        timeout: Duration,
+    ) -> Result<T, ClientError>
+    where
+        T: serde::de::DeserializeOwned + std::fmt::Debug,
+    {
+        self.rpc.request(msg, timeout).await
     }
-    pub async fn run_till_event(&self) -> ConnectionState {
+    /// Waits for the next connection state change or slow consumer warning.
+    pub async fn run_till_event(&self) -> WsEvent {
         let mut rx = self.state_rx.clone();
+        let mut events = self.events_rx.lock().await;
 
         loop {
-            if rx.changed().await.is_ok() {
-                let state = *rx.borrow_and_update();
-                let mut current_state = self.current_connection_state.lock().await;
-                if state != *current_state {
-                    info!("Connection state changed to: {:?}", state);
-                    *current_state = state;
-                    return state;
+            tokio::select! {
+                Some(event) = events.recv() => return event,
+                changed = rx.changed() => {
+                    if changed.is_err() {
+                        return WsEvent::Connection(ConnectionState::Exited);
+                    }
+                    let state = *rx.borrow_and_update();
+                    let mut current_state = self.current_connection_state.lock().await;
+                    if state != *current_state {
+                        info!("Connection state changed to: {:?}", state);
+                        *current_state = state;
+                        return WsEvent::Connection(state);
+                    }
                 }
-            } else {
-                return ConnectionState::Exited;
             }
         }
     }
 
+    /// Keeps a task to abort on shutdown, dropping the handles of those which already ended.
+    pub(crate) async fn track_task(&self, task: JoinHandle<()>) {
+        let mut tasks = self.subscription_tasks.lock().await;
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task);
+    }
+
     pub async fn shutdown(&self, reason: &'static str) -> Result<(), ClientError> {
         debug!("Shutdown requested: {reason}");
         self.subs.clear();
         let _ = self.shutdown_tx.send(true);
-        let _ = self.write_tx.send(InternalCommand::Close);
+        let _ = self.rpc.write_tx.try_send(InternalCommand::Close);
         // we join the supervisor task to ensure it has fully exited before we return from shutdown
         let supervisor_handle = self.supervisor_handle.lock().await;
         supervisor_handle.abort();
@@ -236,27 +918,62 @@ impl WsClient {
         Ok(())
     }
 
+    /// Sends every stored subscribe request again, waiting for the acknowledgements.
+    ///
+    /// The supervisor already does this after every reconnect, reporting
+    /// [`WsEvent::Resubscribed`]; this is only needed to recover from a failed resubscription.
     pub async fn resubscribe_all(&self) -> Result<(), ClientError> {
         debug!("Resubscribing to all channels");
-        for entry in self.subs.iter() {
-            let channel = entry.key();
-            let (_sender, payloads) = entry.value();
-            debug!("Resubscribing to channel: {channel} with payloads: {payloads:?}");
-            for payload in payloads {
-                let res = self.send_rpc_nowait(payload.to_owned()).await;
-                match res {
-                    Ok(_) => debug!("Resubscription message sent for channel: {channel:?}"),
-                    Err(e) => {
-                        error!(
-                            "Failed to send resubscription message for channel {channel:?}: {e}"
-                        );
-                        return Err(e);
-                    }
+        // Collected first, so no map guard is held while waiting for acknowledgements.
+        let subscriptions: Vec<(String, SubscribedTarget)> = self
+            .subs
+            .iter()
+            .map(|route| (route.key().clone(), route.target.clone()))
+            .collect();
+        for (key, target) in subscriptions {
+            debug!("Resubscribing to {key}");
+            let res = self.rpc.subscription_request(target.subscribe).await;
+            match res {
+                Ok(_) => debug!("Resubscribed to {key}"),
+                Err(e) => {
+                    error!("Failed to resubscribe to {key}: {e}");
+                    return Err(e);
                 }
             }
         }
         Ok(())
     }
+    /// Authentication of the current connection; [`AuthState::Failed`] with
+    /// [`AuthError::NotConfigured`] for a client created without a signer.
+    pub fn auth_state(&self) -> AuthState {
+        match &self.auth_rx {
+            Some(rx) => rx.borrow().clone(),
+            None => AuthState::Failed(AuthError::NotConfigured),
+        }
+    }
+
+    /// Waits until the server accepts or rejects the current connection's auth request.
+    ///
+    /// Subscribe to private channels after this returns `Ok`, otherwise their messages are not
+    /// delivered.
+    pub async fn wait_for_authentication(&self) -> Result<(), ClientError> {
+        let mut rx = match &self.auth_rx {
+            Some(rx) => rx.clone(),
+            None => return Err(AuthError::NotConfigured.into()),
+        };
+        loop {
+            let state = rx.borrow_and_update().clone();
+            match state {
+                AuthState::Authenticated => return Ok(()),
+                AuthState::Failed(e) => return Err(e.into()),
+                AuthState::Unauthenticated => {}
+            }
+            if rx.changed().await.is_err() {
+                return Err(ClientError::Disconnected);
+            }
+        }
+    }
+
     pub async fn wait_for_connection(&self) {
         let mut rx = self.state_rx.clone();
 
@@ -276,79 +993,378 @@ impl WsClient {
     }
 }
 
+/// Feeds the messages of a subscription's queue to `callback` until the queue closes.
+pub(crate) fn spawn_callback<P, F, Fut>(
+    event: Channels,
+    rx: QueueReceiver,
+    mut callback: F,
+) -> JoinHandle<()>
+where
+    P: DeserializeOwned + Send + 'static,
+    F: FnMut(P) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        while let Some(item) = rx.recv().await {
+            let msg = match item {
+                QueueItem::Message(_, msg) => msg,
+                QueueItem::Gap => {
+                    warn!("Messages on {event:?} may have been missed while reconnecting");
+                    continue;
+                }
+            };
+            let parsed: P = match deserialise_to_type(&msg) {
+                Ok(m) => m,
+                Err(e) => {
+                    warn!("Failed to parse channel message: {e}; raw: {msg:?}");
+                    continue;
+                }
+            };
+            callback(parsed).await;
+        }
+    })
+}
+
+/// Client state the connection supervisor works on.
+struct SupervisorShared {
+    pending_requests: Arc<DashMap<u64, ResponseSender>>,
+    subscriptions: SubscriptionMap,
+    next_id: Arc<AtomicU64>,
+    events_tx: UnboundedSender<WsEvent>,
+    environment: Environment,
+    auth: Option<WsAuth>,
+    auth_tx: watch::Sender<AuthState>,
+}
+
+impl SupervisorShared {
+    /// Signs a fresh auth request and registers its id, returning the frame to send and the
+    /// receiver of its response.
+    fn auth_request(
+        &self,
+        auth: &WsAuth,
+    ) -> Result<(u64, Bytes, oneshot::Receiver<Bytes>), ClientError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = with_request_id(&auth.request(self.environment)?, id)?;
+        let (tx, rx) = oneshot::channel::<Bytes>();
+        self.pending_requests.insert(id, tx);
+        Ok((id, request, rx))
+    }
+
+    fn report_auth(&self, result: Result<(), AuthError>) {
+        report_auth(&self.auth_tx, &self.events_tx, result);
+    }
+}
+
+/// Publishes the outcome of an auth request as the client's [`AuthState`] and a [`WsEvent`].
+fn report_auth(
+    auth_tx: &watch::Sender<AuthState>,
+    events_tx: &UnboundedSender<WsEvent>,
+    result: Result<(), AuthError>,
+) {
+    match result {
+        Ok(()) => {
+            info!("WebSocket session authenticated");
+            auth_tx.send_replace(AuthState::Authenticated);
+            let _ = events_tx.send(WsEvent::Authenticated);
+        }
+        Err(e) => {
+            error!("WebSocket authentication failed: {e}");
+            auth_tx.send_replace(AuthState::Failed(e.clone()));
+            let _ = events_tx.send(WsEvent::AuthFailed(e));
+        }
+    }
+}
+
 async fn connection_supervisor(
     url: String,
-    mut cmd_rx: mpsc::UnboundedReceiver<InternalCommand>,
+    config: WsConfig,
+    mut cmd_rx: mpsc::Receiver<InternalCommand>,
     mut shutdown_rx: watch::Receiver<bool>,
-    pending_requests: Arc<DashMap<u64, ResponseSender>>,
-    subscriptions: SubscriptionMap,
+    shared: SupervisorShared,
     connection_state_tx: watch::Sender<ConnectionState>,
 ) {
+    info!("Connection supervisor started for {url}");
+    let pending_requests = &shared.pending_requests;
     let mut attempts = 0;
-    loop {
-        info!("Connection supervisor started for {url}");
-
-        if *shutdown_rx.borrow() {
-            info!("Supervisor sees shutdown for {url}");
-            break;
-        }
-        let client = WebSocket::connect(url.parse().unwrap())
-            .with_options(Options::default().with_high_compression())
-            .await;
-
-        match client {
-            Ok(ws_stream) => {
-                info!("Connected to {url}");
-                attempts = 0;
-                connection_state_tx.send(ConnectionState::Connected).ok();
-                let result = run_single_connection(
-                    ws_stream,
-                    &mut cmd_rx,
-                    &mut shutdown_rx,
-                    &pending_requests,
-                    &subscriptions,
-                )
+    let mut reconnecting = false;
+    match url.parse::<url::Url>() {
+        Ok(parsed_url) => loop {
+            if *shutdown_rx.borrow() {
+                info!("Supervisor sees shutdown for {url}");
+                break;
+            }
+            let options = if config.compression {
+                Options::default().with_high_compression()
+            } else {
+                Options::default()
+            };
+            let client = WebSocket::connect(parsed_url.clone())
+                .with_options(options)
                 .await;
-                info!("Connection to {url} ended with result: {result:?}");
 
-                if result.is_ok() {
-                    info!("Connection exited normally for {url}");
-                    connection_state_tx.send(ConnectionState::Exited).ok();
-                    break;
+            match client {
+                Ok(mut ws_stream) => {
+                    info!("Connected to {url}");
+                    let connected_at = Instant::now();
+                    connection_state_tx.send(ConnectionState::Connected).ok();
+                    // Private subscriptions are only served once the session is authenticated.
+                    // Subscriptions made before the first connection are still queued as commands.
+                    let mut prepared = authenticate(&mut ws_stream, &config, &shared).await;
+                    if prepared.is_ok() && reconnecting {
+                        prepared = replay_subscriptions(&mut ws_stream, &shared).await;
+                    }
+                    reconnecting = true;
+                    let result = match prepared {
+                        Ok(()) => {
+                            run_single_connection(
+                                ws_stream,
+                                &config,
+                                &mut cmd_rx,
+                                &mut shutdown_rx,
+                                &shared,
+                            )
+                            .await
+                        }
+                        Err(e) => Err(e),
+                    };
+                    info!("Connection to {url} ended with result: {result:?}");
+                    fail_pending_requests(pending_requests);
+                    shared.auth_tx.send_replace(AuthState::Unauthenticated);
+
+                    if let Err(e) = result {
+                        error!("Connection error on {url}: {e}");
+                        connection_state_tx.send(ConnectionState::Disconnected).ok();
+                    } else {
+                        info!("Connection exited normally for {url}");
+                        break;
+                    }
+                    if connected_at.elapsed() >= config.stable_after {
+                        attempts = 0;
+                    }
+                    attempts += 1;
                 }
-                if let Err(e) = result {
-                    error!("Connection error on {url}: {e}");
-                    connection_state_tx.send(ConnectionState::Disconnected).ok();
+                Err(e) => {
+                    attempts += 1;
+                    error!("Failed to connect to {url}: {e} - attempt {attempts}");
                 }
             }
-            Err(e) => {
-                attempts += 1;
-                error!("Failed to connect to {url}: {e} - attempt {attempts}");
-                if *shutdown_rx.borrow() || cmd_rx.is_closed() {
-                    break;
-                }
-                tokio::time::sleep(std::time::Duration::from_secs(3u64.pow(attempts))).await;
-                connection_state_tx.send(ConnectionState::Disconnected).ok();
+            if *shutdown_rx.borrow() || cmd_rx.is_closed() {
+                break;
             }
+            if !config.should_retry(attempts) {
+                error!("Giving up on {url} after {attempts} failed attempts");
+                break;
+            }
+            let delay = config.backoff_delay(attempts);
+            info!("Reconnecting to {url} in {delay:?}");
+            connection_state_tx.send(ConnectionState::Reconnecting).ok();
+            tokio::select! {
+                _ = sleep(delay) => {}
+                _ = shutdown_rx.changed() => {}
+            }
+        },
+        Err(e) => error!("Invalid WebSocket url {url}: {e}"),
+    }
+    connection_state_tx.send(ConnectionState::Exited).ok();
+    fail_pending_requests(pending_requests);
+    // Nothing arrives any more, so every stream and callback task ends.
+    for route in shared.subscriptions.iter() {
+        for sender in &route.senders {
+            sender.queue.close();
         }
     }
     info!("Connection supervisor exited for {url}");
 }
 
+/// Sends the stored subscribe request of every confirmed route over a new connection, after
+/// marking a gap on each subscription's queue. The acknowledgements are awaited in the
+/// background, since they arrive through the connection loop, and reported as
+/// [`WsEvent::Resubscribed`].
+async fn replay_subscriptions(
+    ws: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+    shared: &SupervisorShared,
+) -> Result<(), ClientError> {
+    // Collected first, so no map guard is held across the sends below. An unconfirmed route's
+    // subscribe is still queued for this connection, or failed its subscriber with the old one.
+    let routes: Vec<(String, SubscribedTarget)> = shared
+        .subscriptions
+        .iter()
+        .filter(|route| route.confirmed)
+        .map(|route| {
+            for sender in &route.senders {
+                sender.queue.mark_gap();
+            }
+            (route.key().clone(), route.target.clone())
+        })
+        .collect();
+    if routes.is_empty() {
+        return Ok(());
+    }
+    info!("Replaying {} subscriptions", routes.len());
+
+    let mut acks = Vec::with_capacity(routes.len());
+    for (key, target) in routes {
+        let id = shared.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = with_request_id(&target.subscribe, id)?;
+        let (tx, rx) = oneshot::channel::<Bytes>();
+        shared.pending_requests.insert(id, tx);
+        ws.send(Frame::text(request)).await?;
+        acks.push((key, id, rx));
+    }
+
+    let pending_requests = shared.pending_requests.clone();
+    let events_tx = shared.events_tx.clone();
+    tokio::spawn(async move {
+        let mut restored = Vec::new();
+        let mut failed = Vec::new();
+        for (key, id, rx) in acks {
+            let result = match tokio::time::timeout(DEFAULT_RPC_TIMEOUT, rx).await {
+                Ok(Ok(resp)) => deserialise_to_type::<SubscriptionAck>(&resp)
+                    .map_err(ClientError::from)
+                    .and_then(SubscriptionAck::into_result),
+                Ok(Err(_)) => Err(ClientError::Disconnected),
+                Err(_) => {
+                    pending_requests.remove(&id);
+                    Err(ClientError::Timeout(DEFAULT_RPC_TIMEOUT))
+                }
+            };
+            match result {
+                Ok(()) => {
+                    debug!("Resubscribed to {key}");
+                    restored.push(key);
+                }
+                Err(e) => {
+                    error!("Failed to resubscribe to {key}: {e}");
+                    failed.push(key);
+                }
+            }
+        }
+        let _ = events_tx.send(WsEvent::Resubscribed { restored, failed });
+    });
+    Ok(())
+}
+
+/// Sends the session's auth request over a new connection and waits for the response, handing
+/// any other frame to the usual routing meanwhile.
+///
+/// A rejected request, or one left unanswered for `config.read_timeout`, is reported and the
+/// connection kept for public channels; only a connection failure is returned as an error.
+async fn authenticate(
+    ws: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+    config: &WsConfig,
+    shared: &SupervisorShared,
+) -> Result<(), ClientError> {
+    let auth = match &shared.auth {
+        Some(auth) => auth,
+        None => return Ok(()),
+    };
+    let (id, request, mut rx) = match shared.auth_request(auth) {
+        Ok(request) => request,
+        Err(ClientError::Auth(e)) => {
+            shared.report_auth(Err(e));
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    ws.send(Frame::text(request)).await?;
+
+    // Nothing else is sent meanwhile, pings included, so the wait stays within the read timeout.
+    let deadline = Instant::now() + config.read_timeout;
+    let result = loop {
+        let frame = match tokio::time::timeout_at(deadline, ws.next()).await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return Err(yawc::WebSocketError::ConnectionClosed.into()),
+            Err(_) => {
+                shared.pending_requests.remove(&id);
+                break Err(AuthError::Timeout(config.read_timeout));
+            }
+        };
+        let (opcode, _, body) = frame.into_parts();
+        match opcode {
+            OpCode::Text => {
+                handle_incoming(&body, &shared.pending_requests, &shared.subscriptions).await
+            }
+            OpCode::Close => return Err(yawc::WebSocketError::ConnectionClosed.into()),
+            _ => continue,
+        }
+        if let Ok(response) = rx.try_recv() {
+            break parse_auth_response(&response);
+        }
+    };
+    shared.report_auth(result);
+    Ok(())
+}
+
+/// Sends a fresh auth request before the session expires; the response arrives through the
+/// connection loop, so it is awaited in the background.
+async fn renew_auth(
+    ws: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+    shared: &SupervisorShared,
+    auth: &WsAuth,
+) -> Result<(), ClientError> {
+    let (id, request, rx) = match shared.auth_request(auth) {
+        Ok(request) => request,
+        Err(ClientError::Auth(e)) => {
+            shared.report_auth(Err(e));
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    debug!("Renewing WebSocket authentication");
+    ws.send(Frame::text(request)).await?;
+
+    let pending_requests = shared.pending_requests.clone();
+    let auth_tx = shared.auth_tx.clone();
+    let events_tx = shared.events_tx.clone();
+    tokio::spawn(async move {
+        let result = match tokio::time::timeout(DEFAULT_RPC_TIMEOUT, rx).await {
+            Ok(Ok(response)) => parse_auth_response(&response),
+            // The connection dropped; the next one authenticates again.
+            Ok(Err(_)) => return,
+            Err(_) => {
+                pending_requests.remove(&id);
+                Err(AuthError::Timeout(DEFAULT_RPC_TIMEOUT))
+            }
+        };
+        report_auth(&auth_tx, &events_tx, result);
+    });
+    Ok(())
+}
+
+/// Drops every pending response sender, so waiting `send_rpc` calls fail with
+/// [`ClientError::Disconnected`] instead of waiting for a response which will never come.
+fn fail_pending_requests(pending_requests: &DashMap<u64, ResponseSender>) {
+    if !pending_requests.is_empty() {
+        warn!(
+            "Failing {} pending requests after disconnect",
+            pending_requests.len()
+        );
+        pending_requests.clear();
+    }
+}
+
 async fn run_single_connection(
     mut ws: WebSocket<MaybeTlsStream<TcpStream>>,
-    cmd_rx: &mut mpsc::UnboundedReceiver<InternalCommand>,
+    config: &WsConfig,
+    cmd_rx: &mut mpsc::Receiver<InternalCommand>,
     shutdown_rx: &mut watch::Receiver<bool>,
-    pending_requests: &Arc<DashMap<u64, ResponseSender>>,
-    subscriptions: &SubscriptionMap,
+    shared: &SupervisorShared,
 ) -> Result<(), ClientError> {
     // Set up ping interval
-    let mut ping_interval = interval(PING_INTERVAL);
+    let mut ping_interval = interval(config.ping_interval);
     ping_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-    let read_deadline = sleep(READ_TIMEOUT);
+    let read_deadline = sleep(config.read_timeout);
     tokio::pin!(read_deadline);
 
+    // Unused without a signer, so a far off deadline does for that case.
+    let refresh_interval = shared
+        .auth
+        .as_ref()
+        .map_or(Duration::from_secs(86400 * 365), WsAuth::refresh_interval);
+    let auth_deadline = sleep(refresh_interval);
+    tokio::pin!(auth_deadline);
+
     loop {
         tokio::select! {
             _ = ping_interval.tick() => {
@@ -386,11 +1402,12 @@ async fn run_single_connection(
             }
 
             msg = ws.next() => {
-                read_deadline.as_mut().reset(Instant::now() + READ_TIMEOUT);
+                read_deadline.as_mut().reset(Instant::now() + config.read_timeout);
                 match msg {
                     None => {
+                        // Only a shutdown ends the connection for good; the supervisor reconnects.
                         warn!("WebSocket stream ended.");
-                        return Ok(());
+                        return Err(ClientError::WebsocketError(yawc::WebSocketError::ConnectionClosed));
                     },
                     Some(frame) => {
                         let (opcode, _is_fin, body) = frame.into_parts();
@@ -399,8 +1416,8 @@ async fn run_single_connection(
                                 // info!("Received text frame");
                                 handle_incoming(
                                     &body,
-                                    pending_requests,
-                                    subscriptions
+                                    &shared.pending_requests,
+                                    &shared.subscriptions
                                 ).await;
                             },
                             OpCode::Pong => {
@@ -420,8 +1437,15 @@ async fn run_single_connection(
                 }
             }
 
+        _ = &mut auth_deadline, if shared.auth.is_some() => {
+            if let Some(auth) = &shared.auth {
+                renew_auth(&mut ws, shared, auth).await?;
+            }
+            auth_deadline.as_mut().reset(Instant::now() + refresh_interval);
+        }
+
         _ = &mut read_deadline => {
-            warn!("WebSocket read timeout. No messages received within {READ_TIMEOUT:?}");
+            warn!("WebSocket read timeout. No messages received within {:?}", config.read_timeout);
             return Err(ClientError::Io(std::io::Error::new(std::io::ErrorKind::TimedOut, "WebSocket read timeout")));
         }
         }
@@ -431,22 +1455,50 @@ async fn run_single_connection(
 #[inline(always)]
 pub async fn handle_incoming(
     bytes: &Bytes,
-    _pending_requests: &Arc<DashMap<u64, ResponseSender>>,
+    pending_requests: &Arc<DashMap<u64, ResponseSender>>,
     subscriptions: &SubscriptionMap,
 ) {
-    // // ---- fast path: channel_name ----
+    // // ---- fast path: channel_name and symbol or subaccount id ----
     if let Some(channel) = extract_event(bytes) {
-        for routes in [subscriptions] {
-            if let Some(subscription) = routes.get(channel) {
-                let (sender, _payloads) = subscription.value();
-                if sender.send(bytes.to_owned()).is_err() {
-                    routes.remove(channel);
+        // Subaccount messages may carry symbols too, so their `sid` is checked first.
+        for target in [extract_subaccount_id(bytes), extract_symbol(bytes)]
+            .iter()
+            .flatten()
+        {
+            let key = route_key(channel, target);
+            // Cloned out, so no map guard is held while a blocking queue waits for its consumer.
+            let senders = match subscriptions.get(&key) {
+                Some(route) => route.senders.clone(),
+                None => continue,
+            };
+            let mut closed = Vec::new();
+            for sender in &senders {
+                if !sender.queue.send(target, bytes.clone()).await {
+                    closed.push(sender.subscription_id);
+                }
+            }
+            if !closed.is_empty() {
+                // An emptied route is kept, so the subscription's handle still unsubscribes it.
+                if let Some(mut route) = subscriptions.get_mut(&key) {
+                    route
+                        .senders
+                        .retain(|s| !closed.contains(&s.subscription_id));
                 }
-                return;
             }
+            return;
         }
         warn!("No subscription handler for channel: {channel}");
         return;
     }
+    // ---- responses to requests carry the request id instead of a channel ----
+    if let Some(id) = extract_request_id(bytes) {
+        match pending_requests.remove(&id) {
+            Some((_, sender)) => {
+                let _ = sender.send(bytes.to_owned());
+            }
+            None => warn!("No pending request for response id {id}"),
+        }
+        return;
+    }
     warn!("Received unhandled message: {bytes:?}");
 }
@@ -0,0 +1,137 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use crate::enums::Environment;
+
+/// Connection and reconnection settings of a [`crate::ws_client::WsClient`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WsConfig {
+    /// Connect here instead of the environment's server, e.g. to a local test server.
+    pub url: Option<String>,
+    /// Wait before the first reconnection attempt; doubled on every failed attempt.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Fraction of each wait, between 0 and 1, taken off at random so clients which lost the
+    /// connection together do not reconnect together.
+    pub jitter: f64,
+    /// Give up after this many failed attempts in a row, ending every subscription stream;
+    /// `None` retries forever.
+    pub max_attempts: Option<u32>,
+    /// A connection which drops counts as a failed attempt unless it stayed up this long, so a
+    /// server which accepts and then closes connections is retried with backoff.
+    pub stable_after: Duration,
+    pub ping_interval: Duration,
+    /// Reconnect when nothing is received for this long.
+    pub read_timeout: Duration,
+    /// Negotiate permessage-deflate compression.
+    pub compression: bool,
+    /// Wait this long for the server to acknowledge a subscribe request.
+    pub subscribe_ack_timeout: Duration,
+    /// Fail a subscription whose subscribe request is not acknowledged in time. By default it is
+    /// kept with a warning, since the server may deliver messages without acknowledging.
+    pub require_subscribe_ack: bool,
+}
+
+impl Default for WsConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: 0.2,
+            max_attempts: None,
+            stable_after: Duration::from_secs(30),
+            ping_interval: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(7),
+            compression: true,
+            subscribe_ack_timeout: Duration::from_secs(10),
+            require_subscribe_ack: false,
+        }
+    }
+}
+
+impl WsConfig {
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    pub fn stable_after(mut self, stable_after: Duration) -> Self {
+        self.stable_after = stable_after;
+        self
+    }
+
+    pub fn ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    pub fn read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    pub fn compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn subscribe_ack_timeout(mut self, subscribe_ack_timeout: Duration) -> Self {
+        self.subscribe_ack_timeout = subscribe_ack_timeout;
+        self
+    }
+
+    pub fn require_subscribe_ack(mut self, require_subscribe_ack: bool) -> Self {
+        self.require_subscribe_ack = require_subscribe_ack;
+        self
+    }
+
+    pub fn server_url(&self, environment: Environment) -> String {
+        self.url
+            .clone()
+            .unwrap_or_else(|| environment.get_server_url().to_string())
+    }
+
+    /// Whether another attempt is allowed after `attempts` failures in a row.
+    pub fn should_retry(&self, attempts: u32) -> bool {
+        self.max_attempts.is_none_or(|max| attempts < max)
+    }
+
+    /// Wait before retrying after `attempt` failures in a row (counting from 1): the initial
+    /// backoff doubled per failure, capped at `max_backoff`, less up to `jitter` of it.
+    pub fn backoff_delay(&self, attempt: u32) -> Duration {
+        let delay = 2u32
+            .checked_pow(attempt.saturating_sub(1))
+            .and_then(|factor| self.initial_backoff.checked_mul(factor))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+        let jitter = self.jitter.clamp(0.0, 1.0) * random_fraction();
+        delay.mul_f64(1.0 - jitter)
+    }
+}
+
+/// Uniform-ish in `[0, 1)`, seeded per call by the standard library's random hasher keys.
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(0);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
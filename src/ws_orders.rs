@@ -0,0 +1,147 @@
+//! Order entry over the WebSocket connection, behind the `experimental-ws-orders` feature.
+//!
+//! **Unverified:** the exchange documents no WebSocket trading API. The request events below
+//! and the response envelope are assumptions modelled on the REST endpoints and the
+//! subscription acknowledgements; a server which does not know them leaves the request
+//! unanswered, failing it with [`ClientError::Timeout`], so the HTTP methods remain the supported
+//! way to trade.
+
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    async_client::session::SubaccountSession,
+    error::Error,
+    models::{
+        CancelOrderDto, CancelOrderResultDto, DryRunOrderCreatedDto, ListOfCancelOrderResultDtos,
+        SubmitDryOrderDto, SubmitOrderCreatedDto, SubmitOrderDto,
+    },
+    order_builder::OrderBuilder,
+    ws_client::{ClientError, WsClient, DEFAULT_RPC_TIMEOUT},
+};
+
+/// Assumed request events of the order endpoints, mirroring `POST /v1/order`,
+/// `/v1/order/dry-run` and `/v1/order/cancel` of the REST API; see the module docs.
+pub const SUBMIT_ORDER_EVENT: &str = "submit_order";
+pub const DRY_RUN_ORDER_EVENT: &str = "dry_run_order";
+pub const CANCEL_ORDER_EVENT: &str = "cancel_order";
+
+#[derive(Serialize)]
+struct OrderRequest<'a, T> {
+    event: &'a str,
+    data: &'a T,
+}
+
+/// Server reply to an order request: the same body as the REST endpoint under `data`, or an
+/// `error` the way subscription acknowledgements report one.
+#[derive(Debug, Deserialize)]
+#[serde(bound = "T: DeserializeOwned")]
+pub struct OrderResponse<T> {
+    #[serde(default)]
+    pub data: Option<T>,
+    #[serde(default)]
+    pub error: Option<serde_json::Value>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl<T> OrderResponse<T> {
+    /// `Err` with the server's message if the request was rejected.
+    pub fn into_result(self) -> Result<T, ClientError> {
+        let rejection = match self.error {
+            None | Some(serde_json::Value::Null) => None,
+            Some(serde_json::Value::String(error)) => Some(error),
+            Some(error) => Some(
+                error
+                    .get("message")
+                    .and_then(|m| m.as_str())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string()),
+            ),
+        };
+        match (rejection, self.data) {
+            (None, Some(data)) => Ok(data),
+            (Some(message), _) => Err(ClientError::RequestRejected(message)),
+            (None, None) => Err(ClientError::RequestRejected(
+                self.message
+                    .unwrap_or_else(|| "response without data".to_string()),
+            )),
+        }
+    }
+}
+
+impl WsClient {
+    /// Submits an order signed with [`crate::async_client::client::HttpClient::prepare_order`]
+    /// over the socket instead of HTTP.
+    ///
+    /// Fails with [`ClientError::Timeout`] or [`ClientError::Disconnected`] when the response is
+    /// lost, in which case the order may still have been accepted.
+    pub async fn submit_order(
+        &self,
+        order: &SubmitOrderDto,
+    ) -> Result<SubmitOrderCreatedDto, ClientError> {
+        self.order_request(SUBMIT_ORDER_EVENT, order).await
+    }
+
+    /// Checks an order built with
+    /// [`crate::async_client::client::HttpClient::prepare_dry_run`] against the account's
+    /// margin without placing it.
+    pub async fn dry_run_order(
+        &self,
+        order: &SubmitDryOrderDto,
+    ) -> Result<DryRunOrderCreatedDto, ClientError> {
+        self.order_request(DRY_RUN_ORDER_EVENT, order).await
+    }
+
+    /// Cancels the orders of a request signed with
+    /// [`crate::async_client::client::HttpClient::prepare_cancel`], returning a result per order.
+    pub async fn cancel_orders(
+        &self,
+        cancel: &CancelOrderDto,
+    ) -> Result<Vec<CancelOrderResultDto>, ClientError> {
+        let results: ListOfCancelOrderResultDtos =
+            self.order_request(CANCEL_ORDER_EVENT, cancel).await?;
+        Ok(results.data)
+    }
+
+    async fn order_request<T, R>(&self, event: &str, data: &T) -> Result<R, ClientError>
+    where
+        T: Serialize,
+        R: DeserializeOwned + std::fmt::Debug,
+    {
+        let request = Bytes::from(serde_json::to_vec(&OrderRequest { event, data })?);
+        self.send_rpc_with_timeout::<OrderResponse<R>>(request, DEFAULT_RPC_TIMEOUT)
+            .await?
+            .into_result()
+    }
+}
+
+impl SubaccountSession<'_> {
+    /// Like [`SubaccountSession::submit_order`], but sends the signed order over the socket.
+    pub async fn submit_order_ws(
+        &self,
+        ws: &WsClient,
+        order: OrderBuilder,
+    ) -> Result<SubmitOrderCreatedDto, Error> {
+        let dto = self.client.prepare_order(self.subaccount, order)?;
+        Ok(ws.submit_order(&dto).await?)
+    }
+
+    pub async fn dry_run_order_ws(
+        &self,
+        ws: &WsClient,
+        order: OrderBuilder,
+    ) -> Result<DryRunOrderCreatedDto, Error> {
+        let dto = self.client.prepare_dry_run(self.subaccount, order)?;
+        Ok(ws.dry_run_order(&dto).await?)
+    }
+
+    pub async fn cancel_orders_ws(
+        &self,
+        ws: &WsClient,
+        order_ids: Vec<String>,
+    ) -> Result<Vec<CancelOrderResultDto>, Error> {
+        let dto = self.client.prepare_cancel(self.subaccount, order_ids)?;
+        Ok(ws.cancel_orders(&dto).await?)
+    }
+}
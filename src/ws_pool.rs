@@ -0,0 +1,267 @@
+use std::{collections::HashMap, future::Future};
+
+use futures_util::future::{join_all, select_all, try_join_all};
+use log::debug;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    channels::Channels,
+    enums::Environment,
+    subscription_stream::{parse_messages, QueueReceiver, StreamConfig, SubscriptionStream},
+    subscriptions::Subscriptions,
+    types::SubscriptionRequest,
+    ws_client::{
+        spawn_callback, ClientError, SubscribedTarget, Subscriber, SubscriptionHandle, WsClient,
+        WsEvent,
+    },
+    ws_config::WsConfig,
+};
+
+/// Spreads subscriptions over several [`WsClient`] connections, each with its own dispatch loop
+/// and reconnecting on its own, so a stalled connection does not hold up the others.
+///
+/// Each symbol or subaccount id goes to the connection it is grouped on with [`WsPool::group`],
+/// or else to one picked by a stable hash of it. A subscription spanning several connections is
+/// still one [`SubscriptionHandle`] or [`SubscriptionStream`] fed from one queue.
+pub struct WsPool {
+    clients: Vec<WsClient>,
+    groups: HashMap<String, usize>,
+}
+
+impl WsPool {
+    pub fn new(environment: Environment, connections: usize) -> Self {
+        Self::with_config(environment, WsConfig::default(), connections)
+    }
+
+    pub fn with_config(environment: Environment, config: WsConfig, connections: usize) -> Self {
+        let clients = (0..connections.max(1))
+            .map(|_| WsClient::with_config(environment, config.clone()))
+            .collect();
+        Self::from_clients(clients)
+    }
+
+    /// Pools clients built elsewhere, e.g. with [`WsClient::with_config`].
+    ///
+    /// # Panics
+    ///
+    /// If `clients` is empty.
+    pub fn from_clients(clients: Vec<WsClient>) -> Self {
+        assert!(!clients.is_empty(), "a WsPool needs at least one client");
+        Self {
+            clients,
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Routes these symbols or subaccount ids through connection `connection` (modulo the pool
+    /// size) instead of the hashed one, e.g. to keep busy markets apart.
+    pub fn group<I, T>(mut self, targets: I, connection: usize) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let connection = connection % self.clients.len();
+        self.groups.extend(
+            targets
+                .into_iter()
+                .map(|target| (target.into(), connection)),
+        );
+        self
+    }
+
+    pub fn clients(&self) -> &[WsClient] {
+        &self.clients
+    }
+
+    /// Index of the connection carrying a symbol or subaccount id.
+    pub fn connection_for(&self, target: &str) -> usize {
+        match self.groups.get(target) {
+            Some(connection) => *connection,
+            None => (fnv1a(target.as_bytes()) % self.clients.len() as u64) as usize,
+        }
+    }
+
+    pub fn subscriptions(&self) -> Subscriptions<'_, WsPool> {
+        Subscriptions {
+            client: self,
+            stream_config: StreamConfig::default(),
+        }
+    }
+
+    /// Like [`WsClient::subscribe_channels`], with each request sent over the connection of its
+    /// target. The acknowledgements of different connections are awaited together.
+    pub async fn subscribe_channels<R, P, F, Fut>(
+        &self,
+        event: Channels,
+        requests: Vec<R>,
+        config: StreamConfig,
+        callback: F,
+    ) -> Result<SubscriptionHandle, ClientError>
+    where
+        R: SubscriptionRequest,
+        P: DeserializeOwned + Send + 'static,
+        F: FnMut(P) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (subscription, new_targets, rx, owner) = self.add_routes(event, &requests, config);
+        debug!(
+            "Subscribing to {event:?} over {} connections",
+            new_targets.len()
+        );
+
+        // Tracked by one client, so shutting the pool down ends the task.
+        let task = spawn_callback(event, rx, callback);
+        self.clients[owner].track_task(task).await;
+
+        self.confirm_subscriptions(event, new_targets).await?;
+        Ok(subscription)
+    }
+
+    /// Like [`WsClient::subscribe_stream`], with each request sent over the connection of its
+    /// target.
+    pub async fn subscribe_stream<R, P>(
+        &self,
+        event: Channels,
+        requests: Vec<R>,
+        config: StreamConfig,
+    ) -> Result<SubscriptionStream<P>, ClientError>
+    where
+        R: SubscriptionRequest,
+        P: DeserializeOwned + Send + 'static,
+    {
+        let (subscription, new_targets, rx, _) = self.add_routes(event, &requests, config);
+        debug!(
+            "Subscribing to {event:?} as a stream over {} connections",
+            new_targets.len()
+        );
+
+        self.confirm_subscriptions(event, new_targets).await?;
+        Ok(SubscriptionStream::new(subscription, parse_messages(rx)))
+    }
+
+    /// Adds one queue to the route of every request on its target's connection, returning the
+    /// handle owning those routes, the new targets per connection, the queue's receiving end and
+    /// the connection reporting the queue's slow consumer warnings.
+    fn add_routes<R: SubscriptionRequest>(
+        &self,
+        event: Channels,
+        requests: &[R],
+        config: StreamConfig,
+    ) -> (
+        SubscriptionHandle,
+        Vec<(usize, Vec<SubscribedTarget>)>,
+        QueueReceiver,
+        usize,
+    ) {
+        let targets: Vec<(SubscribedTarget, usize)> = requests
+            .iter()
+            .map(|request| {
+                let target = SubscribedTarget::new(request);
+                let connection = self.connection_for(&target.target);
+                (target, connection)
+            })
+            .collect();
+        let owner = targets.first().map_or(0, |(_, connection)| *connection);
+        let (sender, rx, monitor) = self.clients[owner].subscription_queue(event, config);
+        let subscription = SubscriptionHandle::new(
+            event,
+            sender.subscription_id,
+            monitor,
+            self.clients.iter().map(WsClient::connection).collect(),
+            targets
+                .iter()
+                .map(|(target, connection)| (target.target.clone(), *connection))
+                .collect(),
+        );
+
+        let mut new_targets = Vec::new();
+        for (index, client) in self.clients.iter().enumerate() {
+            let own: Vec<SubscribedTarget> = targets
+                .iter()
+                .filter(|(_, connection)| *connection == index)
+                .map(|(target, _)| target.clone())
+                .collect();
+            if own.is_empty() {
+                continue;
+            }
+            let new = client.route_targets(event, own, &sender);
+            if !new.is_empty() {
+                new_targets.push((index, new));
+            }
+        }
+        (subscription, new_targets, rx, owner)
+    }
+
+    async fn confirm_subscriptions(
+        &self,
+        event: Channels,
+        new_targets: Vec<(usize, Vec<SubscribedTarget>)>,
+    ) -> Result<(), ClientError> {
+        try_join_all(
+            new_targets
+                .into_iter()
+                .map(|(index, targets)| self.clients[index].confirm_subscriptions(event, targets)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Waits until every connection is up.
+    pub async fn wait_for_connection(&self) {
+        join_all(self.clients.iter().map(WsClient::wait_for_connection)).await;
+    }
+
+    /// Waits for the next event of any connection, returning the connection's index with it.
+    pub async fn run_till_event(&self) -> (usize, WsEvent) {
+        let events =
+            self.clients.iter().enumerate().map(|(index, client)| {
+                Box::pin(async move { (index, client.run_till_event().await) })
+            });
+        select_all(events).await.0
+    }
+
+    pub async fn shutdown(&self, reason: &'static str) -> Result<(), ClientError> {
+        for client in &self.clients {
+            client.shutdown(reason).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Subscriber for WsPool {
+    fn subscribe_channels<R, P, F, Fut>(
+        &self,
+        event: Channels,
+        requests: Vec<R>,
+        config: StreamConfig,
+        callback: F,
+    ) -> impl Future<Output = Result<SubscriptionHandle, ClientError>> + Send
+    where
+        R: SubscriptionRequest + Send,
+        P: DeserializeOwned + Send + 'static,
+        F: FnMut(P) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        WsPool::subscribe_channels(self, event, requests, config, callback)
+    }
+
+    fn subscribe_stream<R, P>(
+        &self,
+        event: Channels,
+        requests: Vec<R>,
+        config: StreamConfig,
+    ) -> impl Future<Output = Result<SubscriptionStream<P>, ClientError>> + Send
+    where
+        R: SubscriptionRequest + Send,
+        P: DeserializeOwned + Send + 'static,
+    {
+        WsPool::subscribe_stream(self, event, requests, config)
+    }
+}
+
+/// 64 bit FNV-1a, so targets land on the same connection in every process.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
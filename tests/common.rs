@@ -1,12 +1,45 @@
+// Shared by every test file; each uses only some of the helpers.
+#![allow(dead_code)]
+
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
 use anyhow::Result;
 use ethereal_rust_sdk::{
-    async_client::client::HttpClient, enums::Environment, models::ProductDto, ws_client::WsClient,
+    async_client::{
+        builder::{HttpClientBuilder, LoadStrategy},
+        client::HttpClient,
+    },
+    enums::Environment,
+    models::{EngineType, ProductDto, SubaccountDto},
+    ws_client::WsClient,
+    ws_config::WsConfig,
+};
+use futures_util::{FutureExt, SinkExt, StreamExt};
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    body::{Bytes, Incoming},
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, Response,
 };
+use hyper_util::rt::TokioIo;
+use rust_decimal_macros::dec;
+use tokio::{
+    net::TcpListener,
+    sync::{
+        broadcast,
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        Mutex,
+    },
+    time::timeout,
+};
+use uuid::Uuid;
+use yawc::{Frame, OpCode, WebSocket};
 
 pub async fn create_test_client() -> Result<HttpClient> {
     let env = Environment::Testnet;
     let private_key = "0bb5d63b84421e1268dda020818ae30cf26e7f10e321fb820a8aa69216dea92a";
-    let http_client = HttpClient::new(env, private_key, None).await;
+    let http_client = HttpClient::new(env, private_key, None).await?;
     Ok(http_client)
 }
 
@@ -41,3 +74,416 @@ async fn test_create_test_ws_client() {
     let ws_client = create_test_ws_client().await;
     assert!(ws_client.is_ok());
 }
+
+pub const PRIVATE_KEY: &str = "0bb5d63b84421e1268dda020818ae30cf26e7f10e321fb820a8aa69216dea92a";
+/// Bytes32 encoded name of the subaccount of [`MockExchange::http_client`].
+pub const SUBACCOUNT_NAME: &str =
+    "0x7072696d61727900000000000000000000000000000000000000000000000000";
+
+/// Id of the subaccount of [`MockExchange::http_client`].
+pub fn subaccount_id() -> Uuid {
+    Uuid::from_u128(1)
+}
+
+/// Id of the BTC-USD product of [`MockExchange::http_client`].
+pub fn btc() -> Uuid {
+    Uuid::from_u128(100)
+}
+
+pub async fn within<F: Future>(future: F) -> F::Output {
+    timeout(Duration::from_secs(5), future)
+        .await
+        .expect("nothing within 5s")
+}
+
+/// A REST request received by the [`MockExchange`], with its JSON body, or `null` without one.
+#[derive(Debug)]
+pub struct RestRequest {
+    pub method: Method,
+    pub path: String,
+    pub query: String,
+    pub body: serde_json::Value,
+}
+
+type RestHandler = Arc<
+    dyn Fn(RestRequest) -> Pin<Box<dyn Future<Output = Response<Full<Bytes>>> + Send>>
+        + Send
+        + Sync,
+>;
+
+pub fn json(status: u16, body: serde_json::Value) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .unwrap()
+}
+
+/// A single page of a paginated listing.
+pub fn page(data: impl serde::Serialize) -> Response<Full<Bytes>> {
+    json(200, serde_json::json!({ "data": data, "hasNext": false }))
+}
+
+pub fn not_found() -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(404)
+        .body(Full::new(Bytes::new()))
+        .unwrap()
+}
+
+/// A WebSocket request received by the [`MockExchange`], without its id.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WsRequest {
+    /// Number of the connection it arrived on, counting from 1.
+    pub connection: usize,
+    pub id: serde_json::Value,
+    pub request: serde_json::Value,
+}
+
+impl WsRequest {
+    pub fn event(&self) -> &str {
+        self.request["event"].as_str().unwrap_or_default()
+    }
+
+    /// The usual acknowledgement of a subscribe, unsubscribe or auth request.
+    pub fn ack(&self) -> Reply {
+        Reply::Send(serde_json::json!({ "id": self.id, "ok": true }).to_string())
+    }
+}
+
+/// What the [`MockExchange`] does in response to a WebSocket request.
+pub enum Reply {
+    Send(String),
+    /// Sent after the replies to the next request on the connection.
+    Defer(String),
+    /// Close the connection once the request is recorded.
+    Close,
+}
+
+type WsHandler = Arc<dyn Fn(&WsRequest) -> Vec<Reply> + Send + Sync>;
+
+/// What the [`MockExchange`]'s WebSocket connections do once upgraded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WsMode {
+    /// Read requests and answer them, recording pings.
+    Serve,
+    /// Never read, so pings go unanswered.
+    Silent,
+    /// Close the connection right after the handshake.
+    Close,
+}
+
+/// Something which happened on the [`MockExchange`]'s WebSocket.
+#[derive(Debug, PartialEq)]
+pub enum ServerEvent {
+    /// A connection was upgraded; carries its `Sec-WebSocket-Extensions` request header.
+    Connected(Option<String>),
+    Request(WsRequest),
+    Ping,
+}
+
+#[derive(Clone)]
+enum Push {
+    Frame(String),
+    Close,
+}
+
+/// Sends frames over the [`MockExchange`]'s connections, from wherever it is held.
+#[derive(Clone)]
+pub struct Pusher(broadcast::Sender<Push>);
+
+impl Default for Pusher {
+    fn default() -> Self {
+        Self(broadcast::channel(16).0)
+    }
+}
+
+impl Pusher {
+    /// Sends a frame over every open connection.
+    pub fn push(&self, frame: impl Into<String>) {
+        let _ = self.0.send(Push::Frame(frame.into()));
+    }
+
+    pub fn close_connections(&self) {
+        let _ = self.0.send(Push::Close);
+    }
+}
+
+/// Serves REST requests with a handler, answering 404 by default, and WebSocket connections
+/// which acknowledge every request by default and send whatever is pushed.
+pub struct MockExchange {
+    addr: String,
+    pusher: Pusher,
+    events: Mutex<UnboundedReceiver<ServerEvent>>,
+}
+
+#[derive(Clone)]
+pub struct MockExchangeBuilder {
+    rest: RestHandler,
+    ws: WsHandler,
+    mode: WsMode,
+    pusher: Pusher,
+}
+
+impl MockExchangeBuilder {
+    pub fn rest<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(RestRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Response<Full<Bytes>>> + Send + 'static,
+    {
+        self.rest = Arc::new(move |request| handler(request).boxed());
+        self
+    }
+
+    pub fn ws<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&WsRequest) -> Vec<Reply> + Send + Sync + 'static,
+    {
+        self.ws = Arc::new(handler);
+        self
+    }
+
+    pub fn mode(mut self, mode: WsMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Pushes through `pusher` rather than a pusher of its own.
+    pub fn pusher(mut self, pusher: Pusher) -> Self {
+        self.pusher = pusher;
+        self
+    }
+
+    pub async fn start(self) -> MockExchange {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let pusher = self.pusher.clone();
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut connection = 0;
+            while let Ok((stream, _)) = listener.accept().await {
+                connection += 1;
+                let (mock, push, events) = (self.clone(), self.pusher.0.clone(), events_tx.clone());
+                tokio::spawn(async move {
+                    let service = service_fn(move |req| {
+                        mock.clone()
+                            .handle(req, connection, push.clone(), events.clone())
+                    });
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .with_upgrades()
+                        .await;
+                });
+            }
+        });
+        MockExchange {
+            addr,
+            pusher,
+            events: Mutex::new(events_rx),
+        }
+    }
+
+    async fn handle(
+        self,
+        mut req: Request<Incoming>,
+        connection: usize,
+        push: broadcast::Sender<Push>,
+        events: UnboundedSender<ServerEvent>,
+    ) -> yawc::Result<Response<Full<Bytes>>> {
+        if !req.headers().contains_key(hyper::header::UPGRADE) {
+            let method = req.method().clone();
+            let path = req.uri().path().to_string();
+            let query = req.uri().query().unwrap_or_default().to_string();
+            let body = req.into_body().collect().await.unwrap().to_bytes();
+            let body = if body.is_empty() {
+                serde_json::Value::Null
+            } else {
+                serde_json::from_slice(&body).unwrap()
+            };
+            let request = RestRequest {
+                method,
+                path,
+                query,
+                body,
+            };
+            return Ok((self.rest)(request).await);
+        }
+        let extensions = req
+            .headers()
+            .get("sec-websocket-extensions")
+            .map(|v| v.to_str().unwrap().to_string());
+        let (response, upgrade) = WebSocket::upgrade(&mut req)?;
+        let mut pushed = push.subscribe();
+        tokio::spawn(async move {
+            let Ok(mut ws) = upgrade.await else {
+                return;
+            };
+            let _ = events.send(ServerEvent::Connected(extensions));
+            match self.mode {
+                WsMode::Serve => {}
+                WsMode::Silent => {
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    return;
+                }
+                WsMode::Close => return,
+            }
+            let mut deferred = Vec::new();
+            loop {
+                tokio::select! {
+                    frame = ws.next() => {
+                        let Some(frame) = frame else { return };
+                        let (opcode, _, body) = frame.into_parts();
+                        match opcode {
+                            OpCode::Text => {}
+                            OpCode::Ping => {
+                                let _ = events.send(ServerEvent::Ping);
+                                continue;
+                            }
+                            _ => continue,
+                        }
+                        let mut request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                        let id = request
+                            .as_object_mut()
+                            .and_then(|request| request.remove("id"))
+                            .unwrap_or_default();
+                        let request = WsRequest { connection, id, request };
+                        let mut close = false;
+                        let mut defer = Vec::new();
+                        for reply in (self.ws)(&request) {
+                            match reply {
+                                Reply::Send(frame) => ws.send(Frame::text(frame)).await.unwrap(),
+                                Reply::Defer(frame) => defer.push(frame),
+                                Reply::Close => close = true,
+                            }
+                        }
+                        for frame in std::mem::replace(&mut deferred, defer) {
+                            ws.send(Frame::text(frame)).await.unwrap();
+                        }
+                        let _ = events.send(ServerEvent::Request(request));
+                        if close {
+                            let _ = ws.close().await;
+                            return;
+                        }
+                    }
+                    Ok(push) = pushed.recv() => match push {
+                        Push::Frame(frame) => ws.send(Frame::text(frame)).await.unwrap(),
+                        Push::Close => {
+                            let _ = ws.close().await;
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+        Ok(response.map(|_| Full::new(Bytes::new())))
+    }
+}
+
+impl MockExchange {
+    pub fn builder() -> MockExchangeBuilder {
+        MockExchangeBuilder {
+            rest: Arc::new(|_| async { not_found() }.boxed()),
+            ws: Arc::new(|request| vec![request.ack()]),
+            mode: WsMode::Serve,
+            pusher: Pusher::default(),
+        }
+    }
+
+    pub async fn start() -> Self {
+        Self::builder().start().await
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    pub fn ws_config(&self) -> WsConfig {
+        WsConfig::default().url(self.ws_url())
+    }
+
+    /// A client of the mock with one subaccount, [`subaccount_id`], and one product, BTC-USD.
+    pub async fn http_client(&self) -> HttpClient {
+        self.http_client_with(|builder| builder).await
+    }
+
+    pub async fn http_client_with(
+        &self,
+        configure: impl FnOnce(HttpClientBuilder) -> HttpClientBuilder,
+    ) -> HttpClient {
+        let builder = HttpClient::builder(Environment::Testnet, PRIVATE_KEY)
+            .base_url(format!("http://{}", self.addr))
+            .load_strategy(LoadStrategy::Lazy);
+        let mut http_client = configure(builder).build().await.unwrap();
+        http_client.subaccounts.push(SubaccountDto {
+            id: subaccount_id(),
+            name: SUBACCOUNT_NAME.to_string(),
+            ..Default::default()
+        });
+        let product = ProductDto {
+            id: btc(),
+            ticker: "BTCUSD".to_string(),
+            display_ticker: "BTC-USD".to_string(),
+            engine_type: EngineType::PERP,
+            onchain_id: 2,
+            tick_size: dec!(1),
+            lot_size: dec!(0.0001),
+            ..Default::default()
+        };
+        http_client
+            .product_id_hashmap
+            .insert(btc(), product.clone());
+        http_client
+            .product_hashmap
+            .insert("BTC-USD".to_string(), product);
+        http_client
+    }
+
+    /// A client of the mock's WebSocket, once connected.
+    pub async fn ws_client(&self) -> WsClient {
+        let ws_client = WsClient::with_config(Environment::Testnet, self.ws_config());
+        ws_client.wait_for_connection().await;
+        ws_client
+    }
+
+    /// Sends a frame over every open connection.
+    pub fn push(&self, frame: impl Into<String>) {
+        self.pusher.push(frame);
+    }
+
+    pub fn close_connections(&self) {
+        self.pusher.close_connections();
+    }
+
+    pub async fn next_event(&self) -> ServerEvent {
+        within(async { self.events.lock().await.recv().await })
+            .await
+            .unwrap()
+    }
+
+    /// The next request, skipping connections and pings.
+    pub async fn next_request(&self) -> WsRequest {
+        loop {
+            if let ServerEvent::Request(request) = self.next_event().await {
+                return request;
+            }
+        }
+    }
+
+    /// The events so far and not yet taken.
+    pub async fn events(&self) -> Vec<ServerEvent> {
+        let mut events = self.events.lock().await;
+        std::iter::from_fn(|| events.try_recv().ok()).collect()
+    }
+
+    /// The requests received so far and not yet taken.
+    pub async fn requests(&self) -> Vec<WsRequest> {
+        self.events()
+            .await
+            .into_iter()
+            .filter_map(|event| match event {
+                ServerEvent::Request(request) => Some(request),
+                _ => None,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,340 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use common::{btc, page, subaccount_id, within, MockExchange, RestRequest};
+use ethereal_rust_sdk::{
+    account_state::{AccountChange, AccountFeed, AccountState, Margin},
+    models::{
+        OrderDto, OrderFillMessageDataDInner, OrderSide, OrderStatus, OrderUpdateMessage,
+        OrderUpdateMessageData, OrderUpdateMessageDataDInner, PositionDto,
+        PositionUpdateMessageDataDInner, SubaccountBalanceDto, TokenTransferMessageData,
+    },
+};
+use futures_util::{Stream, StreamExt};
+use http_body_util::Full;
+use hyper::{body::Bytes, Response};
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn product_id() -> Uuid {
+    btc()
+}
+
+fn symbols() -> HashMap<Uuid, String> {
+    HashMap::from([(product_id(), "BTCUSD".to_string())])
+}
+
+fn order_dto(id: u128, status: OrderStatus) -> OrderDto {
+    OrderDto {
+        id: Uuid::from_u128(id),
+        client_order_id: Some(format!("cloid{id}")),
+        product_id: product_id(),
+        subaccount_id: subaccount_id(),
+        side: OrderSide::BUY,
+        status,
+        quantity: dec!(2),
+        available_quantity: dec!(2),
+        price: dec!(80000),
+        updated_at: 100,
+        ..Default::default()
+    }
+}
+
+fn position_dto(size: rust_decimal::Decimal) -> PositionDto {
+    PositionDto {
+        id: Uuid::from_u128(3),
+        product_id: product_id(),
+        side: OrderSide::BUY,
+        size,
+        cost: size * dec!(80000),
+        updated_at: 100,
+        ..Default::default()
+    }
+}
+
+fn balance(amount: rust_decimal::Decimal, used: rust_decimal::Decimal) -> SubaccountBalanceDto {
+    SubaccountBalanceDto {
+        subaccount_id: subaccount_id(),
+        amount,
+        available: amount - used,
+        total_used: used,
+        token_name: "USD".to_string(),
+        ..Default::default()
+    }
+}
+
+fn order_update(id: u128, status: OrderStatus, t: i64) -> OrderUpdateMessageDataDInner {
+    OrderUpdateMessageDataDInner {
+        id: Uuid::from_u128(id),
+        cloid: Some(format!("cloid{id}")),
+        qty: dec!(2),
+        aqty: dec!(2),
+        px: Some(dec!(80000)),
+        sd: OrderSide::BUY,
+        s: "BTCUSD".to_string(),
+        sid: subaccount_id(),
+        st: status,
+        t,
+        ..Default::default()
+    }
+}
+
+fn position_update(size: rust_decimal::Decimal) -> PositionUpdateMessageDataDInner {
+    PositionUpdateMessageDataDInner {
+        id: Uuid::from_u128(3),
+        sid: subaccount_id(),
+        s: "BTCUSD".to_string(),
+        sd: OrderSide::BUY,
+        sz: size,
+        ..Default::default()
+    }
+}
+
+fn seeded_state() -> AccountState {
+    let mut state = AccountState::new(subaccount_id(), symbols());
+    state.seed(
+        &[
+            order_dto(10, OrderStatus::New),
+            order_dto(11, OrderStatus::Filled),
+        ],
+        &[position_dto(dec!(1))],
+        vec![balance(dec!(1000), dec!(200))],
+        100,
+    );
+    state
+}
+
+#[test]
+fn test_seed_keeps_open_orders_and_positions() {
+    let state = seeded_state();
+    assert!(state.is_synced());
+    assert_eq!(state.open_orders().count(), 1);
+    let order = state.order_by_client_id("cloid10").unwrap();
+    assert_eq!(order.symbol, "BTCUSD");
+    assert_eq!(order.price, Some(dec!(80000)));
+    assert!(state.order(&Uuid::from_u128(11)).is_none());
+    assert_eq!(state.position("BTCUSD").unwrap().size, dec!(1));
+    assert_eq!(
+        state.margin(),
+        Margin {
+            total: dec!(1000),
+            available: dec!(800),
+            used: dec!(200),
+        }
+    );
+}
+
+#[test]
+fn test_order_updates_and_fills_apply_in_order() {
+    let mut state = seeded_state();
+
+    let change = state.apply_order_update(&order_update(12, OrderStatus::New, 110));
+    assert!(matches!(change, Some(AccountChange::OrderUpdated(o)) if o.id == Uuid::from_u128(12)));
+    assert_eq!(state.open_orders_for("BTCUSD").count(), 2);
+
+    let fill = OrderFillMessageDataDInner {
+        oid: Uuid::from_u128(12),
+        sid: subaccount_id(),
+        sz: dec!(0.5),
+        t: 120,
+        ..Default::default()
+    };
+    assert!(matches!(
+        state.apply_fill(&fill),
+        Some(AccountChange::Filled(_))
+    ));
+    let order = state.order(&Uuid::from_u128(12)).unwrap();
+    assert_eq!(order.filled, dec!(0.5));
+    assert_eq!(order.available_quantity, dec!(1.5));
+
+    let change = state.apply_order_update(&order_update(12, OrderStatus::Canceled, 130));
+    assert!(matches!(change, Some(AccountChange::OrderClosed(_))));
+    assert!(state.order_by_client_id("cloid12").is_none());
+
+    // Older than what was applied, e.g. received while the snapshot was loading.
+    assert_eq!(
+        state.apply_order_update(&order_update(12, OrderStatus::New, 110)),
+        None
+    );
+    assert_eq!(
+        state.apply_order_update(&order_update(10, OrderStatus::New, 90)),
+        None
+    );
+    assert!(state.order(&Uuid::from_u128(12)).is_none());
+}
+
+#[test]
+fn test_updates_older_than_snapshot_do_not_reopen() {
+    let mut state = AccountState::new(subaccount_id(), symbols());
+    // Order 13 and the position were closed before the snapshot, so REST leaves them out.
+    state.seed(
+        &[order_dto(10, OrderStatus::New)],
+        &[],
+        vec![balance(dec!(1000), dec!(0))],
+        150,
+    );
+
+    // Queued while the snapshot was loading.
+    assert_eq!(
+        state.apply_order_update(&order_update(13, OrderStatus::New, 120)),
+        None
+    );
+    assert_eq!(
+        state.apply_position_update(&position_update(dec!(1)), 120),
+        None
+    );
+    assert!(state.order(&Uuid::from_u128(13)).is_none());
+    assert!(state.position("BTCUSD").is_none());
+
+    // Newer than the snapshot.
+    assert!(matches!(
+        state.apply_order_update(&order_update(14, OrderStatus::New, 160)),
+        Some(AccountChange::OrderUpdated(_))
+    ));
+    assert!(matches!(
+        state.apply_position_update(&position_update(dec!(1)), 160),
+        Some(AccountChange::PositionUpdated(_))
+    ));
+    assert_eq!(state.open_orders().count(), 2);
+}
+
+#[test]
+fn test_updates_of_other_subaccounts_are_ignored() {
+    let mut state = seeded_state();
+    let mut update = order_update(12, OrderStatus::New, 110);
+    update.sid = Uuid::from_u128(99);
+    assert_eq!(state.apply_order_update(&update), None);
+    let transfer = TokenTransferMessageData {
+        sid: Uuid::from_u128(99),
+        ..Default::default()
+    };
+    assert_eq!(state.apply_transfer(&transfer), None);
+    assert_eq!(state.open_orders().count(), 1);
+}
+
+#[test]
+fn test_positions_are_updated_and_closed() {
+    let mut state = seeded_state();
+    let change = state.apply_position_update(&position_update(dec!(3)), 110);
+    assert!(matches!(change, Some(AccountChange::PositionUpdated(p)) if p.size == dec!(3)));
+    assert_eq!(
+        state.apply_position_update(&position_update(dec!(2)), 105),
+        None
+    );
+    assert_eq!(state.position("BTCUSD").unwrap().size, dec!(3));
+
+    let change = state.apply_position_update(&position_update(dec!(0)), 120);
+    assert!(matches!(change, Some(AccountChange::PositionClosed(_))));
+    assert_eq!(state.positions().count(), 0);
+
+    state.mark_stale();
+    assert!(!state.is_synced());
+}
+
+/// REST snapshots served by the local server, replaceable between reconciliations.
+#[derive(Clone, Default)]
+struct Snapshots {
+    orders: Arc<Mutex<Vec<OrderDto>>>,
+    positions: Arc<Mutex<Vec<PositionDto>>>,
+}
+
+/// Serves the order, position and balance endpoints.
+async fn handle(req: RestRequest, snapshots: Snapshots) -> Response<Full<Bytes>> {
+    match req.path.as_str() {
+        "/v1/order" => {
+            // Working and pending orders are listed by separate queries.
+            let pending = req.query.contains("isPending=true");
+            let orders: Vec<OrderDto> = snapshots
+                .orders
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|o| (o.status == OrderStatus::Pending) == pending)
+                .cloned()
+                .collect();
+            page(orders)
+        }
+        "/v1/position" => page(&*snapshots.positions.lock().unwrap()),
+        "/v1/subaccount/balance" => page([balance(dec!(1000), dec!(200))]),
+        path => panic!("Unexpected path {}", path),
+    }
+}
+
+async fn next_change(changes: &mut (impl Stream<Item = AccountChange> + Unpin)) -> AccountChange {
+    within(changes.next()).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_feed_applies_updates_and_reconciles_after_reconnect() {
+    let snapshots = Snapshots::default();
+    snapshots
+        .orders
+        .lock()
+        .unwrap()
+        .push(order_dto(10, OrderStatus::New));
+    snapshots
+        .orders
+        .lock()
+        .unwrap()
+        .push(order_dto(11, OrderStatus::Pending));
+    let server = {
+        let snapshots = snapshots.clone();
+        MockExchange::builder()
+            .rest(move |req| handle(req, snapshots.clone()))
+            .start()
+            .await
+    };
+    let http_client = server.http_client().await;
+    let ws_client = server.ws_client().await;
+
+    let feed = AccountFeed::subscribe(&http_client, &ws_client, subaccount_id())
+        .await
+        .unwrap();
+    let view = feed.view();
+    let mut changes = Box::pin(feed.changes());
+    assert!(view.is_synced());
+    assert!(view.order_by_client_id("cloid10").is_some());
+    assert!(view.order_by_client_id("cloid11").is_some());
+    assert_eq!(view.margin().available, dec!(800));
+
+    // Sent after the snapshot was requested.
+    let t = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+    let message = OrderUpdateMessage {
+        t,
+        data: OrderUpdateMessageData {
+            t,
+            d: vec![order_update(12, OrderStatus::New, t)],
+        },
+        ..Default::default()
+    };
+    server.push(serde_json::to_string(&message).unwrap());
+    assert!(matches!(
+        next_change(&mut changes).await,
+        AccountChange::OrderUpdated(order) if order.client_order_id.as_deref() == Some("cloid12")
+    ));
+    assert_eq!(view.open_orders().len(), 3);
+
+    // Order 10 was cancelled and a position opened while disconnected.
+    snapshots.orders.lock().unwrap().clear();
+    snapshots
+        .positions
+        .lock()
+        .unwrap()
+        .push(position_dto(dec!(1)));
+    server.close_connections();
+    assert_eq!(next_change(&mut changes).await, AccountChange::Reconciled);
+    assert!(view.is_synced());
+    assert!(view.open_orders().is_empty());
+    assert_eq!(view.position("BTCUSD").unwrap().size, dec!(1));
+
+    drop(feed);
+    ws_client.shutdown("test").await.unwrap();
+}
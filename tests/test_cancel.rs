@@ -0,0 +1,409 @@
+mod common;
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use common::{btc, json, MockExchange, RestRequest};
+use ethereal_rust_sdk::{
+    async_client::{client::HttpClient, session::SubaccountSelector},
+    cancel::{CancelBatch, CancelFilter, CancelReport, MAX_CANCEL_BATCH},
+    error::Error,
+    models::{
+        CancelOrderResultCode, CancelOrderResultDto, OrderDto, OrderSide, OrderStatus, StopType,
+    },
+    signing::get_nonce,
+};
+use http_body_util::Full;
+use hyper::{body::Bytes, Method, Response};
+use uuid::Uuid;
+
+fn eth() -> Uuid {
+    Uuid::from_u128(101)
+}
+
+fn order(id: u128, product_id: Uuid, side: OrderSide, status: OrderStatus) -> OrderDto {
+    OrderDto {
+        id: Uuid::from_u128(id),
+        product_id,
+        side,
+        status,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_batches_respect_the_request_limit() {
+    let ids: Vec<Uuid> = (0..450).map(Uuid::from_u128).collect();
+    let repeated: Vec<Uuid> = ids.iter().chain(ids.iter()).cloned().collect();
+    let client_order_ids = vec!["a1".to_string(), "a2".to_string(), "a1".to_string()];
+
+    let batches = CancelBatch::split(&repeated, &client_order_ids);
+    let sizes: Vec<(usize, usize)> = batches
+        .iter()
+        .map(|b| (b.order_ids.len(), b.client_order_ids.len()))
+        .collect();
+    assert_eq!(
+        sizes,
+        vec![
+            (MAX_CANCEL_BATCH, 0),
+            (MAX_CANCEL_BATCH, 0),
+            (50, 0),
+            (0, 2)
+        ]
+    );
+    assert_eq!(batches[0].order_ids[0], ids[0]);
+    assert_eq!(batches[2].order_ids[49], ids[449]);
+    assert!(CancelBatch::split(&[], &[]).is_empty());
+}
+
+#[test]
+fn test_report_aggregates_result_codes() {
+    let result = |id, result| CancelOrderResultDto {
+        id: Uuid::from_u128(id),
+        result,
+        ..Default::default()
+    };
+    let mut report = CancelReport {
+        results: vec![
+            result(1, CancelOrderResultCode::Ok),
+            result(2, CancelOrderResultCode::AlreadyFilled),
+            result(3, CancelOrderResultCode::Ok),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(
+        report.cancelled(),
+        vec![Uuid::from_u128(1), Uuid::from_u128(3)]
+    );
+    assert_eq!(report.count(CancelOrderResultCode::Ok), 2);
+    assert_eq!(
+        report.by_code().into_iter().collect::<Vec<_>>(),
+        vec![
+            (CancelOrderResultCode::Ok, 2),
+            (CancelOrderResultCode::AlreadyFilled, 1)
+        ]
+    );
+    assert!(report.is_complete());
+
+    report
+        .results
+        .push(result(4, CancelOrderResultCode::NotFound));
+    assert!(!report.is_complete());
+}
+
+#[test]
+fn test_nonces_are_unique_and_increasing() {
+    let nonces: Vec<u64> = (0..1000).map(|_| get_nonce()).collect();
+    assert!(nonces.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn test_filter_matches_working_orders() {
+    let filter = CancelFilter::all().product("BTC-USD").side(OrderSide::SELL);
+    let matches = |order: &OrderDto| filter.matches(order, Some(btc()));
+    assert!(matches(&order(1, btc(), OrderSide::SELL, OrderStatus::New)));
+    assert!(matches(&order(
+        1,
+        btc(),
+        OrderSide::SELL,
+        OrderStatus::Pending
+    )));
+    assert!(!matches(&order(1, btc(), OrderSide::BUY, OrderStatus::New)));
+    assert!(!matches(&order(
+        1,
+        eth(),
+        OrderSide::SELL,
+        OrderStatus::New
+    )));
+    assert!(!matches(&order(
+        1,
+        btc(),
+        OrderSide::SELL,
+        OrderStatus::Filled
+    )));
+}
+
+/// How the local server answers a cancel request.
+#[derive(Clone, Copy)]
+enum Reply {
+    Cancel,
+    NonceAlreadyUsed,
+    Fail,
+}
+
+/// Working and pending orders of the subaccount, and the cancel requests received.
+#[derive(Clone, Default)]
+struct Exchange {
+    working: Vec<OrderDto>,
+    pending: Vec<OrderDto>,
+    queries: Arc<Mutex<Vec<String>>>,
+    cancels: Arc<Mutex<Vec<serde_json::Value>>>,
+    replies: Arc<Mutex<VecDeque<Reply>>>,
+}
+
+impl Exchange {
+    fn cancels(&self) -> Vec<serde_json::Value> {
+        self.cancels.lock().unwrap().clone()
+    }
+}
+
+fn ids(value: &serde_json::Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|ids| {
+            ids.iter()
+                .map(|id| id.as_str().unwrap().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+async fn handle(req: RestRequest, exchange: Exchange) -> Response<Full<Bytes>> {
+    if req.method == Method::GET {
+        assert_eq!(req.path, "/v1/order");
+        let orders = if req.query.contains("isPending=true") {
+            &exchange.pending
+        } else {
+            &exchange.working
+        };
+        // Pages of 100, with the offset as cursor.
+        let start: usize = req
+            .query
+            .split('&')
+            .find_map(|param| param.strip_prefix("cursor="))
+            .map_or(0, |cursor| cursor.parse().unwrap());
+        let end = orders.len().min(start + 100);
+        exchange.queries.lock().unwrap().push(req.query);
+        return json(
+            200,
+            serde_json::json!({
+                "data": orders[start..end],
+                "hasNext": end < orders.len(),
+                "nextCursor": end.to_string(),
+            }),
+        );
+    }
+    assert_eq!(req.path, "/v1/order/cancel");
+    let request = req.body;
+    exchange.cancels.lock().unwrap().push(request.clone());
+    let reply = exchange
+        .replies
+        .lock()
+        .unwrap()
+        .pop_front()
+        .unwrap_or(Reply::Cancel);
+    let code = match reply {
+        Reply::Cancel => CancelOrderResultCode::Ok,
+        Reply::NonceAlreadyUsed => CancelOrderResultCode::NonceAlreadyUsed,
+        Reply::Fail => {
+            return json(500, serde_json::json!({ "message": "unavailable" }));
+        }
+    };
+    let by_id = ids(&request["data"]["orderIds"])
+        .into_iter()
+        .map(|id| CancelOrderResultDto {
+            id: Uuid::parse_str(&id).unwrap(),
+            result: code,
+            ..Default::default()
+        });
+    let by_client_id = ids(&request["data"]["clientOrderIds"])
+        .into_iter()
+        .map(|id| CancelOrderResultDto {
+            id: Uuid::new_v4(),
+            client_order_id: Some(id),
+            result: code,
+        });
+    let results: Vec<CancelOrderResultDto> = by_id.chain(by_client_id).collect();
+    json(200, serde_json::json!({ "data": results }))
+}
+
+async fn client(exchange: Exchange) -> HttpClient {
+    MockExchange::builder()
+        .rest(move |req| handle(req, exchange.clone()))
+        .start()
+        .await
+        .http_client()
+        .await
+}
+
+#[tokio::test]
+async fn test_cancel_all_batches_working_and_pending_orders() {
+    let exchange = Exchange {
+        working: (0..250)
+            .map(|id| order(id, btc(), OrderSide::BUY, OrderStatus::New))
+            .collect(),
+        pending: vec![order(1000, eth(), OrderSide::SELL, OrderStatus::Pending)],
+        ..Default::default()
+    };
+    let http_client = client(exchange.clone()).await;
+    let report = http_client
+        .session(SubaccountSelector::First)
+        .unwrap()
+        .cancel_all()
+        .await
+        .unwrap();
+
+    let cancels = exchange.cancels();
+    let sizes: Vec<usize> = cancels
+        .iter()
+        .map(|c| ids(&c["data"]["orderIds"]).len())
+        .collect();
+    assert_eq!(sizes, vec![200, 51]);
+    let nonces: Vec<u64> = cancels
+        .iter()
+        .map(|c| c["data"]["nonce"].as_str().unwrap().parse().unwrap())
+        .collect();
+    assert!(nonces[0] < nonces[1]);
+    assert!(cancels[0]["data"].get("clientOrderIds").is_none());
+
+    assert_eq!(report.cancelled().len(), 251);
+    assert!(report.cancelled().contains(&Uuid::from_u128(1000)));
+    assert!(report.is_complete());
+}
+
+#[tokio::test]
+async fn test_cancel_by_product_and_side() {
+    let exchange = Exchange {
+        working: vec![
+            order(1, btc(), OrderSide::BUY, OrderStatus::New),
+            order(2, btc(), OrderSide::SELL, OrderStatus::FilledPartial),
+            order(3, eth(), OrderSide::SELL, OrderStatus::New),
+        ],
+        ..Default::default()
+    };
+    let http_client = client(exchange.clone()).await;
+
+    let report = http_client
+        .cancel_where(
+            SubaccountSelector::First,
+            CancelFilter::all().product("BTC-USD").side(OrderSide::SELL),
+        )
+        .await
+        .unwrap();
+    assert_eq!(report.cancelled(), vec![Uuid::from_u128(2)]);
+    let queries = exchange.queries.lock().unwrap().clone();
+    assert_eq!(queries.len(), 2);
+    assert!(queries
+        .iter()
+        .all(|q| q.contains(&format!("productIds={}", btc())) && q.contains("side=")));
+
+    let report = http_client
+        .cancel_by_side(SubaccountSelector::First, OrderSide::BUY)
+        .await
+        .unwrap();
+    assert_eq!(report.cancelled(), vec![Uuid::from_u128(1)]);
+
+    let unknown = http_client
+        .cancel_by_product(SubaccountSelector::First, "DOGE-USD")
+        .await;
+    assert!(matches!(unknown, Err(Error::Validation(_))));
+}
+
+#[tokio::test]
+async fn test_cancel_by_client_order_ids_skips_malformed_ids() {
+    let exchange = Exchange::default();
+    exchange
+        .replies
+        .lock()
+        .unwrap()
+        .push_back(Reply::NonceAlreadyUsed);
+    let http_client = client(exchange.clone()).await;
+
+    let client_order_ids = vec![
+        "bot1".to_string(),
+        "not-alphanumeric!".to_string(),
+        "bot1".to_string(),
+        "bot2".to_string(),
+    ];
+    let report = http_client
+        .cancel_by_client_order_ids(SubaccountSelector::First, &client_order_ids)
+        .await
+        .unwrap();
+
+    // The request whose nonce was already used is sent again with a new one.
+    let cancels = exchange.cancels();
+    assert_eq!(cancels.len(), 2);
+    assert_eq!(
+        cancels[0]["data"]["clientOrderIds"],
+        cancels[1]["data"]["clientOrderIds"]
+    );
+    assert_ne!(cancels[0]["data"]["nonce"], cancels[1]["data"]["nonce"]);
+    assert_eq!(
+        ids(&cancels[1]["data"]["clientOrderIds"]),
+        vec!["bot1", "bot2"]
+    );
+
+    assert_eq!(report.count(CancelOrderResultCode::Ok), 2);
+    assert_eq!(report.invalid_client_order_ids, vec!["not-alphanumeric!"]);
+    assert!(!report.is_complete());
+}
+
+#[tokio::test]
+async fn test_failed_batches_are_reported() {
+    let exchange = Exchange::default();
+    exchange.replies.lock().unwrap().push_back(Reply::Fail);
+    let http_client = client(exchange.clone()).await;
+
+    let order_ids: Vec<Uuid> = (0..300).map(Uuid::from_u128).collect();
+    let report = http_client
+        .cancel_order_ids(SubaccountSelector::First, &order_ids)
+        .await
+        .unwrap();
+
+    assert_eq!(exchange.cancels().len(), 2);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].batch.order_ids.len(), 200);
+    assert!(matches!(
+        report.failed[0].error,
+        Error::Api { status: 500, .. }
+    ));
+    assert_eq!(report.cancelled(), order_ids[200..].to_vec());
+    assert!(!report.is_complete());
+}
+
+#[tokio::test]
+async fn test_malformed_order_ids_are_an_error() {
+    let http_client = client(Exchange::default()).await;
+    let result = http_client
+        .cancel_orders(SubaccountSelector::First, vec!["not-a-uuid".to_string()])
+        .await;
+    assert!(matches!(result, Err(Error::Validation(_))));
+}
+
+#[tokio::test]
+async fn test_open_and_stop_orders_list_every_page() {
+    let exchange = Exchange {
+        working: (0..250)
+            .map(|id| {
+                let status = if id % 2 == 0 {
+                    OrderStatus::New
+                } else {
+                    OrderStatus::FilledPartial
+                };
+                order(id, btc(), OrderSide::BUY, status)
+            })
+            .collect(),
+        pending: vec![order(1000, btc(), OrderSide::SELL, OrderStatus::Pending)],
+        ..Default::default()
+    };
+    let http_client = client(exchange.clone()).await;
+    let session = http_client.session(SubaccountSelector::First).unwrap();
+
+    let orders = session.get_open_orders().await.unwrap();
+    assert_eq!(orders.len(), 251);
+    assert!(orders.iter().any(|o| o.status == OrderStatus::Pending));
+    assert!(orders
+        .iter()
+        .any(|o| o.status == OrderStatus::FilledPartial));
+    // Three pages of working orders, then the pending ones.
+    assert_eq!(exchange.queries.lock().unwrap().len(), 4);
+
+    exchange.queries.lock().unwrap().clear();
+    let stops = session.get_stop_orders(vec![StopType::LOSS]).await.unwrap();
+    assert_eq!(stops.len(), 251);
+    let queries = exchange.queries.lock().unwrap().clone();
+    assert!(queries.iter().all(|q| q.contains("stopTypes=")));
+    assert!(queries.last().unwrap().contains("isPending=true"));
+}
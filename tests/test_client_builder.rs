@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use ethereal_rust_sdk::async_client::builder::{ClientBuildError, LoadStrategy};
+use ethereal_rust_sdk::async_client::client::HttpClient;
+use ethereal_rust_sdk::enums::Environment;
+
+const TEST_PRIVATE_KEY: &str = "0bb5d63b84421e1268dda020818ae30cf26e7f10e321fb820a8aa69216dea92a";
+// Nothing listens on the discard port, so requests fail fast without touching the network.
+const UNREACHABLE_URL: &str = "http://127.0.0.1:9";
+
+#[tokio::test]
+async fn test_invalid_private_key_is_an_error() {
+    let result = HttpClient::builder(Environment::Testnet, "not-a-key")
+        .load_strategy(LoadStrategy::Lazy)
+        .build()
+        .await;
+    assert!(matches!(
+        result,
+        Err(ClientBuildError::InvalidPrivateKey(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_lazy_client_skips_loading() {
+    let client = HttpClient::builder(Environment::Testnet, TEST_PRIVATE_KEY)
+        .base_url(UNREACHABLE_URL)
+        .user_agent("ethereal-test")
+        .timeout(Duration::from_secs(1))
+        .load_strategy(LoadStrategy::Lazy)
+        .build()
+        .await
+        .unwrap();
+    assert!(client.subaccounts.is_empty());
+    assert!(client.product_hashmap.is_empty());
+    assert!(client
+        .get_subaccount(ethereal_rust_sdk::async_client::session::SubaccountSelector::First)
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_eager_client_reports_load_failure() {
+    let result = HttpClient::builder(Environment::Testnet, TEST_PRIVATE_KEY)
+        .base_url(UNREACHABLE_URL)
+        .connect_timeout(Duration::from_secs(1))
+        .build()
+        .await;
+    assert!(matches!(result, Err(ClientBuildError::Subaccounts(_))));
+
+    let mut client = HttpClient::builder(Environment::Testnet, TEST_PRIVATE_KEY)
+        .base_url(UNREACHABLE_URL)
+        .load_strategy(LoadStrategy::Lazy)
+        .build()
+        .await
+        .unwrap();
+    let result = client.refresh_products().await;
+    assert!(matches!(result, Err(ClientBuildError::Products(_))));
+}
@@ -0,0 +1,231 @@
+mod common;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{json, MockExchange, RestRequest};
+use ethereal_rust_sdk::{
+    async_client::{
+        client::{HttpClient, SubmitRetryPolicy},
+        session::SubaccountSelector,
+    },
+    error::Error,
+    models::{OrderDto, OrderSide, SubmitCreatedCode, SubmitOrderCreatedDto, SubmitOrderData},
+    order_builder::{validate_client_order_id, ClientOrderIdStrategy, OrderBuilder},
+};
+use http_body_util::Full;
+use hyper::{body::Bytes, Method, Response};
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn client_order_id(order: &SubmitOrderData) -> Option<String> {
+    match order {
+        SubmitOrderData::SubmitOrderLimitDtoData(data) => data.client_order_id.clone(),
+        SubmitOrderData::SubmitOrderMarketDtoData(data) => data.client_order_id.clone(),
+    }
+}
+
+#[test]
+fn test_generated_ids_are_valid() {
+    let uuid = ClientOrderIdStrategy::Uuid.generate().unwrap();
+    assert!(Uuid::parse_str(&uuid).is_ok());
+
+    let prefixed = ClientOrderIdStrategy::prefixed("mm1").unwrap();
+    assert!(
+        matches!(&prefixed, ClientOrderIdStrategy::Prefixed(prefix) if prefix.as_str() == "mm1")
+    );
+    let first = prefixed.generate().unwrap();
+    let second = prefixed.generate().unwrap();
+    assert_eq!(first.len(), 32);
+    assert!(first.starts_with("mm1"));
+    assert_ne!(first, second);
+    validate_client_order_id(&first).unwrap();
+
+    assert_eq!(ClientOrderIdStrategy::Disabled.generate(), None);
+    assert!(ClientOrderIdStrategy::prefixed("not-alphanumeric").is_err());
+    assert!(ClientOrderIdStrategy::prefixed("a".repeat(25)).is_err());
+    assert!(ClientOrderIdStrategy::prefixed("a".repeat(24)).is_ok());
+}
+
+/// Orders accepted by the local server, and how it fails the first submission.
+#[derive(Clone)]
+struct Exchange {
+    orders: Arc<Mutex<Vec<OrderDto>>>,
+    submissions: Arc<Mutex<Vec<String>>>,
+    first: Failure,
+}
+
+#[derive(Clone, Copy)]
+enum Failure {
+    /// Accept the order but answer after the client's timeout.
+    LandThenTimeout,
+    /// Refuse the order with a 503.
+    Unavailable,
+}
+
+async fn handle(req: RestRequest, exchange: Exchange) -> Response<Full<Bytes>> {
+    if req.method == Method::GET {
+        assert_eq!(req.path, "/v1/order");
+        let orders: Vec<OrderDto> = exchange
+            .orders
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|o| {
+                req.query.contains(&format!(
+                    "clientOrderId={}",
+                    o.client_order_id.as_ref().unwrap()
+                ))
+            })
+            .cloned()
+            .collect();
+        return json(200, serde_json::json!({ "data": orders, "hasNext": false }));
+    }
+    let cloid = req.body["data"]["clientOrderId"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let attempt = {
+        let mut submissions = exchange.submissions.lock().unwrap();
+        submissions.push(cloid.clone());
+        submissions.len()
+    };
+    if attempt == 1 {
+        if let Failure::Unavailable = exchange.first {
+            return json(503, serde_json::json!({ "message": "unavailable" }));
+        }
+    }
+    let id = Uuid::new_v4();
+    exchange.orders.lock().unwrap().push(OrderDto {
+        id,
+        client_order_id: Some(cloid.clone()),
+        filled: dec!(0.001),
+        ..Default::default()
+    });
+    if attempt == 1 {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+    let created = SubmitOrderCreatedDto {
+        id,
+        client_order_id: Some(cloid),
+        filled: dec!(0),
+        result: SubmitCreatedCode::Ok,
+    };
+    json(200, serde_json::to_value(created).unwrap())
+}
+
+async fn client(first: Failure, client_order_ids: ClientOrderIdStrategy) -> (HttpClient, Exchange) {
+    let exchange = Exchange {
+        orders: Default::default(),
+        submissions: Default::default(),
+        first,
+    };
+    let server = {
+        let exchange = exchange.clone();
+        MockExchange::builder()
+            .rest(move |req| handle(req, exchange.clone()))
+            .start()
+            .await
+    };
+    let http_client = server
+        .http_client_with(|builder| {
+            builder
+                .timeout(Duration::from_millis(300))
+                .client_order_ids(client_order_ids)
+        })
+        .await;
+    (http_client, exchange)
+}
+
+fn order() -> OrderBuilder {
+    OrderBuilder::limit("BTC-USD", OrderSide::BUY, dec!(0.001), dec!(80000))
+}
+
+fn policy() -> SubmitRetryPolicy {
+    SubmitRetryPolicy {
+        max_attempts: 3,
+        lookup_delay: Duration::from_millis(10),
+    }
+}
+
+#[tokio::test]
+async fn test_prepared_orders_get_a_client_order_id() {
+    let (http_client, _) = client(Failure::Unavailable, ClientOrderIdStrategy::default()).await;
+    let prepared = http_client
+        .prepare_order(SubaccountSelector::First, order())
+        .unwrap();
+    let cloid = client_order_id(&prepared.data).unwrap();
+    assert!(Uuid::parse_str(&cloid).is_ok());
+
+    let prepared = http_client
+        .prepare_order(SubaccountSelector::First, order().client_order_id("mine1"))
+        .unwrap();
+    assert_eq!(client_order_id(&prepared.data).as_deref(), Some("mine1"));
+
+    let (http_client, _) = client(Failure::Unavailable, ClientOrderIdStrategy::Disabled).await;
+    let prepared = http_client
+        .prepare_order(SubaccountSelector::First, order())
+        .unwrap();
+    assert_eq!(client_order_id(&prepared.data), None);
+}
+
+#[tokio::test]
+async fn test_timed_out_order_which_landed_is_not_resubmitted() {
+    let strategy = ClientOrderIdStrategy::prefixed("bot").unwrap();
+    let (http_client, exchange) = client(Failure::LandThenTimeout, strategy).await;
+    let session = http_client.session(SubaccountSelector::First).unwrap();
+
+    let created = session
+        .submit_order_with_retry(order(), policy())
+        .await
+        .unwrap();
+    let submissions = exchange.submissions.lock().unwrap().clone();
+    assert_eq!(submissions.len(), 1);
+    assert_eq!(created.client_order_id.as_ref(), Some(&submissions[0]));
+    assert_eq!(created.id, exchange.orders.lock().unwrap()[0].id);
+    assert_eq!(created.filled, dec!(0.001));
+
+    let found = session
+        .get_order_by_client_id(&submissions[0])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id, created.id);
+    assert!(session
+        .get_order_by_client_id("other")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_order_which_did_not_land_is_resubmitted_with_the_same_id() {
+    let (http_client, exchange) =
+        client(Failure::Unavailable, ClientOrderIdStrategy::Disabled).await;
+    let created = http_client
+        .submit_order_with_retry(SubaccountSelector::First, order(), policy())
+        .await
+        .unwrap();
+    let submissions = exchange.submissions.lock().unwrap().clone();
+    assert_eq!(submissions.len(), 2);
+    // An id is generated for the retry even when the client does not generate them.
+    assert_eq!(submissions[0], submissions[1]);
+    assert_eq!(created.client_order_id.as_ref(), Some(&submissions[0]));
+    assert_eq!(exchange.orders.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_gives_up_after_the_last_attempt() {
+    let (http_client, exchange) = client(Failure::Unavailable, ClientOrderIdStrategy::Uuid).await;
+    let policy = SubmitRetryPolicy {
+        max_attempts: 1,
+        ..policy()
+    };
+    let result = http_client
+        .submit_order_with_retry(SubaccountSelector::First, order(), policy)
+        .await;
+    assert!(matches!(result, Err(Error::Api { status: 503, .. })));
+    assert_eq!(exchange.submissions.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,97 @@
+use ethereal_rust_sdk::apis::{self, ResponseContent};
+use ethereal_rust_sdk::models::{RateLimitErrorType, SubmitFailedCode};
+use ethereal_rust_sdk::order_builder::OrderBuilderError;
+use ethereal_rust_sdk::ws_client::ClientError;
+use ethereal_rust_sdk::Error;
+use rust_decimal_macros::dec;
+
+fn get_response_error(status: u16, content: &str) -> Error {
+    apis::Error::<()>::ResponseError(ResponseContent {
+        status: reqwest::StatusCode::from_u16(status).unwrap(),
+        content: content.to_string(),
+        entity: None,
+    })
+    .into()
+}
+
+#[test]
+fn test_rate_limited_is_retryable() {
+    let error = get_response_error(
+        429,
+        r#"{"error":"Too Many Requests","message":"Slow down","statusCode":"429","type":"RATE_LIMIT_ACCOUNT"}"#,
+    );
+    match &error {
+        Error::RateLimited { kind, message } => {
+            assert_eq!(*kind, Some(RateLimitErrorType::RateLimitAccount));
+            assert_eq!(message, "Slow down");
+        }
+        other => panic!("Expected rate limit, got {:?}", other),
+    }
+    assert!(error.is_retryable());
+}
+
+#[test]
+fn test_order_rejection_is_classified() {
+    let error = get_response_error(
+        422,
+        r#"{"error":"Unprocessable Entity","message":"InsufficientBalance","statusCode":"422"}"#,
+    );
+    assert!(matches!(
+        error,
+        Error::OrderRejected {
+            code: SubmitFailedCode::InsufficientBalance
+        }
+    ));
+    assert!(!error.is_retryable());
+
+    let error = get_response_error(
+        422,
+        r#"{"error":"Unprocessable Entity","message":"InvalidQuantity","statusCode":"422"}"#,
+    );
+    assert!(matches!(
+        error,
+        Error::OrderRejected {
+            code: SubmitFailedCode::InvalidQuantity
+        }
+    ));
+
+    let error = get_response_error(
+        422,
+        r#"{"error":"Unprocessable Entity","message":"quantity must be positive","statusCode":"422"}"#,
+    );
+    assert!(matches!(error, Error::Validation(_)));
+}
+
+#[test]
+fn test_status_classification() {
+    assert!(matches!(
+        get_response_error(401, r#"{"message":"Unauthorized"}"#),
+        Error::Auth(_)
+    ));
+    assert!(matches!(
+        get_response_error(403, r#"{"message":"Forbidden"}"#),
+        Error::Auth(_)
+    ));
+    assert!(matches!(
+        get_response_error(400, r#"{"message":"Bad Request"}"#),
+        Error::Validation(_)
+    ));
+
+    let error = get_response_error(503, "Service Unavailable");
+    assert!(matches!(error, Error::Api { status: 503, .. }));
+    assert!(error.is_retryable());
+    assert!(!get_response_error(404, "Not Found").is_retryable());
+}
+
+#[test]
+fn test_local_errors_are_classified() {
+    let error: Error = OrderBuilderError::InvalidQuantity(dec!(0)).into();
+    assert!(matches!(error, Error::Validation(_)));
+
+    let error: Error = serde_json::from_str::<u32>("not json").unwrap_err().into();
+    assert!(matches!(error, Error::Deserialization(_)));
+
+    let error: Error = ClientError::SubscriptionError("unknown channel".to_string()).into();
+    assert!(matches!(error, Error::Validation(_)));
+    assert!(!error.is_retryable());
+}
@@ -0,0 +1,348 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{json, not_found, subaccount_id, MockExchange, RestRequest};
+use ethereal_rust_sdk::{
+    managed_order::{
+        is_legal_transition, OrderLifecycle, OrderTracker, TransitionError, WaitError,
+    },
+    models::{
+        OrderDto, OrderFillMessage, OrderFillMessageData, OrderFillMessageDataDInner, OrderStatus,
+        OrderUpdateMessage, OrderUpdateMessageData, OrderUpdateMessageDataDInner,
+        SubmitOrderCreatedDto,
+    },
+    ws_client::WsClient,
+};
+use http_body_util::Full;
+use hyper::{body::Bytes, Response};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn update(id: u128, status: OrderStatus, fill: Decimal, t: i64) -> OrderUpdateMessageDataDInner {
+    OrderUpdateMessageDataDInner {
+        id: Uuid::from_u128(id),
+        cloid: Some(format!("cloid{id}")),
+        qty: dec!(2),
+        aqty: dec!(2) - fill,
+        fill,
+        s: "BTCUSD".to_string(),
+        sid: subaccount_id(),
+        st: status,
+        t,
+        ..Default::default()
+    }
+}
+
+fn fill(fill_id: u128, order_id: u128, size: Decimal) -> OrderFillMessageDataDInner {
+    OrderFillMessageDataDInner {
+        id: Uuid::from_u128(fill_id),
+        oid: Uuid::from_u128(order_id),
+        sid: subaccount_id(),
+        sz: size,
+        s: "BTCUSD".to_string(),
+        ..Default::default()
+    }
+}
+
+fn lifecycle() -> OrderLifecycle {
+    OrderLifecycle::new(Uuid::from_u128(10), None)
+}
+
+#[test]
+fn test_legal_transitions() {
+    use OrderStatus::*;
+    assert!(is_legal_transition(Pending, New));
+    assert!(is_legal_transition(Pending, Rejected));
+    assert!(is_legal_transition(New, FilledPartial));
+    assert!(is_legal_transition(New, Filled));
+    assert!(is_legal_transition(FilledPartial, FilledPartial));
+    assert!(is_legal_transition(FilledPartial, Canceled));
+    assert!(!is_legal_transition(New, Pending));
+    assert!(!is_legal_transition(FilledPartial, New));
+    assert!(!is_legal_transition(Filled, Canceled));
+    assert!(!is_legal_transition(Canceled, Canceled));
+}
+
+#[test]
+fn test_fills_advance_the_status() {
+    let mut order = lifecycle();
+    assert_eq!(
+        order.apply_update(&update(10, OrderStatus::New, dec!(0), 100)),
+        Ok(Some(OrderStatus::New))
+    );
+    assert_eq!(order.client_order_id(), Some("cloid10"));
+
+    assert_eq!(
+        order.apply_fill(&fill(1, 10, dec!(0.5))),
+        Ok(Some(OrderStatus::FilledPartial))
+    );
+    // Repeated fills count once.
+    assert_eq!(order.apply_fill(&fill(1, 10, dec!(0.5))), Ok(None));
+    assert_eq!(order.filled(), dec!(0.5));
+    assert_eq!(order.available_quantity(), dec!(1.5));
+
+    // An update sent before the fill does not take the order back.
+    assert_eq!(
+        order.apply_update(&update(10, OrderStatus::New, dec!(0), 110)),
+        Ok(None)
+    );
+    assert_eq!(order.status(), OrderStatus::FilledPartial);
+    assert!(order.anomalies().is_empty());
+
+    assert_eq!(
+        order.apply_fill(&fill(2, 10, dec!(1.5))),
+        Ok(Some(OrderStatus::Filled))
+    );
+    assert!(order.is_terminal());
+    assert_eq!(order.available_quantity(), dec!(0));
+    // The update for the last fill changes nothing.
+    assert_eq!(
+        order.apply_update(&update(10, OrderStatus::Filled, dec!(2), 120)),
+        Ok(None)
+    );
+    assert_eq!(order.filled(), dec!(2));
+}
+
+#[test]
+fn test_fill_before_first_update() {
+    let mut order = lifecycle();
+    assert_eq!(
+        order.apply_fill(&fill(1, 10, dec!(2))),
+        Ok(Some(OrderStatus::FilledPartial))
+    );
+    // Only the update tells the quantity.
+    assert_eq!(
+        order.apply_update(&update(10, OrderStatus::New, dec!(0), 100)),
+        Ok(Some(OrderStatus::Filled))
+    );
+    assert!(order.anomalies().is_empty());
+}
+
+#[test]
+fn test_illegal_and_out_of_order_updates_are_flagged() {
+    let mut order = lifecycle();
+    order
+        .apply_update(&update(10, OrderStatus::Canceled, dec!(0), 100))
+        .unwrap();
+
+    let illegal = TransitionError::Illegal {
+        from: OrderStatus::Canceled,
+        to: OrderStatus::FilledPartial,
+    };
+    assert_eq!(
+        order.apply_update(&update(10, OrderStatus::FilledPartial, dec!(1), 110)),
+        Err(illegal.clone())
+    );
+    let out_of_order = TransitionError::OutOfOrder {
+        received: 90,
+        last: 100,
+    };
+    assert_eq!(
+        order.apply_update(&update(10, OrderStatus::New, dec!(0), 90)),
+        Err(out_of_order.clone())
+    );
+    assert_eq!(order.status(), OrderStatus::Canceled);
+    assert_eq!(order.anomalies(), &[illegal, out_of_order]);
+}
+
+#[test]
+fn test_overfill_is_flagged() {
+    let mut order = lifecycle();
+    order
+        .apply_update(&update(10, OrderStatus::New, dec!(0), 100))
+        .unwrap();
+    order.apply_fill(&fill(1, 10, dec!(1.5))).unwrap();
+    assert_eq!(
+        order.apply_fill(&fill(2, 10, dec!(1))),
+        Err(TransitionError::Overfilled {
+            filled: dec!(2.5),
+            quantity: dec!(2),
+        })
+    );
+    assert_eq!(order.status(), OrderStatus::Filled);
+}
+
+/// Orders served by `GET /v1/order/{id}`.
+type RestOrders = Arc<Mutex<HashMap<Uuid, OrderDto>>>;
+
+/// Serves the orders in `rest`.
+async fn handle(req: RestRequest, rest: RestOrders) -> Response<Full<Bytes>> {
+    let id = req.path.trim_start_matches("/v1/order/");
+    let order = rest
+        .lock()
+        .unwrap()
+        .get(&Uuid::parse_str(id).unwrap())
+        .cloned();
+    match order {
+        Some(order) => json(200, serde_json::to_value(order).unwrap()),
+        None => not_found(),
+    }
+}
+
+struct Harness {
+    tracker: OrderTracker,
+    ws_client: WsClient,
+    server: MockExchange,
+    rest: RestOrders,
+}
+
+async fn tracker() -> Harness {
+    let rest = RestOrders::default();
+    let server = {
+        let rest = rest.clone();
+        MockExchange::builder()
+            .rest(move |req| handle(req, rest.clone()))
+            .start()
+            .await
+    };
+    let http_client = server.http_client().await;
+    let ws_client = server.ws_client().await;
+    let tracker = OrderTracker::subscribe(&http_client, &ws_client, subaccount_id())
+        .await
+        .unwrap();
+    Harness {
+        tracker,
+        ws_client,
+        server,
+        rest,
+    }
+}
+
+fn rest_order(id: u128, status: OrderStatus, filled: Decimal, updated_at: i64) -> OrderDto {
+    OrderDto {
+        id: Uuid::from_u128(id),
+        client_order_id: Some(format!("cloid{id}")),
+        subaccount_id: subaccount_id(),
+        status,
+        quantity: dec!(2),
+        filled,
+        available_quantity: dec!(2) - filled,
+        updated_at,
+        ..Default::default()
+    }
+}
+
+fn push_update(server: &MockExchange, update: OrderUpdateMessageDataDInner) {
+    let message = OrderUpdateMessage {
+        t: update.t,
+        data: OrderUpdateMessageData {
+            t: update.t,
+            d: vec![update],
+        },
+        ..Default::default()
+    };
+    server.push(serde_json::to_string(&message).unwrap());
+}
+
+fn push_fill(server: &MockExchange, fill: OrderFillMessageDataDInner) {
+    let message = OrderFillMessage {
+        data: OrderFillMessageData {
+            t: fill.t,
+            d: vec![fill],
+        },
+        ..Default::default()
+    };
+    server.push(serde_json::to_string(&message).unwrap());
+}
+
+fn created(id: u128) -> SubmitOrderCreatedDto {
+    SubmitOrderCreatedDto {
+        id: Uuid::from_u128(id),
+        client_order_id: Some(format!("cloid{id}")),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_wait_filled_sees_fills_received_before_tracking() {
+    let Harness {
+        tracker,
+        ws_client,
+        server,
+        ..
+    } = tracker().await;
+    push_update(&server, update(10, OrderStatus::New, dec!(0), 100));
+    push_fill(&server, fill(1, 10, dec!(2)));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let order = tracker.track(&created(10));
+    let filled = order.wait_filled(Duration::from_secs(5)).await.unwrap();
+    assert_eq!(filled.status(), OrderStatus::Filled);
+    assert_eq!(filled.filled(), dec!(2));
+    assert_eq!(tracker.get(&Uuid::from_u128(10)).unwrap().id(), order.id());
+    ws_client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_wait_filled_fails_on_other_terminal_states_and_timeouts() {
+    let Harness {
+        tracker,
+        ws_client,
+        server,
+        ..
+    } = tracker().await;
+    let cancelled = tracker.track(&created(11));
+    let resting = tracker.track(&created(12));
+    assert_eq!(cancelled.status(), OrderStatus::Pending);
+
+    push_update(&server, update(11, OrderStatus::New, dec!(0), 100));
+    push_update(&server, update(12, OrderStatus::New, dec!(0), 100));
+    push_update(&server, update(11, OrderStatus::Canceled, dec!(0), 110));
+    assert_eq!(
+        cancelled.wait_filled(Duration::from_secs(5)).await,
+        Err(WaitError::Ended(OrderStatus::Canceled))
+    );
+    assert_eq!(
+        resting.wait_terminal(Duration::from_millis(200)).await,
+        Err(WaitError::Timeout(OrderStatus::New))
+    );
+
+    drop(tracker);
+    assert_eq!(
+        resting.wait_terminal(Duration::from_secs(5)).await,
+        Err(WaitError::TrackerClosed)
+    );
+    ws_client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unfinished_orders_are_looked_up_after_a_reconnect() {
+    let harness = tracker().await;
+    let order = harness.tracker.track(&created(20));
+    push_update(&harness.server, update(20, OrderStatus::New, dec!(0), 100));
+
+    // Order 20 filled while the connection was down.
+    harness.rest.lock().unwrap().insert(
+        Uuid::from_u128(20),
+        rest_order(20, OrderStatus::Filled, dec!(2), 120),
+    );
+    harness.server.close_connections();
+    let filled = order.wait_filled(Duration::from_secs(5)).await.unwrap();
+    assert_eq!(filled.filled(), dec!(2));
+    assert_eq!(filled.updated_at(), 120);
+    assert!(filled.anomalies().is_empty());
+    harness.ws_client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_order_without_updates_is_looked_up() {
+    let harness = tracker().await;
+    // Finished before tracking, e.g. long enough ago to have been pruned.
+    harness.rest.lock().unwrap().insert(
+        Uuid::from_u128(30),
+        rest_order(30, OrderStatus::Canceled, dec!(0), 100),
+    );
+    let order = harness.tracker.track(&created(30));
+    assert_eq!(order.status(), OrderStatus::Pending);
+    assert_eq!(
+        order.wait_filled(Duration::from_secs(5)).await,
+        Err(WaitError::Ended(OrderStatus::Canceled))
+    );
+    harness.ws_client.shutdown("test").await.unwrap();
+}
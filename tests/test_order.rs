@@ -4,6 +4,7 @@ use ethereal_rust_sdk::apis::order_api::{
     OrderControllerListBySubaccountIdParams, OrderControllerListFillsBySubaccountIdParams,
     OrderControllerListTradesParams, OrderControllerSubmitParams,
 };
+use ethereal_rust_sdk::pagination::PaginationOptions;
 
 #[tokio::test]
 #[ignore]
@@ -67,6 +68,24 @@ async fn test_list_trades() {
     assert!(result.is_ok());
 }
 
+#[tokio::test]
+async fn test_list_trades_all() {
+    let client = common::create_test_client().await.unwrap();
+    let product = common::get_product(&client).await.unwrap();
+    let params = OrderControllerListTradesParams {
+        product_id: product.id.clone().to_string(),
+        limit: Some(10),
+        ..Default::default()
+    };
+    let options = PaginationOptions::default().max_items(25);
+    let trades = client
+        .order()
+        .list_trades_all(params, options)
+        .await
+        .unwrap();
+    assert!(trades.len() <= 25);
+}
+
 #[tokio::test]
 #[ignore]
 async fn test_submit() {
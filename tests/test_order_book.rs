@@ -0,0 +1,211 @@
+use ethereal_rust_sdk::models::{L2BookMessageData, MarketLiquidityDto, OrderSide};
+use ethereal_rust_sdk::order_book::{BookGap, OrderBook, SyncState};
+use ethereal_rust_sdk::types::PriceLevel;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+fn get_book_message(
+    t: i64,
+    pt: Option<i64>,
+    asks: &[(Decimal, Decimal)],
+    bids: &[(Decimal, Decimal)],
+) -> L2BookMessageData {
+    let levels = |levels: &[(Decimal, Decimal)]| {
+        levels
+            .iter()
+            .map(|(p, q)| PriceLevel::new(*p, *q))
+            .collect()
+    };
+    L2BookMessageData {
+        s: "BTCUSD".to_string(),
+        t,
+        pt,
+        a: levels(asks),
+        b: levels(bids),
+    }
+}
+
+fn get_synced_book() -> OrderBook {
+    let mut book = OrderBook::new("BTCUSD");
+    book.apply(&get_book_message(
+        100,
+        Some(90),
+        &[(dec!(101), dec!(1)), (dec!(102), dec!(2))],
+        &[(dec!(99), dec!(1)), (dec!(98), dec!(3))],
+    ))
+    .unwrap();
+    book
+}
+
+#[test]
+fn test_snapshot_then_diffs() {
+    let mut book = get_synced_book();
+    assert_eq!(book.state(), SyncState::Synced(100));
+    assert_eq!(book.best_bid(), Some(PriceLevel::new(dec!(99), dec!(1))));
+    assert_eq!(book.best_ask(), Some(PriceLevel::new(dec!(101), dec!(1))));
+    assert_eq!(book.mid_price(), Some(dec!(100)));
+
+    // Absolute quantities; zero removes the level.
+    book.apply(&get_book_message(
+        110,
+        Some(100),
+        &[(dec!(101), dec!(0)), (dec!(102), dec!(5))],
+        &[(dec!(99.5), dec!(2))],
+    ))
+    .unwrap();
+    assert_eq!(book.best_ask(), Some(PriceLevel::new(dec!(102), dec!(5))));
+    assert_eq!(book.best_bid(), Some(PriceLevel::new(dec!(99.5), dec!(2))));
+    let depth = book.depth(2);
+    assert_eq!(
+        depth.bids,
+        vec![
+            PriceLevel::new(dec!(99.5), dec!(2)),
+            PriceLevel::new(dec!(99), dec!(1))
+        ]
+    );
+    assert_eq!(depth.asks, vec![PriceLevel::new(dec!(102), dec!(5))]);
+}
+
+#[test]
+fn test_gap_marks_book_stale_until_snapshot() {
+    let mut book = get_synced_book();
+    let gap = book.apply(&get_book_message(
+        120,
+        Some(110),
+        &[],
+        &[(dec!(97), dec!(1))],
+    ));
+    assert_eq!(
+        gap,
+        Err(BookGap {
+            expected: 100,
+            previous: 110
+        })
+    );
+    assert_eq!(book.state(), SyncState::Stale);
+    assert!(book.best_bid().is_none());
+
+    // Diffs are kept aside while stale.
+    book.apply(&get_book_message(
+        130,
+        Some(120),
+        &[],
+        &[(dec!(97), dec!(1))],
+    ))
+    .unwrap();
+    assert!(book.best_bid().is_none());
+
+    // The REST snapshot falls between diffs; the kept diff after it is replayed.
+    book.seed(&MarketLiquidityDto {
+        asks: vec![PriceLevel::new(dec!(101), dec!(1))],
+        bids: vec![PriceLevel::new(dec!(99), dec!(4))],
+        timestamp: 125,
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(book.state(), SyncState::Synced(130));
+    assert_eq!(book.best_bid(), Some(PriceLevel::new(dec!(99), dec!(4))));
+    assert_eq!(
+        book.depth(2).bids,
+        vec![
+            PriceLevel::new(dec!(99), dec!(4)),
+            PriceLevel::new(dec!(97), dec!(1))
+        ]
+    );
+
+    book.apply(&get_book_message(
+        140,
+        Some(130),
+        &[],
+        &[(dec!(99), dec!(0))],
+    ))
+    .unwrap();
+    assert_eq!(book.best_bid(), Some(PriceLevel::new(dec!(97), dec!(1))));
+}
+
+#[test]
+fn test_seed_bridges_to_next_diff() {
+    let mut book = OrderBook::new("BTCUSD");
+    book.seed(&MarketLiquidityDto {
+        asks: vec![PriceLevel::new(dec!(101), dec!(1))],
+        bids: vec![PriceLevel::new(dec!(99), dec!(4))],
+        timestamp: 1_000_250,
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(book.state(), SyncState::Bridging(1_000_250));
+    assert!(book.is_synced());
+
+    // Sent before the snapshot was taken, so already part of it.
+    book.apply(&get_book_message(
+        1_000_200,
+        Some(1_000_100),
+        &[(dec!(101), dec!(9))],
+        &[],
+    ))
+    .unwrap();
+    assert_eq!(book.best_ask(), Some(PriceLevel::new(dec!(101), dec!(1))));
+
+    // Its `pt` is the previous diff's timestamp, never the snapshot's.
+    book.apply(&get_book_message(
+        1_000_300,
+        Some(1_000_200),
+        &[(dec!(101), dec!(0)), (dec!(102), dec!(2))],
+        &[],
+    ))
+    .unwrap();
+    assert_eq!(book.state(), SyncState::Synced(1_000_300));
+    assert_eq!(book.best_ask(), Some(PriceLevel::new(dec!(102), dec!(2))));
+
+    let gap = book.apply(&get_book_message(1_000_500, Some(1_000_400), &[], &[]));
+    assert_eq!(
+        gap,
+        Err(BookGap {
+            expected: 1_000_300,
+            previous: 1_000_400
+        })
+    );
+}
+
+#[test]
+fn test_vwap() {
+    let book = get_synced_book();
+    assert_eq!(book.vwap(OrderSide::BUY, dec!(1)), Some(dec!(101)));
+    // 1 @ 101 + 2 @ 102
+    assert_eq!(
+        book.vwap(OrderSide::BUY, dec!(3)),
+        Some(dec!(305) / dec!(3))
+    );
+    // 1 @ 99 + 1 @ 98
+    assert_eq!(book.vwap(OrderSide::SELL, dec!(2)), Some(dec!(98.5)));
+    assert_eq!(book.vwap(OrderSide::SELL, dec!(5)), None);
+}
+
+#[test]
+fn test_stale_book_drops_in_flight_diffs_until_snapshot() {
+    let mut book = get_synced_book();
+    book.mark_stale();
+    assert_eq!(book.state(), SyncState::Stale);
+    assert!(book.best_ask().is_none());
+
+    // A diff sent before the resubscribe is not taken as the full book.
+    book.apply(&get_book_message(
+        110,
+        Some(100),
+        &[(dec!(103), dec!(1))],
+        &[],
+    ))
+    .unwrap();
+    assert_eq!(book.state(), SyncState::Stale);
+    assert!(book.best_ask().is_none());
+
+    book.apply(&get_book_message(
+        120,
+        None,
+        &[(dec!(104), dec!(2))],
+        &[(dec!(96), dec!(1))],
+    ))
+    .unwrap();
+    assert_eq!(book.state(), SyncState::Synced(120));
+    assert_eq!(book.best_ask(), Some(PriceLevel::new(dec!(104), dec!(2))));
+}
@@ -0,0 +1,176 @@
+use ethereal_rust_sdk::enums::Environment;
+use ethereal_rust_sdk::models::{
+    EngineType, OrderSide, OrderTimeInForce, OrderType, ProductDto, StopType, SubaccountDto,
+    SubmitOrderData,
+};
+use ethereal_rust_sdk::order_builder::{OrderBuilder, OrderBuilderError};
+use ethereal_rust_sdk::signable_messages::TradeOrder;
+use ethereal_rust_sdk::signing::{hex_to_bytes32, to_scaled_e9, Eip712, SigningContext};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::Signature;
+use rust_decimal_macros::dec;
+use std::str::FromStr;
+
+fn get_test_wallet() -> LocalWallet {
+    "0bb5d63b84421e1268dda020818ae30cf26e7f10e321fb820a8aa69216dea92a"
+        .parse()
+        .unwrap()
+}
+
+fn get_test_subaccount() -> SubaccountDto {
+    SubaccountDto {
+        name: "0x7072696d61727900000000000000000000000000000000000000000000000000".to_string(),
+        ..Default::default()
+    }
+}
+
+fn get_test_product() -> ProductDto {
+    ProductDto {
+        ticker: "BTCUSD".to_string(),
+        display_ticker: "BTC-USD".to_string(),
+        engine_type: EngineType::PERP,
+        onchain_id: 2,
+        tick_size: dec!(1),
+        lot_size: dec!(0.0001),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_limit_order_carries_all_fields() {
+    let wallet = get_test_wallet();
+    let subaccount = get_test_subaccount();
+    let ctx = SigningContext::new(&wallet, &subaccount);
+
+    let dto = OrderBuilder::limit("BTC-USD", OrderSide::SELL, dec!(0.00123), dec!(80000.4))
+        .time_in_force(OrderTimeInForce::Ioc)
+        .post_only(true)
+        .reduce_only(true)
+        .client_order_id("abc123")
+        .expires_at(1_900_000_000)
+        .build(&get_test_product(), &ctx, Environment::Testnet)
+        .unwrap();
+
+    match dto.data {
+        SubmitOrderData::SubmitOrderLimitDtoData(data) => {
+            assert_eq!(data.quantity, dec!(0.0012));
+            assert_eq!(data.price, dec!(80000));
+            assert_eq!(data.side, OrderSide::SELL);
+            assert_eq!(data.time_in_force, OrderTimeInForce::Ioc);
+            assert!(data.post_only);
+            assert_eq!(data.reduce_only, Some(true));
+            assert_eq!(data.client_order_id.as_deref(), Some("abc123"));
+            assert_eq!(data.expires_at, Some(1_900_000_000));
+            assert_eq!(data.r#type, OrderType::Limit);
+            assert_eq!(data.nonce, ctx.nonce.to_string());
+        }
+        _ => panic!("Expected a limit order"),
+    }
+}
+
+#[test]
+fn test_market_order_signs_zero_price() {
+    let wallet = get_test_wallet();
+    let subaccount = get_test_subaccount();
+    let ctx = SigningContext::new(&wallet, &subaccount);
+    let env = Environment::Testnet;
+
+    let dto = OrderBuilder::market("BTC-USD", OrderSide::BUY, dec!(0.01))
+        .build(&get_test_product(), &ctx, env)
+        .unwrap();
+    assert!(matches!(
+        dto.data,
+        SubmitOrderData::SubmitOrderMarketDtoData(_)
+    ));
+
+    let expected = TradeOrder {
+        sender: wallet.address(),
+        subaccount: hex_to_bytes32(&subaccount.name).unwrap(),
+        quantity: to_scaled_e9(dec!(0.01)).unwrap(),
+        price: 0,
+        reduce_only: false,
+        side: 0,
+        engine_type: 0,
+        product_id: 2,
+        nonce: ctx.nonce,
+        signed_at: ctx.signed_at,
+    };
+    let signature = Signature::from_str(&dto.signature).unwrap();
+    let recovered = signature
+        .recover(expected.encode_eip712(env).unwrap())
+        .unwrap();
+    assert_eq!(recovered, wallet.address());
+}
+
+#[test]
+fn test_close_position_is_valid() {
+    let wallet = get_test_wallet();
+    let subaccount = get_test_subaccount();
+    let ctx = SigningContext::new(&wallet, &subaccount);
+
+    let dto = OrderBuilder::close_position("BTC-USD", OrderSide::SELL)
+        .build(&get_test_product(), &ctx, Environment::Testnet)
+        .unwrap();
+    match dto.data {
+        SubmitOrderData::SubmitOrderMarketDtoData(data) => {
+            assert_eq!(data.close, Some(true));
+            assert_eq!(data.reduce_only, Some(true));
+            assert!(data.quantity.is_zero());
+        }
+        _ => panic!("Expected a market order"),
+    }
+}
+
+#[test]
+fn test_invalid_orders_are_rejected() {
+    let wallet = get_test_wallet();
+    let subaccount = get_test_subaccount();
+    let ctx = SigningContext::new(&wallet, &subaccount);
+    let product = get_test_product();
+    let env = Environment::Testnet;
+
+    let result = OrderBuilder::market("BTC-USD", OrderSide::BUY, dec!(0.01))
+        .post_only(true)
+        .build(&product, &ctx, env);
+    assert!(matches!(
+        result,
+        Err(OrderBuilderError::UnsupportedForMarket(_))
+    ));
+
+    let result = OrderBuilder::market("BTC-USD", OrderSide::SELL, dec!(0.01))
+        .close(true)
+        .reduce_only(true)
+        .build(&product, &ctx, env);
+    assert!(matches!(result, Err(OrderBuilderError::InvalidClose)));
+
+    let result = OrderBuilder::limit("BTC-USD", OrderSide::BUY, dec!(0.01), dec!(0))
+        .build(&product, &ctx, env);
+    assert!(matches!(result, Err(OrderBuilderError::InvalidPrice(_))));
+
+    let result = OrderBuilder::limit("BTC-USD", OrderSide::BUY, dec!(0.00001), dec!(100))
+        .build(&product, &ctx, env);
+    assert!(matches!(result, Err(OrderBuilderError::InvalidQuantity(_))));
+
+    let result = OrderBuilder::limit("BTC-USD", OrderSide::BUY, dec!(0.01), dec!(100))
+        .stop(dec!(0), StopType::LOSS)
+        .build(&product, &ctx, env);
+    assert!(matches!(
+        result,
+        Err(OrderBuilderError::InvalidStopPrice(_))
+    ));
+
+    let result = OrderBuilder::limit("BTC-USD", OrderSide::BUY, dec!(0.01), dec!(100))
+        .client_order_id("not-a-valid-client-order-id!")
+        .build(&product, &ctx, env);
+    assert!(matches!(
+        result,
+        Err(OrderBuilderError::InvalidClientOrderId(_))
+    ));
+
+    let result = OrderBuilder::limit("ETH-USD", OrderSide::BUY, dec!(0.01), dec!(100))
+        .build(&product, &ctx, env);
+    assert!(matches!(
+        result,
+        Err(OrderBuilderError::ProductMismatch { .. })
+    ));
+}
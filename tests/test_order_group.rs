@@ -0,0 +1,171 @@
+use ethereal_rust_sdk::enums::Environment;
+use ethereal_rust_sdk::models::{
+    EngineType, GroupContingencyType, OrderSide, ProductDto, StopType, SubaccountDto,
+    SubmitOrderData,
+};
+use ethereal_rust_sdk::order_builder::{OrderBuilder, OrderBuilderError};
+use ethereal_rust_sdk::order_group::{BracketLeg, BracketOrder};
+use ethereal_rust_sdk::signing::SigningContext;
+use ethers::signers::LocalWallet;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn get_test_product() -> ProductDto {
+    ProductDto {
+        ticker: "BTCUSD".to_string(),
+        display_ticker: "BTC-USD".to_string(),
+        engine_type: EngineType::PERP,
+        onchain_id: 2,
+        tick_size: dec!(1),
+        lot_size: dec!(0.0001),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_bracket_legs_share_group() {
+    let group_id = Uuid::from_u128(42);
+    let orders = BracketOrder::new(OrderBuilder::limit(
+        "BTC-USD",
+        OrderSide::BUY,
+        dec!(0.01),
+        dec!(80000),
+    ))
+    .group_id(group_id)
+    .take_profit(dec!(90000))
+    .stop_loss_limit(dec!(75000), dec!(74900))
+    .into_orders()
+    .unwrap();
+
+    let legs: Vec<BracketLeg> = orders.iter().map(|(leg, _)| *leg).collect();
+    assert_eq!(
+        legs,
+        vec![
+            BracketLeg::Entry,
+            BracketLeg::TakeProfit,
+            BracketLeg::StopLoss
+        ]
+    );
+
+    let wallet: LocalWallet = "0bb5d63b84421e1268dda020818ae30cf26e7f10e321fb820a8aa69216dea92a"
+        .parse()
+        .unwrap();
+    let subaccount = SubaccountDto {
+        name: "0x7072696d61727900000000000000000000000000000000000000000000000000".to_string(),
+        ..Default::default()
+    };
+    let ctx = SigningContext::new(&wallet, &subaccount);
+    let product = get_test_product();
+
+    let mut dtos = orders
+        .into_iter()
+        .map(|(_, order)| order.build(&product, &ctx, Environment::Testnet).unwrap());
+
+    match dtos.next().unwrap().data {
+        SubmitOrderData::SubmitOrderLimitDtoData(entry) => {
+            assert_eq!(entry.group_id, Some(group_id));
+            assert_eq!(
+                entry.group_contingency_type,
+                Some(GroupContingencyType::OTO)
+            );
+            assert_eq!(entry.stop_type, None);
+        }
+        _ => panic!("Expected a limit entry"),
+    }
+    match dtos.next().unwrap().data {
+        SubmitOrderData::SubmitOrderMarketDtoData(take_profit) => {
+            assert_eq!(take_profit.group_id, Some(group_id));
+            assert_eq!(
+                take_profit.group_contingency_type,
+                Some(GroupContingencyType::OCO)
+            );
+            assert_eq!(take_profit.side, OrderSide::SELL);
+            assert_eq!(take_profit.stop_type, Some(StopType::GAIN));
+            assert_eq!(take_profit.reduce_only, Some(true));
+        }
+        _ => panic!("Expected a market take profit"),
+    }
+    match dtos.next().unwrap().data {
+        SubmitOrderData::SubmitOrderLimitDtoData(stop_loss) => {
+            assert_eq!(stop_loss.group_id, Some(group_id));
+            assert_eq!(stop_loss.price, dec!(74900));
+            assert_eq!(stop_loss.stop_price, Some(dec!(75000)));
+            assert_eq!(stop_loss.stop_type, Some(StopType::LOSS));
+        }
+        _ => panic!("Expected a limit stop loss"),
+    }
+}
+
+#[test]
+fn test_bracket_requires_exit_leg() {
+    let result = BracketOrder::new(OrderBuilder::market("BTC-USD", OrderSide::BUY, dec!(0.01)))
+        .into_orders();
+    assert!(matches!(result, Err(OrderBuilderError::IncompleteBracket)));
+}
+
+#[test]
+fn test_bracket_exits_must_be_placed_around_the_entry() {
+    let buy = || OrderBuilder::limit("BTC-USD", OrderSide::BUY, dec!(0.01), dec!(80000));
+    let result = BracketOrder::new(buy())
+        .take_profit(dec!(79000))
+        .into_orders();
+    assert!(matches!(
+        result,
+        Err(OrderBuilderError::MisplacedBracketLeg {
+            leg: BracketLeg::TakeProfit,
+            ..
+        })
+    ));
+    let result = BracketOrder::new(buy())
+        .stop_loss(dec!(81000))
+        .into_orders();
+    assert!(matches!(
+        result,
+        Err(OrderBuilderError::MisplacedBracketLeg {
+            leg: BracketLeg::StopLoss,
+            ..
+        })
+    ));
+
+    // A short takes profit below the entry.
+    let sell = OrderBuilder::limit("BTC-USD", OrderSide::SELL, dec!(0.01), dec!(80000));
+    let orders = BracketOrder::new(sell)
+        .take_profit(dec!(70000))
+        .stop_loss(dec!(85000))
+        .into_orders()
+        .unwrap();
+    assert!(orders
+        .iter()
+        .skip(1)
+        .all(|(_, order)| order.side() == OrderSide::BUY));
+
+    // Without an entry price only the exits are compared.
+    let market = || OrderBuilder::market("BTC-USD", OrderSide::BUY, dec!(0.01));
+    let result = BracketOrder::new(market())
+        .take_profit(dec!(75000))
+        .stop_loss(dec!(85000))
+        .into_orders();
+    assert!(matches!(
+        result,
+        Err(OrderBuilderError::MisplacedBracketLeg { .. })
+    ));
+    assert!(BracketOrder::new(market())
+        .take_profit(dec!(85000))
+        .stop_loss(dec!(75000))
+        .into_orders()
+        .is_ok());
+}
+
+#[test]
+fn test_bracket_exits_must_fit_the_entry_quantity() {
+    let result = BracketOrder::new(OrderBuilder::close_position("BTC-USD", OrderSide::BUY))
+        .stop_loss(dec!(75000))
+        .into_orders();
+    assert!(matches!(
+        result,
+        Err(OrderBuilderError::BracketLegQuantity {
+            leg: BracketLeg::StopLoss,
+            ..
+        })
+    ));
+}
@@ -0,0 +1,118 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ethereal_rust_sdk::pagination::{paginate, CursorParams, Page, PaginationOptions};
+use ethereal_rust_sdk::Error;
+use futures_util::TryStreamExt;
+
+#[derive(Clone, Default)]
+struct TestParams {
+    cursor: Option<String>,
+}
+
+impl CursorParams for TestParams {
+    fn set_cursor(&mut self, cursor: Option<String>) {
+        self.cursor = cursor;
+    }
+}
+
+struct TestPage {
+    data: Vec<u32>,
+    has_next: bool,
+    next_cursor: Option<String>,
+}
+
+impl Page for TestPage {
+    type Item = u32;
+
+    fn into_parts(self) -> (Vec<u32>, bool, Option<String>) {
+        (self.data, self.has_next, self.next_cursor)
+    }
+}
+
+/// Serves three pages of three items.
+fn get_test_page(cursor: Option<String>) -> TestPage {
+    let page: u32 = cursor.map(|c| c.parse().unwrap()).unwrap_or(0);
+    TestPage {
+        data: (page * 3..page * 3 + 3).collect(),
+        has_next: page < 2,
+        next_cursor: Some((page + 1).to_string()),
+    }
+}
+
+#[tokio::test]
+async fn test_follows_cursor_until_last_page() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let fetch = |params: TestParams| {
+        let requests = requests.clone();
+        async move {
+            requests.lock().unwrap().push(params.cursor.clone());
+            Ok::<_, Error>(get_test_page(params.cursor))
+        }
+    };
+    let items: Vec<u32> = paginate(TestParams::default(), PaginationOptions::default(), fetch)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(items, (0..9).collect::<Vec<_>>());
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![None, Some("1".to_string()), Some("2".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn test_max_items_stops_early() {
+    let requests = Arc::new(Mutex::new(0));
+    let fetch = |params: TestParams| {
+        let requests = requests.clone();
+        async move {
+            *requests.lock().unwrap() += 1;
+            Ok::<_, Error>(get_test_page(params.cursor))
+        }
+    };
+    let options = PaginationOptions::default().max_items(4);
+    let items: Vec<u32> = paginate(TestParams::default(), options, fetch)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(items, vec![0, 1, 2, 3]);
+    assert_eq!(*requests.lock().unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_rate_limited_pages_are_retried() {
+    let attempts = Arc::new(Mutex::new(0));
+    let fetch = |params: TestParams| {
+        let attempts = attempts.clone();
+        async move {
+            let mut attempts = attempts.lock().unwrap();
+            *attempts += 1;
+            if *attempts == 2 {
+                return Err(Error::RateLimited {
+                    kind: None,
+                    message: "Too Many Requests".to_string(),
+                });
+            }
+            Ok(get_test_page(params.cursor))
+        }
+    };
+    let options = PaginationOptions::default().rate_limit_backoff(Duration::from_millis(1), 1);
+    let items: Vec<u32> = paginate(TestParams::default(), options, fetch)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 9);
+    assert_eq!(*attempts.lock().unwrap(), 4);
+}
+
+#[tokio::test]
+async fn test_errors_end_the_stream() {
+    let fetch =
+        |_: TestParams| async { Err::<TestPage, _>(Error::Validation("bad cursor".to_string())) };
+    let result: Result<Vec<u32>, Error> =
+        paginate(TestParams::default(), PaginationOptions::default(), fetch)
+            .try_collect()
+            .await;
+    assert!(matches!(result, Err(Error::Validation(_))));
+}
@@ -0,0 +1,52 @@
+use ethereal_rust_sdk::models::{L2BookMessageData, MarketLiquidityDto};
+use ethereal_rust_sdk::types::PriceLevel;
+use rust_decimal_macros::dec;
+
+#[test]
+fn test_deserialize_string_and_number_tuples() {
+    let levels: Vec<PriceLevel> = serde_json::from_str(r#"[["101.5", "2"], [100, 0.25]]"#).unwrap();
+    assert_eq!(
+        levels,
+        vec![
+            PriceLevel::new(dec!(101.5), dec!(2)),
+            PriceLevel::new(dec!(100), dec!(0.25))
+        ]
+    );
+}
+
+#[test]
+fn test_reject_malformed_levels() {
+    assert!(serde_json::from_str::<PriceLevel>(r#"["101.5"]"#).is_err());
+    assert!(serde_json::from_str::<PriceLevel>(r#"["101.5", "2", "3"]"#).is_err());
+    assert!(serde_json::from_str::<PriceLevel>(r#"["abc", "2"]"#).is_err());
+    assert!(serde_json::from_str::<PriceLevel>(r#"{"price": "1", "quantity": "2"}"#).is_err());
+}
+
+#[test]
+fn test_serialize_as_tuple() {
+    let level = PriceLevel::new(dec!(101.5), dec!(2));
+    let json = serde_json::to_string(&level).unwrap();
+    assert_eq!(json, r#"["101.5","2"]"#);
+    assert_eq!(serde_json::from_str::<PriceLevel>(&json).unwrap(), level);
+}
+
+#[test]
+fn test_book_models_use_price_levels() {
+    let data: L2BookMessageData = serde_json::from_str(
+        r#"{"s": "BTCUSD", "t": 2, "pt": 1, "a": [["101", "1"]], "b": [["99", "3"], ["98", "0"]]}"#,
+    )
+    .unwrap();
+    assert_eq!(data.a, vec![PriceLevel::new(dec!(101), dec!(1))]);
+    assert_eq!(data.b[1], PriceLevel::new(dec!(98), dec!(0)));
+
+    let liquidity: MarketLiquidityDto = serde_json::from_str(
+        r#"{
+            "asks": [["101", "1"]],
+            "bids": [["99", "3"]],
+            "productId": "7b7c7f0e-2c1c-4d4e-8f0a-1f2e3d4c5b6a",
+            "timestamp": 2
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(liquidity.bids, vec![PriceLevel::new(dec!(99), dec!(3))]);
+}
@@ -0,0 +1,373 @@
+mod common;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{btc, json, not_found, subaccount_id, MockExchange, Pusher, RestRequest};
+use ethereal_rust_sdk::{
+    async_client::{client::HttpClient, session::SubaccountSelector},
+    managed_order::OrderTracker,
+    models::{
+        CancelOrderResultCode, CancelOrderResultDto, OrderDto, OrderDtoOrderType, OrderSide,
+        OrderStatus, OrderUpdateMessage, OrderUpdateMessageData, OrderUpdateMessageDataDInner,
+        SubmitCreatedCode, SubmitOrderCreatedDto,
+    },
+    order_builder::OrderBuilder,
+    replace_order::{ReplaceError, ReplaceMode, ReplaceOptions},
+    ws_client::WsClient,
+};
+use http_body_util::Full;
+use hyper::{body::Bytes, Method, Response};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn original_id() -> Uuid {
+    Uuid::from_u128(10)
+}
+
+fn replacement_id() -> Uuid {
+    Uuid::from_u128(20)
+}
+
+/// A resting buy for 2 of which 0.5 is filled.
+fn original() -> OrderDto {
+    OrderDto {
+        id: original_id(),
+        client_order_id: Some("quote1".to_string()),
+        subaccount_id: subaccount_id(),
+        product_id: btc(),
+        side: OrderSide::BUY,
+        r#type: OrderDtoOrderType::Limit,
+        status: OrderStatus::FilledPartial,
+        quantity: dec!(2),
+        available_quantity: dec!(1.5),
+        filled: dec!(0.5),
+        price: dec!(80000),
+        post_only: Some(true),
+        ..Default::default()
+    }
+}
+
+/// The order being replaced, and what the server does when it is cancelled.
+#[derive(Clone)]
+struct Exchange {
+    original: Arc<Mutex<OrderDto>>,
+    /// Filled on the original before the cancel reaches it.
+    fill_before_cancel: Decimal,
+    cancel_code: CancelOrderResultCode,
+    /// Whether closing the original is published on the order update channel.
+    publish_updates: bool,
+    events: Arc<Mutex<Vec<&'static str>>>,
+    submissions: Arc<Mutex<Vec<serde_json::Value>>>,
+    pusher: Pusher,
+}
+
+impl Exchange {
+    fn new() -> Self {
+        Self {
+            original: Arc::new(Mutex::new(original())),
+            fill_before_cancel: dec!(0),
+            cancel_code: CancelOrderResultCode::Ok,
+            publish_updates: true,
+            events: Default::default(),
+            submissions: Default::default(),
+            pusher: Pusher::default(),
+        }
+    }
+
+    fn events(&self) -> Vec<&'static str> {
+        self.events.lock().unwrap().clone()
+    }
+
+    fn submitted(&self) -> serde_json::Value {
+        self.submissions.lock().unwrap()[0]["data"].clone()
+    }
+
+    fn cancel(&self) -> CancelOrderResultDto {
+        self.events.lock().unwrap().push("cancel");
+        let mut order = self.original.lock().unwrap();
+        order.filled += self.fill_before_cancel;
+        order.status = match self.cancel_code {
+            CancelOrderResultCode::Ok | CancelOrderResultCode::AlreadyCanceled => {
+                OrderStatus::Canceled
+            }
+            CancelOrderResultCode::AlreadyFilled => OrderStatus::Filled,
+            _ => order.status,
+        };
+        if self.publish_updates && order.status != OrderStatus::FilledPartial {
+            let update = OrderUpdateMessageDataDInner {
+                id: order.id,
+                cloid: order.client_order_id.clone(),
+                qty: order.quantity,
+                aqty: order.quantity - order.filled,
+                fill: order.filled,
+                s: "BTCUSD".to_string(),
+                sid: subaccount_id(),
+                st: order.status,
+                t: 100,
+                ..Default::default()
+            };
+            let message = OrderUpdateMessage {
+                t: 100,
+                data: OrderUpdateMessageData {
+                    t: 100,
+                    d: vec![update],
+                },
+                ..Default::default()
+            };
+            self.pusher.push(serde_json::to_string(&message).unwrap());
+        }
+        CancelOrderResultDto {
+            id: order.id,
+            result: self.cancel_code,
+            ..Default::default()
+        }
+    }
+}
+
+async fn handle(req: RestRequest, exchange: Exchange) -> Response<Full<Bytes>> {
+    if req.method == Method::GET {
+        // The tracker may also look up the replacement, which is not listed.
+        if req.path != format!("/v1/order/{}", original_id()) {
+            return not_found();
+        }
+        let order = exchange.original.lock().unwrap().clone();
+        return json(200, serde_json::to_value(order).unwrap());
+    }
+    match req.path.as_str() {
+        "/v1/order/cancel" => {
+            let result = exchange.cancel();
+            json(200, serde_json::json!({ "data": [result] }))
+        }
+        "/v1/order" => {
+            exchange.events.lock().unwrap().push("place");
+            let client_order_id = req.body["data"]["clientOrderId"]
+                .as_str()
+                .map(str::to_string);
+            exchange.submissions.lock().unwrap().push(req.body);
+            let created = SubmitOrderCreatedDto {
+                id: replacement_id(),
+                client_order_id,
+                filled: dec!(0),
+                result: SubmitCreatedCode::Ok,
+            };
+            json(200, serde_json::to_value(created).unwrap())
+        }
+        path => panic!("Unexpected path {}", path),
+    }
+}
+
+async fn connect(exchange: &Exchange) -> (HttpClient, WsClient, OrderTracker) {
+    let server = {
+        let exchange = exchange.clone();
+        MockExchange::builder()
+            .pusher(exchange.pusher.clone())
+            .rest(move |req| handle(req, exchange.clone()))
+            .start()
+            .await
+    };
+    let http_client = server.http_client().await;
+    let ws_client = server.ws_client().await;
+    let tracker = OrderTracker::subscribe(&http_client, &ws_client, subaccount_id())
+        .await
+        .unwrap();
+    (http_client, ws_client, tracker)
+}
+
+fn requote() -> OrderBuilder {
+    OrderBuilder::limit("BTC-USD", OrderSide::BUY, dec!(1), dec!(81000))
+}
+
+#[tokio::test]
+async fn test_cancel_then_place_carries_the_client_order_id_over() {
+    let exchange = Exchange {
+        fill_before_cancel: dec!(0.3),
+        ..Exchange::new()
+    };
+    let (http_client, ws_client, tracker) = connect(&exchange).await;
+    let session = http_client.session(SubaccountSelector::First).unwrap();
+
+    let report = session
+        .replace_order(
+            &tracker,
+            original_id(),
+            requote(),
+            ReplaceOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(exchange.events(), vec!["cancel", "place"]);
+    assert!(report.is_replaced());
+    assert_eq!(report.cancel_result, CancelOrderResultCode::Ok);
+    assert_eq!(report.original_status, OrderStatus::Canceled);
+    assert_eq!(report.filled_during_replace, dec!(0.3));
+    assert_eq!(report.replacement.unwrap().id(), replacement_id());
+    assert_eq!(exchange.submitted()["clientOrderId"], "quote1");
+    ws_client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_place_then_cancel_places_first() {
+    let exchange = Exchange::new();
+    let (http_client, ws_client, tracker) = connect(&exchange).await;
+
+    let options = ReplaceOptions::default().mode(ReplaceMode::PlaceThenCancel);
+    let report = http_client
+        .replace_order(
+            SubaccountSelector::First,
+            &tracker,
+            original_id(),
+            requote(),
+            options,
+        )
+        .await
+        .unwrap();
+    assert_eq!(exchange.events(), vec!["place", "cancel"]);
+    assert!(report.is_replaced());
+    assert_eq!(report.filled_during_replace, dec!(0));
+    // Both orders were working at once, so the replacement has an id of its own.
+    assert_ne!(exchange.submitted()["clientOrderId"], "quote1");
+    ws_client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_original_filled_before_the_cancel_is_not_replaced() {
+    let exchange = Exchange {
+        fill_before_cancel: dec!(1.5),
+        cancel_code: CancelOrderResultCode::AlreadyFilled,
+        ..Exchange::new()
+    };
+    let (http_client, ws_client, tracker) = connect(&exchange).await;
+
+    let report = http_client
+        .replace_order(
+            SubaccountSelector::First,
+            &tracker,
+            original_id(),
+            requote(),
+            ReplaceOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(exchange.events(), vec!["cancel"]);
+    assert!(!report.is_replaced());
+    assert!(report.replacement.is_none());
+    assert_eq!(report.original_status, OrderStatus::Filled);
+    assert_eq!(report.filled_during_replace, dec!(1.5));
+    ws_client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_close_is_confirmed_over_rest_without_an_update() {
+    let exchange = Exchange {
+        fill_before_cancel: dec!(0.2),
+        publish_updates: false,
+        ..Exchange::new()
+    };
+    let (http_client, ws_client, tracker) = connect(&exchange).await;
+
+    let options = ReplaceOptions::default().confirm_timeout(Duration::from_millis(100));
+    let report = http_client
+        .replace_order(
+            SubaccountSelector::First,
+            &tracker,
+            original_id(),
+            requote(),
+            options,
+        )
+        .await
+        .unwrap();
+    assert!(report.is_replaced());
+    assert_eq!(report.filled_during_replace, dec!(0.2));
+    ws_client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_amend_keeps_the_order_terms() {
+    let exchange = Exchange::new();
+    let (http_client, ws_client, tracker) = connect(&exchange).await;
+    let session = http_client.session(SubaccountSelector::First).unwrap();
+
+    let report = session
+        .amend_order(
+            &tracker,
+            original_id(),
+            dec!(81000),
+            dec!(1.5),
+            ReplaceOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert!(report.is_replaced());
+    let submitted = exchange.submitted();
+    let decimal = |value: &serde_json::Value| -> Decimal {
+        value.as_str().map_or_else(
+            || value.to_string().parse().unwrap(),
+            |s| s.parse().unwrap(),
+        )
+    };
+    assert_eq!(decimal(&submitted["price"]), dec!(81000));
+    assert_eq!(decimal(&submitted["quantity"]), dec!(1.5));
+    assert_eq!(
+        submitted["side"],
+        serde_json::to_value(OrderSide::BUY).unwrap()
+    );
+    assert_eq!(submitted["postOnly"], true);
+    assert_eq!(submitted["clientOrderId"], "quote1");
+    ws_client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_failures_leave_orders_as_reported() {
+    let exchange = Exchange::new();
+    exchange.original.lock().unwrap().status = OrderStatus::Canceled;
+    let (http_client, ws_client, tracker) = connect(&exchange).await;
+    let result = http_client
+        .replace_order(
+            SubaccountSelector::First,
+            &tracker,
+            original_id(),
+            requote(),
+            ReplaceOptions::default(),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(ReplaceError::NotWorking {
+            status: OrderStatus::Canceled,
+            ..
+        })
+    ));
+    assert!(exchange.events().is_empty());
+    ws_client.shutdown("test").await.unwrap();
+
+    let exchange = Exchange {
+        cancel_code: CancelOrderResultCode::NotFound,
+        ..Exchange::new()
+    };
+    let (http_client, ws_client, tracker) = connect(&exchange).await;
+    let options = ReplaceOptions::default().mode(ReplaceMode::PlaceThenCancel);
+    let error = http_client
+        .replace_order(
+            SubaccountSelector::First,
+            &tracker,
+            original_id(),
+            requote(),
+            options,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ReplaceError::CancelRejected {
+            code: CancelOrderResultCode::NotFound,
+            ..
+        }
+    ));
+    // The replacement is still working and handed back.
+    assert_eq!(error.replacement().unwrap().id(), replacement_id());
+    ws_client.shutdown("test").await.unwrap();
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{within, MockExchange, Reply, WsRequest};
+use ethereal_rust_sdk::{
+    channels::Channels,
+    enums::Environment,
+    subscription_stream::{subscription_queue, StreamConfig},
+    types::ProductSubscriptionMessage,
+    ws_client::{ClientError, ConnectionState, WsClient, WsEvent},
+};
+use futures_util::{FutureExt, StreamExt};
+use hyper::body::Bytes;
+
+/// Acknowledges every request and answers each subscribe with one ticker message tagged with the
+/// connection number. The first connection is closed right after that message.
+async fn spawn_server() -> MockExchange {
+    MockExchange::builder()
+        .ws(|request| {
+            let mut replies = vec![request.ack()];
+            if request.event() == "subscribe" {
+                // Channel messages start with `{"e":`, so they are formatted by hand.
+                replies.push(Reply::Send(format!(
+                    r#"{{"e":"Ticker","t":1,"data":{{"s":{},"connection":{}}}}}"#,
+                    request.request["data"]["symbol"], request.connection
+                )));
+                if request.connection == 1 {
+                    replies.push(Reply::Close);
+                }
+            }
+            replies
+        })
+        .start()
+        .await
+}
+
+async fn next_subscribe(server: &MockExchange) -> WsRequest {
+    loop {
+        let request = server.next_request().await;
+        if request.event() == "subscribe" {
+            return request;
+        }
+    }
+}
+
+async fn subscribes(server: &MockExchange) -> Vec<WsRequest> {
+    let mut requests = server.requests().await;
+    requests.retain(|request| request.event() == "subscribe");
+    requests
+}
+
+#[tokio::test]
+async fn test_gap_markers_are_collapsed_and_never_dropped() {
+    let (queue, messages) =
+        subscription_queue::<serde_json::Value>(StreamConfig::default().capacity(2));
+    let mut messages = Box::pin(messages);
+    queue.send("BTCUSD", Bytes::from(r#"{"n":1}"#)).await;
+    queue.mark_gap();
+    queue.mark_gap();
+    // Full: the oldest message goes, the gap stays.
+    queue.send("BTCUSD", Bytes::from(r#"{"n":2}"#)).await;
+
+    assert!(matches!(messages.next().await, Some(Err(ClientError::Gap))));
+    assert_eq!(messages.next().await.unwrap().unwrap()["n"], 2);
+    assert!(messages.next().now_or_never().is_none());
+}
+
+#[tokio::test]
+async fn test_supervisor_resubscribes_after_reconnect() {
+    let server = spawn_server().await;
+    let client = server.ws_client().await;
+
+    let mut tickers = client
+        .subscribe_stream::<_, serde_json::Value>(
+            Channels::Ticker,
+            vec![ProductSubscriptionMessage {
+                msg_type: Channels::Ticker,
+                symbol: "BTCUSD".to_string(),
+            }],
+            StreamConfig::default(),
+        )
+        .await
+        .unwrap();
+
+    let first = within(tickers.next()).await.unwrap().unwrap();
+    assert_eq!(first["data"]["connection"], 1);
+    assert!(matches!(
+        within(tickers.next()).await,
+        Some(Err(ClientError::Gap))
+    ));
+    let second = within(tickers.next()).await.unwrap().unwrap();
+    assert_eq!(second["data"]["connection"], 2);
+
+    // The same subscribe request is replayed once on the new connection.
+    let original = next_subscribe(&server).await;
+    let replayed = next_subscribe(&server).await;
+    assert_eq!(original.connection, 1);
+    assert_eq!(replayed.connection, 2);
+    assert_eq!(original.request, replayed.request);
+    assert!(subscribes(&server).await.is_empty());
+
+    loop {
+        if let WsEvent::Resubscribed { restored, failed } = within(client.run_till_event()).await {
+            assert_eq!(restored, vec!["Ticker:BTCUSD".to_string()]);
+            assert!(failed.is_empty());
+            break;
+        }
+    }
+    client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_subscribe_queued_during_reconnect_is_sent_once() {
+    let server = spawn_server().await;
+    let config = server
+        .ws_config()
+        .backoff(Duration::from_millis(300), Duration::from_millis(300))
+        .jitter(0.0);
+    let client = WsClient::with_config(Environment::Testnet, config);
+    client.wait_for_connection().await;
+    let ticker = |symbol: &str| {
+        vec![ProductSubscriptionMessage {
+            msg_type: Channels::Ticker,
+            symbol: symbol.to_string(),
+        }]
+    };
+
+    // The first connection closes after this subscribe, leaving the next one queued.
+    let _btc = client
+        .subscribe_stream::<_, serde_json::Value>(
+            Channels::Ticker,
+            ticker("BTCUSD"),
+            StreamConfig::default(),
+        )
+        .await
+        .unwrap();
+    loop {
+        if let WsEvent::Connection(ConnectionState::Reconnecting) =
+            within(client.run_till_event()).await
+        {
+            break;
+        }
+    }
+    let _eth = within(client.subscribe_stream::<_, serde_json::Value>(
+        Channels::Ticker,
+        ticker("ETHUSD"),
+        StreamConfig::default(),
+    ))
+    .await
+    .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let received: Vec<_> = subscribes(&server)
+        .await
+        .into_iter()
+        .map(|request| {
+            (
+                request.connection,
+                request.request["data"]["symbol"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        received,
+        vec![
+            (1, serde_json::json!("BTCUSD")),
+            (2, serde_json::json!("BTCUSD")),
+            (2, serde_json::json!("ETHUSD")),
+        ]
+    );
+    client.shutdown("test").await.unwrap();
+}
@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use ethereal_rust_sdk::{
+    routing::{extract_event, extract_subaccount_id, extract_symbol, route_key},
+    subscription_stream::{subscription_queue, OverflowPolicy, StreamConfig},
+    ws_client::{handle_incoming, ChannelRoute, ClientError, RouteSender, SubscribedTarget},
+};
+use futures_util::{FutureExt, Stream, StreamExt};
+
+fn get_route(target: &str, senders: Vec<RouteSender>) -> ChannelRoute {
+    ChannelRoute {
+        senders,
+        target: SubscribedTarget {
+            target: target.to_string(),
+            subscribe: Bytes::new(),
+            unsubscribe: Bytes::new(),
+        },
+        confirmed: true,
+    }
+}
+
+fn route_sender(
+    subscription_id: u64,
+) -> (
+    RouteSender,
+    impl Stream<Item = Result<serde_json::Value, ClientError>> + Unpin,
+) {
+    let (queue, messages) = subscription_queue(StreamConfig::default());
+    (
+        RouteSender {
+            subscription_id,
+            queue,
+        },
+        Box::pin(messages),
+    )
+}
+
+fn drain(
+    rx: &mut (impl Stream<Item = Result<serde_json::Value, ClientError>> + Unpin),
+) -> Vec<serde_json::Value> {
+    let mut messages = Vec::new();
+    while let Some(Some(msg)) = rx.next().now_or_never() {
+        messages.push(msg.unwrap());
+    }
+    messages
+}
+
+fn json(msg: &Bytes) -> serde_json::Value {
+    serde_json::from_slice(msg).unwrap()
+}
+
+#[test]
+fn test_extract_routing_fields() {
+    let ticker = br#"{"e":"Ticker","t":1,"data":{"s":"BTCUSD","t":1,"bidPx":"1"}}"#;
+    assert_eq!(extract_event(ticker), Some("Ticker"));
+    assert_eq!(extract_symbol(ticker), Some("BTCUSD"));
+    assert_eq!(extract_subaccount_id(ticker), None);
+
+    let fill = br#"{"e":"OrderFill","t":1,"data":{"t":1,"d":[{"s":"ETHUSD","sid":"sub-1"}]}}"#;
+    assert_eq!(extract_subaccount_id(fill), Some("sub-1"));
+    assert_eq!(extract_symbol(fill), Some("ETHUSD"));
+
+    // Keys which merely end in `s` are not mistaken for the symbol.
+    assert_eq!(extract_symbol(br#"{"e":"X","data":{"ts":"1"}}"#), None);
+    assert_eq!(route_key("L2Book", "BTCUSD"), "L2Book:BTCUSD");
+}
+
+#[tokio::test]
+async fn test_routes_by_channel_and_symbol() {
+    let pending = Arc::new(DashMap::new());
+    let subscriptions = Arc::new(DashMap::new());
+    let (btc_tx, mut btc_rx) = route_sender(1);
+    let (eth_tx, mut eth_rx) = route_sender(2);
+    let (shared_tx, mut shared_rx) = route_sender(3);
+    subscriptions.insert(
+        route_key("L2Book", "BTCUSD"),
+        get_route("BTCUSD", vec![btc_tx, shared_tx.clone()]),
+    );
+    subscriptions.insert(
+        route_key("L2Book", "ETHUSD"),
+        get_route("ETHUSD", vec![eth_tx, shared_tx]),
+    );
+
+    let btc = Bytes::from(r#"{"e":"L2Book","data":{"s":"BTCUSD","t":1,"a":[],"b":[]}}"#);
+    let eth = Bytes::from(r#"{"e":"L2Book","data":{"s":"ETHUSD","t":1,"a":[],"b":[]}}"#);
+    let sol = Bytes::from(r#"{"e":"L2Book","data":{"s":"SOLUSD","t":1,"a":[],"b":[]}}"#);
+    for msg in [&btc, &eth, &sol].iter() {
+        handle_incoming(msg, &pending, &subscriptions).await;
+    }
+
+    assert_eq!(drain(&mut btc_rx), vec![json(&btc)]);
+    assert_eq!(drain(&mut eth_rx), vec![json(&eth)]);
+    assert_eq!(drain(&mut shared_rx), vec![json(&btc), json(&eth)]);
+}
+
+#[tokio::test]
+async fn test_routes_by_subaccount_id_and_drops_closed_senders() {
+    let pending = Arc::new(DashMap::new());
+    let subscriptions = Arc::new(DashMap::new());
+    let (first_tx, mut first_rx) = route_sender(1);
+    let (second_tx, second_rx) = route_sender(2);
+    subscriptions.insert(
+        route_key("OrderFill", "sub-1"),
+        get_route("sub-1", vec![first_tx]),
+    );
+    subscriptions.insert(
+        route_key("OrderFill", "sub-2"),
+        get_route("sub-2", vec![second_tx]),
+    );
+    drop(second_rx);
+
+    let first = Bytes::from(r#"{"e":"OrderFill","data":{"d":[{"s":"BTCUSD","sid":"sub-1"}]}}"#);
+    let second = Bytes::from(r#"{"e":"OrderFill","data":{"d":[{"s":"BTCUSD","sid":"sub-2"}]}}"#);
+    handle_incoming(&first, &pending, &subscriptions).await;
+    handle_incoming(&second, &pending, &subscriptions).await;
+
+    assert_eq!(drain(&mut first_rx), vec![json(&first)]);
+    assert_eq!(
+        subscriptions
+            .get(&route_key("OrderFill", "sub-1"))
+            .unwrap()
+            .senders
+            .len(),
+        1
+    );
+    // The emptied route stays until its handle unsubscribes it from the server.
+    assert!(subscriptions
+        .get(&route_key("OrderFill", "sub-2"))
+        .unwrap()
+        .senders
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_stream_routes_conflate_per_symbol() {
+    let pending = Arc::new(DashMap::new());
+    let subscriptions = Arc::new(DashMap::new());
+    let config = StreamConfig::default()
+        .capacity(4)
+        .overflow(OverflowPolicy::ConflateLatest);
+    let (queue, messages) = subscription_queue::<serde_json::Value>(config);
+    let sender = RouteSender {
+        subscription_id: 1,
+        queue,
+    };
+    for symbol in ["BTCUSD", "ETHUSD"].iter() {
+        subscriptions.insert(
+            route_key("Ticker", symbol),
+            get_route(symbol, vec![sender.clone()]),
+        );
+    }
+    drop(sender);
+
+    for (symbol, t) in [("BTCUSD", 1), ("ETHUSD", 2), ("BTCUSD", 3)].iter() {
+        let msg = format!(r#"{{"e":"Ticker","data":{{"s":"{symbol}","t":{t}}}}}"#);
+        handle_incoming(&Bytes::from(msg), &pending, &subscriptions).await;
+    }
+    subscriptions.clear();
+
+    let received: Vec<(String, u64)> = messages
+        .map(|msg| {
+            let msg = msg.unwrap();
+            let data = &msg["data"];
+            (
+                data["s"].as_str().unwrap().to_string(),
+                data["t"].as_u64().unwrap(),
+            )
+        })
+        .collect()
+        .await;
+    assert_eq!(
+        received,
+        vec![("BTCUSD".to_string(), 3), ("ETHUSD".to_string(), 2)]
+    );
+}
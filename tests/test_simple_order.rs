@@ -1,6 +1,8 @@
 mod common;
 use ethereal_rust_sdk::apis::order_api::OrderControllerListBySubaccountIdParams;
-use ethereal_rust_sdk::models::{OrderSide, OrderTimeInForce, OrderType, SubmitCreatedCode};
+use ethereal_rust_sdk::models::{OrderSide, OrderTimeInForce, StopType, SubmitCreatedCode};
+use ethereal_rust_sdk::order_builder::OrderBuilder;
+use ethereal_rust_sdk::order_group::BracketOrder;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
@@ -16,26 +18,14 @@ async fn test_simple_order() {
     let quantity = dec!(0.0010);
     let price = dec!(40000.0);
     let side = OrderSide::BUY;
-    let r#type = OrderType::Limit;
-
-    let expires_at = None;
-    let time_in_force = OrderTimeInForce::Gtd;
-
-    // We have a few more options when creating an order now.
-    let post_only = false;
-    let reduce_only = false;
 
     let order = client
         .submit_order(
-            ticker,
-            quantity,
-            price,
-            side,
-            r#type,
-            time_in_force,
-            post_only,
-            reduce_only,
-            expires_at,
+            &client.subaccounts[0],
+            OrderBuilder::limit(ticker, side, quantity, price)
+                .time_in_force(OrderTimeInForce::Gtd)
+                .post_only(false)
+                .reduce_only(false),
         )
         .await
         .unwrap();
@@ -45,6 +35,52 @@ async fn test_simple_order() {
     assert!(order.filled == Decimal::ZERO);
 }
 
+#[tokio::test]
+async fn test_market_order() {
+    let client = common::create_test_client()
+        .await
+        .expect("Failed to create client");
+
+    let order = client
+        .submit_order(
+            &client.subaccounts[0],
+            OrderBuilder::market("BTC-USD", OrderSide::BUY, dec!(0.0010)),
+        )
+        .await
+        .unwrap();
+    println!("Market order created: {:?}", order);
+    assert!(order.result == SubmitCreatedCode::Ok);
+
+    let close = client
+        .submit_order(
+            &client.subaccounts[0],
+            OrderBuilder::market("BTC-USD", OrderSide::SELL, Decimal::ZERO)
+                .reduce_only(true)
+                .close(true),
+        )
+        .await
+        .unwrap();
+    println!("Close order created: {:?}", close);
+    assert!(close.result == SubmitCreatedCode::Ok);
+}
+
+#[tokio::test]
+async fn test_market_order_close_requires_zero_quantity() {
+    let client = common::create_test_client()
+        .await
+        .expect("Failed to create client");
+
+    let result = client
+        .submit_order(
+            &client.subaccounts[0],
+            OrderBuilder::market("BTC-USD", OrderSide::SELL, dec!(0.0010))
+                .reduce_only(true)
+                .close(true),
+        )
+        .await;
+    assert!(result.is_err());
+}
+
 #[tokio::test]
 async fn test_cancel_all_orders() {
     let client = common::create_test_client()
@@ -70,7 +106,82 @@ async fn test_cancel_all_orders() {
         .map(|order| order.id.clone().to_string())
         .collect();
 
-    let result = client.cancel_orders(order_ids).await;
+    let result = client
+        .cancel_orders(&client.subaccounts[0], order_ids)
+        .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_stop_order() {
+    let client = common::create_test_client()
+        .await
+        .expect("Failed to create client");
 
+    let order = client
+        .submit_order(
+            &client.subaccounts[0],
+            OrderBuilder::stop_market(
+                "BTC-USD",
+                OrderSide::SELL,
+                dec!(0.0010),
+                dec!(1000.0),
+                StopType::LOSS,
+            )
+            .reduce_only(true),
+        )
+        .await
+        .unwrap();
+    assert!(order.result == SubmitCreatedCode::Ok);
+
+    let stops = client
+        .get_stop_orders(&client.subaccounts[0], vec![StopType::LOSS])
+        .await
+        .unwrap();
+    assert!(stops.iter().any(|stop| stop.id == order.id));
+}
+
+#[tokio::test]
+async fn test_bracket_order() {
+    let client = common::create_test_client()
+        .await
+        .expect("Failed to create client");
+
+    let bracket = BracketOrder::new(OrderBuilder::limit(
+        "BTC-USD",
+        OrderSide::BUY,
+        dec!(0.0010),
+        dec!(40000.0),
+    ))
+    .take_profit(dec!(50000.0))
+    .stop_loss(dec!(35000.0));
+    let group = client
+        .submit_bracket(&client.subaccounts[0], bracket)
+        .await
+        .unwrap();
+    assert_eq!(group.orders.len(), 3);
+
+    let orders = client.get_order_group(&group).await.unwrap();
+    assert!(!orders.is_empty());
+
+    let result = client.cancel_order_group(&group).await;
     assert!(result.is_ok());
 }
+
+#[tokio::test]
+async fn test_subaccount_session() {
+    let client = common::create_test_client()
+        .await
+        .expect("Failed to create client");
+
+    let subaccount = &client.subaccounts[0];
+    let session = client.session(subaccount.name.as_str()).unwrap();
+    assert_eq!(session.id(), subaccount.id);
+    assert!(client.session(uuid::Uuid::nil()).is_err());
+
+    let orders = session.get_open_orders().await.unwrap();
+    assert!(orders
+        .iter()
+        .all(|order| order.subaccount_id == subaccount.id));
+}
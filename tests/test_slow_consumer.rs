@@ -0,0 +1,206 @@
+use std::{
+    sync::{Arc, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use ethereal_rust_sdk::{
+    channels::Channels,
+    routing::extract_timestamp,
+    subscription_stream::{
+        monitored_subscription_queue, subscription_queue, OverflowPolicy, QueueSender,
+        SlowConsumerAction, SlowConsumerMonitor, SlowConsumerReason, SlowConsumerWarning,
+        StreamConfig,
+    },
+    ws_client::WsEvent,
+};
+use futures_util::{FutureExt, StreamExt};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+fn ticker(symbol: &str, t: u64) -> Bytes {
+    Bytes::from(format!(
+        r#"{{"e":"Ticker","t":{t},"data":{{"s":"{symbol}","t":{t}}}}}"#
+    ))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+fn monitor(action: SlowConsumerAction) -> (SlowConsumerMonitor, UnboundedReceiver<WsEvent>) {
+    let (events, events_rx) = mpsc::unbounded_channel();
+    let monitor = SlowConsumerMonitor {
+        channel: Channels::Ticker,
+        subscription_id: 7,
+        events,
+        policy: Some(Arc::new(move |_: &SlowConsumerWarning| action)),
+    };
+    (monitor, events_rx)
+}
+
+fn warnings(events: &mut UnboundedReceiver<WsEvent>) -> Vec<SlowConsumerWarning> {
+    let mut warnings = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        match event {
+            WsEvent::SlowConsumer(warning) => warnings.push(warning),
+            event => panic!("Unexpected event {:?}", event),
+        }
+    }
+    warnings
+}
+
+#[test]
+fn test_extract_timestamp() {
+    assert_eq!(
+        extract_timestamp(&ticker("BTCUSD", 1700000000123)),
+        Some(1700000000123)
+    );
+    assert_eq!(extract_timestamp(br#"{"e":"Ticker","data":{}}"#), None);
+    assert_eq!(extract_timestamp(br#"{"e":"Ticker","t":"x"}"#), None);
+}
+
+#[tokio::test]
+async fn test_metrics_count_depth_drops_and_deliveries() {
+    let config = StreamConfig::default()
+        .capacity(2)
+        .overflow(OverflowPolicy::DropNewest);
+    let (queue, messages) = subscription_queue::<serde_json::Value>(config);
+    let mut messages = Box::pin(messages);
+    for t in 1..=3 {
+        assert!(queue.send("BTCUSD", ticker("BTCUSD", t)).await);
+    }
+    let metrics = queue.metrics();
+    assert_eq!(metrics.queue_depth, 2);
+    assert_eq!(metrics.dropped, 1);
+    assert_eq!(metrics.delivered, 0);
+
+    let first = messages.next().await.unwrap().unwrap();
+    assert_eq!(first["t"], 1);
+    let metrics = queue.metrics();
+    assert_eq!(metrics.queue_depth, 1);
+    assert_eq!(metrics.delivered, 1);
+    // Timestamps from 1970 are as late as it gets.
+    assert!(metrics.max_lag > Duration::from_secs(3600));
+}
+
+#[tokio::test]
+async fn test_lagging_consumer_is_reported() {
+    let (monitor, mut events) = monitor(SlowConsumerAction::Continue);
+    let config = StreamConfig::default().lag_warning(Duration::from_secs(5));
+    let (queue, messages) = monitored_subscription_queue::<serde_json::Value>(config, monitor);
+    let mut messages = Box::pin(messages);
+
+    queue.send("BTCUSD", ticker("BTCUSD", now_ms())).await;
+    messages.next().await.unwrap().unwrap();
+    assert!(warnings(&mut events).is_empty());
+
+    queue
+        .send("ETHUSD", ticker("ETHUSD", now_ms() - 10_000))
+        .await;
+    messages.next().await.unwrap().unwrap();
+    let warnings = warnings(&mut events);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].subscription_id, 7);
+    assert_eq!(warnings[0].target, "ETHUSD");
+    match warnings[0].reason {
+        SlowConsumerReason::Lagging(lag) => assert!(lag >= Duration::from_secs(10)),
+        reason => panic!("Unexpected reason {:?}", reason),
+    }
+    assert!(queue.metrics().max_lag >= Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn test_drop_warnings_are_rate_limited() {
+    let (monitor, mut events) = monitor(SlowConsumerAction::Continue);
+    let config = StreamConfig::default()
+        .capacity(1)
+        .warning_interval(Duration::from_secs(60));
+    let (queue, _messages) = monitored_subscription_queue::<serde_json::Value>(config, monitor);
+    for t in 1..=5 {
+        assert!(queue.send("BTCUSD", ticker("BTCUSD", t)).await);
+    }
+
+    let warnings = warnings(&mut events);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].reason, SlowConsumerReason::Dropped);
+    assert_eq!(warnings[0].metrics.dropped, 1);
+    assert_eq!(queue.metrics().dropped, 4);
+}
+
+#[tokio::test]
+async fn test_evict_policy_ends_the_stream() {
+    let (monitor, mut events) = monitor(SlowConsumerAction::Evict);
+    let config = StreamConfig::default().capacity(1);
+    let (queue, messages) = monitored_subscription_queue::<serde_json::Value>(config, monitor);
+    assert!(queue.send("BTCUSD", ticker("BTCUSD", 1)).await);
+    assert!(!queue.send("BTCUSD", ticker("BTCUSD", 2)).await);
+    assert!(!queue.send("BTCUSD", ticker("BTCUSD", 3)).await);
+
+    assert_eq!(warnings(&mut events).len(), 1);
+    let remaining: Vec<_> = messages.collect().await;
+    assert!(remaining.is_empty());
+}
+
+#[tokio::test]
+async fn test_blocked_dispatch_is_reported() {
+    let (monitor, mut events) = monitor(SlowConsumerAction::Continue);
+    let config = StreamConfig::default()
+        .capacity(1)
+        .overflow(OverflowPolicy::Block);
+    let (queue, messages) = monitored_subscription_queue::<serde_json::Value>(config, monitor);
+    let mut messages = Box::pin(messages);
+    assert!(queue.send("BTCUSD", ticker("BTCUSD", 1)).await);
+
+    let mut blocked = Box::pin(queue.send("BTCUSD", ticker("BTCUSD", 2)));
+    assert!((&mut blocked).now_or_never().is_none());
+    let warnings = warnings(&mut events);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].reason, SlowConsumerReason::Blocked);
+
+    assert_eq!(messages.next().await.unwrap().unwrap()["t"], 1);
+    assert!(blocked.await);
+    assert_eq!(messages.next().await.unwrap().unwrap()["t"], 2);
+    assert_eq!(queue.metrics().dropped, 0);
+}
+
+#[test]
+fn test_policy_may_read_the_queue_metrics() {
+    let queue_cell = Arc::new(OnceLock::<Arc<QueueSender>>::new());
+    let (events, mut events_rx) = mpsc::unbounded_channel();
+    let policy_queue = queue_cell.clone();
+    let monitor = SlowConsumerMonitor {
+        channel: Channels::Ticker,
+        subscription_id: 7,
+        events,
+        policy: Some(Arc::new(move |_: &SlowConsumerWarning| match policy_queue
+            .get()
+            .unwrap()
+            .metrics()
+            .dropped
+        {
+            0 => SlowConsumerAction::Continue,
+            _ => SlowConsumerAction::Evict,
+        })),
+    };
+    let config = StreamConfig::default().capacity(1);
+    let (queue, _messages) = monitored_subscription_queue::<serde_json::Value>(config, monitor);
+    queue_cell.set(queue.clone()).ok().unwrap();
+
+    // A deadlock would hang the thread, so the result is awaited with a timeout.
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let sent = runtime.block_on(async {
+            queue.send("BTCUSD", ticker("BTCUSD", 1)).await
+                && queue.send("BTCUSD", ticker("BTCUSD", 2)).await
+        });
+        let _ = done_tx.send(sent);
+    });
+    assert_eq!(done_rx.recv_timeout(Duration::from_secs(5)), Ok(false));
+    assert_eq!(warnings(&mut events_rx).len(), 1);
+}
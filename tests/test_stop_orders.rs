@@ -0,0 +1,104 @@
+use ethereal_rust_sdk::models::{
+    OrderSide, OrderStatus, OrderType, OrderUpdateMessageDataDInner, StopPriceType, StopType,
+    TriggeredEnum,
+};
+use ethereal_rust_sdk::order_builder::OrderBuilder;
+use ethereal_rust_sdk::stop_orders::{StopOrderTracker, StopOrderUpdate};
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn get_stop_update(id: Uuid, status: OrderStatus, triggered: &str) -> OrderUpdateMessageDataDInner {
+    OrderUpdateMessageDataDInner {
+        id,
+        otyp: OrderType::Market,
+        qty: dec!(0.01),
+        aqty: dec!(0.01),
+        sd: OrderSide::SELL,
+        s: "BTCUSD".to_string(),
+        st: status,
+        ro: true,
+        spx: Some(dec!(60000)),
+        styp: Some(1),
+        spxtyp: Some(1),
+        tr: triggered.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_decode_stop_update() {
+    let id = Uuid::from_u128(1);
+    let update = get_stop_update(id, OrderStatus::Pending, "NOT_TRIGGERED");
+    let stop = StopOrderUpdate::from_update(&update).unwrap();
+    assert_eq!(stop.order_id, id);
+    assert_eq!(stop.stop_price, dec!(60000));
+    assert_eq!(stop.stop_type, StopType::LOSS);
+    assert_eq!(stop.stop_price_type, Some(StopPriceType::MARK_PRICE));
+    assert_eq!(stop.triggered, TriggeredEnum::NotTriggered);
+    assert!(!stop.is_triggered());
+}
+
+#[test]
+fn test_non_stop_update_is_ignored() {
+    let mut update = get_stop_update(Uuid::from_u128(1), OrderStatus::New, "NOT_TRIGGERED");
+    update.spx = None;
+    update.styp = None;
+    assert!(StopOrderUpdate::from_update(&update).is_none());
+}
+
+#[test]
+fn test_tracker_reports_trigger_transitions() {
+    let id = Uuid::from_u128(1);
+    let mut tracker = StopOrderTracker::new();
+
+    let placed = tracker
+        .apply(&get_stop_update(id, OrderStatus::Pending, "NOT_TRIGGERED"))
+        .unwrap();
+    assert_eq!(placed.previous, None);
+    assert_eq!(tracker.working().count(), 1);
+
+    let repeated = tracker.apply(&get_stop_update(id, OrderStatus::Pending, "NOT_TRIGGERED"));
+    assert!(repeated.is_none());
+
+    let triggered = tracker
+        .apply(&get_stop_update(
+            id,
+            OrderStatus::New,
+            "STOP_ORDER_TRIGGERED",
+        ))
+        .unwrap();
+    assert_eq!(triggered.previous, Some(TriggeredEnum::NotTriggered));
+    assert_eq!(
+        triggered.update.triggered,
+        TriggeredEnum::StopOrderTriggered
+    );
+
+    tracker.apply(&get_stop_update(
+        id,
+        OrderStatus::Filled,
+        "STOP_ORDER_TRIGGERED",
+    ));
+    assert!(tracker.get(&id).is_none());
+}
+
+#[test]
+fn test_stop_builders() {
+    let stop_market = OrderBuilder::stop_market(
+        "BTC-USD",
+        OrderSide::SELL,
+        dec!(0.01),
+        dec!(60000),
+        StopType::LOSS,
+    );
+    assert_eq!(stop_market.order_type(), OrderType::Market);
+
+    let stop_limit = OrderBuilder::stop_limit(
+        "BTC-USD",
+        OrderSide::SELL,
+        dec!(0.01),
+        dec!(59900),
+        dec!(60000),
+        StopType::LOSS,
+    );
+    assert_eq!(stop_limit.order_type(), OrderType::Limit);
+}
@@ -0,0 +1,32 @@
+use ethereal_rust_sdk::async_client::session::{encode_subaccount_name, SubaccountSelector};
+use ethereal_rust_sdk::models::SubaccountDto;
+use uuid::Uuid;
+
+fn get_test_subaccount() -> SubaccountDto {
+    SubaccountDto {
+        id: Uuid::from_u128(1),
+        name: "0x7072696d61727900000000000000000000000000000000000000000000000000".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_encode_subaccount_name() {
+    assert_eq!(
+        encode_subaccount_name("primary"),
+        "0x7072696d61727900000000000000000000000000000000000000000000000000"
+    );
+}
+
+#[test]
+fn test_selector_matches() {
+    let subaccount = get_test_subaccount();
+    assert!(SubaccountSelector::First.matches(&subaccount));
+    assert!(SubaccountSelector::from("primary").matches(&subaccount));
+    assert!(SubaccountSelector::from(subaccount.name.clone()).matches(&subaccount));
+    assert!(SubaccountSelector::from(Uuid::from_u128(1)).matches(&subaccount));
+    assert!(SubaccountSelector::from(&subaccount).matches(&subaccount));
+
+    assert!(!SubaccountSelector::from("secondary").matches(&subaccount));
+    assert!(!SubaccountSelector::from(Uuid::from_u128(2)).matches(&subaccount));
+}
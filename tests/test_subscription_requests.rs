@@ -0,0 +1,72 @@
+use ethereal_rust_sdk::{
+    channels::Channels,
+    types::{
+        ProductSubscriptionMessage, SubaccountSubscriptionMessage, SubscriptionAck,
+        SubscriptionRequest,
+    },
+    ws_client::ClientError,
+};
+
+fn parse_ack(json: &str) -> Result<(), ClientError> {
+    serde_json::from_str::<SubscriptionAck>(json)
+        .unwrap()
+        .into_result()
+}
+
+#[test]
+fn test_subscribe_and_unsubscribe_payloads() {
+    let request = ProductSubscriptionMessage {
+        msg_type: Channels::Ticker,
+        symbol: "BTCUSD".to_string(),
+    };
+    assert_eq!(request.target(), "BTCUSD");
+    let payload: serde_json::Value =
+        serde_json::from_slice(&request.payload("unsubscribe")).unwrap();
+    assert_eq!(
+        payload,
+        serde_json::json!({
+            "event": "unsubscribe",
+            "data": {"type": "Ticker", "symbol": "BTCUSD"}
+        })
+    );
+
+    let request = SubaccountSubscriptionMessage {
+        msg_type: Channels::OrderFill,
+        subaccount_id: "sub-1".to_string(),
+    };
+    assert_eq!(request.target(), "sub-1");
+    let payload: serde_json::Value = serde_json::from_slice(&request.payload("subscribe")).unwrap();
+    assert_eq!(payload["event"], "subscribe");
+    assert_eq!(payload["data"]["subaccountId"], "sub-1");
+}
+
+#[test]
+fn test_ack_success() {
+    assert!(parse_ack(r#"{"id": 1}"#).is_ok());
+    assert!(parse_ack(r#"{"id": 1, "ok": true}"#).is_ok());
+    assert!(parse_ack(r#"{"id": 1, "error": null}"#).is_ok());
+}
+
+#[test]
+fn test_ack_errors_are_surfaced() {
+    let message = |result: Result<(), ClientError>| match result {
+        Err(ClientError::SubscriptionError(message)) => message,
+        other => panic!("expected a subscription error, got {:?}", other),
+    };
+    assert_eq!(
+        message(parse_ack(r#"{"id": 1, "error": "Unknown symbol"}"#)),
+        "Unknown symbol"
+    );
+    assert_eq!(
+        message(parse_ack(
+            r#"{"id": 1, "error": {"code": 400, "message": "Not subscribed"}}"#
+        )),
+        "Not subscribed"
+    );
+    assert_eq!(
+        message(parse_ack(
+            r#"{"id": 1, "ok": false, "message": "Too many"}"#
+        )),
+        "Too many"
+    );
+}
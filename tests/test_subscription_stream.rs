@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use ethereal_rust_sdk::subscription_stream::{subscription_queue, OverflowPolicy, StreamConfig};
+use futures_util::StreamExt;
+
+fn get_message(n: u64) -> Bytes {
+    Bytes::from(format!(r#"{{"n":{n}}}"#))
+}
+
+fn get_config(capacity: usize, overflow: OverflowPolicy) -> StreamConfig {
+    StreamConfig::default()
+        .capacity(capacity)
+        .overflow(overflow)
+}
+
+async fn collect_numbers(
+    messages: impl futures_util::Stream<Item = Result<serde_json::Value, impl std::fmt::Debug>>,
+) -> Vec<u64> {
+    messages
+        .map(|msg| msg.unwrap()["n"].as_u64().unwrap())
+        .collect()
+        .await
+}
+
+#[tokio::test]
+async fn test_drop_oldest() {
+    let (sender, messages) = subscription_queue(get_config(2, OverflowPolicy::DropOldest));
+    for n in 1..=3 {
+        assert!(sender.send("BTCUSD", get_message(n)).await);
+    }
+    drop(sender);
+    assert_eq!(collect_numbers(messages).await, vec![2, 3]);
+}
+
+#[tokio::test]
+async fn test_drop_newest() {
+    let (sender, messages) = subscription_queue(get_config(2, OverflowPolicy::DropNewest));
+    for n in 1..=3 {
+        assert!(sender.send("BTCUSD", get_message(n)).await);
+    }
+    drop(sender);
+    assert_eq!(collect_numbers(messages).await, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn test_conflate_latest_per_target() {
+    let (sender, messages) = subscription_queue(get_config(2, OverflowPolicy::ConflateLatest));
+    sender.send("BTCUSD", get_message(1)).await;
+    sender.send("ETHUSD", get_message(2)).await;
+    sender.send("BTCUSD", get_message(3)).await;
+    sender.send("ETHUSD", get_message(4)).await;
+    drop(sender);
+    assert_eq!(collect_numbers(messages).await, vec![3, 4]);
+}
+
+#[tokio::test]
+async fn test_block_waits_for_consumer() {
+    let (sender, messages) = subscription_queue(get_config(1, OverflowPolicy::Block));
+    let mut messages = Box::pin(messages);
+    assert!(sender.send("BTCUSD", get_message(1)).await);
+
+    let blocked = tokio::time::timeout(
+        Duration::from_millis(50),
+        sender.send("BTCUSD", get_message(2)),
+    )
+    .await;
+    assert!(blocked.is_err());
+
+    let (sent, first) = tokio::join!(sender.send("BTCUSD", get_message(2)), messages.next());
+    assert!(sent);
+    let first: serde_json::Value = first.unwrap().unwrap();
+    assert_eq!(first["n"], 1);
+    drop(sender);
+    assert_eq!(collect_numbers(messages).await, vec![2]);
+}
+
+#[tokio::test]
+async fn test_send_fails_once_stream_is_dropped() {
+    let (sender, messages) =
+        subscription_queue::<serde_json::Value>(get_config(1, OverflowPolicy::Block));
+    assert!(sender.send("BTCUSD", get_message(1)).await);
+    drop(messages);
+    assert!(!sender.send("BTCUSD", get_message(2)).await);
+}
+
+#[tokio::test]
+async fn test_malformed_messages_are_yielded_as_errors() {
+    let (sender, messages) = subscription_queue::<serde_json::Value>(StreamConfig::default());
+    sender.send("BTCUSD", Bytes::from("not json")).await;
+    sender.send("BTCUSD", get_message(1)).await;
+    drop(sender);
+    let results: Vec<_> = messages.collect().await;
+    assert!(results[0].is_err());
+    assert_eq!(results[1].as_ref().unwrap()["n"], 1);
+}
@@ -13,7 +13,7 @@ async fn ticker_sub() -> Result<(), Box<dyn std::error::Error>> {
     let tx_arc = std::sync::Arc::new(tokio::sync::Mutex::new(Some(tx)));
     client.wait_for_connection().await;
 
-    let _ = client
+    let _subscription = client
         .subscriptions()
         .ticker(["BTCUSD".to_string()].to_vec(), move |_msg| {
             // Parses into a json value initally
@@ -0,0 +1,237 @@
+mod common;
+
+use std::{str::FromStr, time::Duration};
+
+use common::{within, MockExchange, Reply, WsRequest, PRIVATE_KEY, SUBACCOUNT_NAME};
+use ethereal_rust_sdk::{
+    channels::Channels,
+    enums::Environment,
+    signable_messages::EIP712Auth,
+    signing::Eip712,
+    types::SubaccountSubscriptionMessage,
+    ws_auth::{AuthError, AuthState, WsAuth},
+    ws_client::{ClientError, WsClient, WsEvent},
+};
+use ethers::types::{Address, Signature, H256};
+
+/// Any intent the server might assign; the spec does not define one for private streams.
+const INTENT: u8 = 7;
+
+/// How the local server answers auth requests.
+#[derive(Clone, Copy)]
+enum Mode {
+    Accept,
+    Reject,
+    /// Leave auth requests unanswered.
+    Ignore,
+    /// Accept, but close the first connection after its first subscribe request.
+    CloseFirst,
+}
+
+/// Checks that an auth request carries a valid signature of its own fields.
+fn verify_auth(data: &serde_json::Value) -> bool {
+    let Ok(sender) = Address::from_str(data["sender"].as_str().unwrap_or_default()) else {
+        return false;
+    };
+    let message = EIP712Auth {
+        sender,
+        intent: data["intent"].as_u64().unwrap_or_default() as u8,
+        signed_at: data["signedAt"]
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default(),
+    };
+    let hash = message.encode_eip712(Environment::Testnet).unwrap();
+    let Ok(signature) = Signature::from_str(data["signature"].as_str().unwrap_or_default()) else {
+        return false;
+    };
+    data["auth"] == "EIP712Auth" && signature.recover(H256::from(hash)).ok() == Some(sender)
+}
+
+/// Answers auth requests as `mode` says and acknowledges every other request.
+fn reply(mode: Mode, request: &WsRequest) -> Vec<Reply> {
+    let id = &request.id;
+    let ack = match (mode, request.event()) {
+        (Mode::Ignore, "auth") => return vec![],
+        (Mode::Reject, "auth") => {
+            serde_json::json!({ "id": id, "ok": false, "message": "unknown signer" })
+        }
+        (_, "auth") if !verify_auth(&request.request["data"]) => {
+            serde_json::json!({ "id": id, "ok": false, "message": "bad signature" })
+        }
+        _ => serde_json::json!({ "id": id, "ok": true }),
+    };
+    let mut replies = vec![Reply::Send(ack.to_string())];
+    if request.event() == "subscribe" && request.connection == 1 && matches!(mode, Mode::CloseFirst)
+    {
+        replies.push(Reply::Close);
+    }
+    replies
+}
+
+async fn spawn_server(mode: Mode) -> MockExchange {
+    MockExchange::builder()
+        .ws(move |request| reply(mode, request))
+        .start()
+        .await
+}
+
+fn client(server: &MockExchange, auth: WsAuth) -> WsClient {
+    WsClient::authenticated(Environment::Testnet, server.ws_config(), auth)
+}
+
+fn order_updates() -> Vec<SubaccountSubscriptionMessage> {
+    vec![SubaccountSubscriptionMessage {
+        msg_type: Channels::OrderUpdate,
+        subaccount_id: "5e2f4ed2-7c1b-4d3a-9a47-2e0a3b8d6c11".to_string(),
+    }]
+}
+
+#[test]
+fn test_auth_request_is_signed_by_the_wallet() {
+    let auth = WsAuth::new(PRIVATE_KEY, INTENT).unwrap();
+    let request: serde_json::Value =
+        serde_json::from_slice(&auth.request(Environment::Testnet).unwrap()).unwrap();
+    assert_eq!(request["event"], "auth");
+    assert_eq!(request["data"]["intent"], INTENT);
+    assert!(request["data"].get("subaccount").is_none());
+    assert!(verify_auth(&request["data"]));
+
+    let linked = WsAuth::new(PRIVATE_KEY, INTENT)
+        .unwrap()
+        .linked_signer(SUBACCOUNT_NAME)
+        .request(Environment::Testnet)
+        .unwrap();
+    let linked: serde_json::Value = serde_json::from_slice(&linked).unwrap();
+    assert_eq!(linked["data"]["subaccount"], SUBACCOUNT_NAME);
+}
+
+#[test]
+fn test_refresh_interval_leaves_a_margin() {
+    let auth = WsAuth::new(PRIVATE_KEY, INTENT).unwrap();
+    assert_eq!(auth.refresh_interval(), Duration::from_secs(3540));
+    let auth = auth.session_ttl(Duration::from_secs(10), Duration::from_secs(30));
+    assert_eq!(auth.refresh_interval(), Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_authenticates_before_subscribing() {
+    let server = spawn_server(Mode::Accept).await;
+    let client = client(&server, WsAuth::new(PRIVATE_KEY, INTENT).unwrap());
+    within(client.wait_for_authentication()).await.unwrap();
+    assert_eq!(client.auth_state(), AuthState::Authenticated);
+
+    let _subscription = client
+        .subscriptions()
+        .order_update_stream(vec!["5e2f4ed2-7c1b-4d3a-9a47-2e0a3b8d6c11".to_string()])
+        .await
+        .unwrap();
+    let auth = server.next_request().await;
+    let subscribe = server.next_request().await;
+    assert_eq!(auth.request["event"], "auth");
+    assert_eq!(subscribe.request["event"], "subscribe");
+    client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_rejected_auth_is_a_typed_error() {
+    let server = spawn_server(Mode::Reject).await;
+    let client = client(&server, WsAuth::new(PRIVATE_KEY, INTENT).unwrap());
+    match within(client.wait_for_authentication()).await {
+        Err(ClientError::Auth(AuthError::Rejected(message))) => {
+            assert_eq!(message, "unknown signer")
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+    loop {
+        if let WsEvent::AuthFailed(e) = within(client.run_till_event()).await {
+            assert_eq!(e, AuthError::Rejected("unknown signer".to_string()));
+            break;
+        }
+    }
+    client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unanswered_auth_times_out_within_the_read_timeout() {
+    let server = spawn_server(Mode::Ignore).await;
+    let config = server.ws_config().read_timeout(Duration::from_millis(300));
+    let client = WsClient::authenticated(
+        Environment::Testnet,
+        config,
+        WsAuth::new(PRIVATE_KEY, INTENT).unwrap(),
+    );
+    assert!(matches!(
+        within(client.wait_for_authentication()).await,
+        Err(ClientError::Auth(AuthError::Timeout(timeout))) if timeout == Duration::from_millis(300)
+    ));
+    client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_client_without_signer_is_not_authenticated() {
+    let server = spawn_server(Mode::Accept).await;
+    let client = WsClient::with_config(Environment::Testnet, server.ws_config());
+    assert!(matches!(
+        client.wait_for_authentication().await,
+        Err(ClientError::Auth(AuthError::NotConfigured))
+    ));
+    client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_renews_auth_before_expiry() {
+    let server = spawn_server(Mode::Accept).await;
+    let auth = WsAuth::new(PRIVATE_KEY, INTENT)
+        .unwrap()
+        .session_ttl(Duration::from_secs(2), Duration::from_secs(1));
+    let client = client(&server, auth);
+
+    let first = server.next_request().await;
+    let renewed = server.next_request().await;
+    assert_eq!(first.request["event"], "auth");
+    assert_eq!(renewed.request["event"], "auth");
+    assert_eq!(renewed.connection, 1);
+    let mut authenticated = 0;
+    while authenticated < 2 {
+        if let WsEvent::Authenticated = within(client.run_till_event()).await {
+            authenticated += 1;
+        }
+    }
+    client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reauthenticates_before_replaying_after_reconnect() {
+    let server = spawn_server(Mode::CloseFirst).await;
+    let client = client(&server, WsAuth::new(PRIVATE_KEY, INTENT).unwrap());
+    within(client.wait_for_authentication()).await.unwrap();
+    let _updates = client
+        .subscribe_stream::<_, serde_json::Value>(
+            Channels::OrderUpdate,
+            order_updates(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+
+    let received: Vec<(usize, String)> = within(async {
+        let mut received = Vec::new();
+        while received.len() < 4 {
+            let r = server.next_request().await;
+            received.push((r.connection, r.event().to_string()));
+        }
+        received
+    })
+    .await;
+    let expected = [(1, "auth"), (1, "subscribe"), (2, "auth"), (2, "subscribe")];
+    assert_eq!(
+        received,
+        expected
+            .iter()
+            .map(|(c, e)| (*c, e.to_string()))
+            .collect::<Vec<_>>()
+    );
+    assert!(server.requests().await.is_empty());
+    client.shutdown("test").await.unwrap();
+}
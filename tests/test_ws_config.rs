@@ -0,0 +1,237 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{MockExchange, ServerEvent, WsMode};
+use ethereal_rust_sdk::{
+    enums::Environment,
+    ws_client::{ClientError, ConnectionState, WsClient, WsEvent},
+    ws_config::WsConfig,
+};
+use futures_util::StreamExt;
+use tokio::{net::TcpListener, time::timeout};
+
+async fn spawn_server(mode: WsMode) -> MockExchange {
+    MockExchange::builder().mode(mode).start().await
+}
+
+async fn next_state(client: &WsClient) -> ConnectionState {
+    let event = timeout(Duration::from_secs(5), client.run_till_event())
+        .await
+        .expect("no client event within 5s");
+    match event {
+        WsEvent::Connection(state) => state,
+        event => panic!("Unexpected event {:?}", event),
+    }
+}
+
+#[test]
+fn test_backoff_doubles_up_to_the_cap() {
+    let config = WsConfig::default()
+        .backoff(Duration::from_millis(100), Duration::from_secs(1))
+        .jitter(0.0);
+    let delays: Vec<Duration> = (1..=6)
+        .map(|attempt| config.backoff_delay(attempt))
+        .collect();
+    assert_eq!(
+        delays,
+        vec![
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+            Duration::from_millis(400),
+            Duration::from_millis(800),
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+        ]
+    );
+    assert_eq!(config.backoff_delay(1000), Duration::from_secs(1));
+}
+
+#[test]
+fn test_backoff_jitter_stays_within_bounds() {
+    let config = WsConfig::default()
+        .backoff(Duration::from_secs(1), Duration::from_secs(1))
+        .jitter(0.5);
+    let delays: Vec<Duration> = (0..100).map(|_| config.backoff_delay(3)).collect();
+    assert!(delays
+        .iter()
+        .all(|d| *d >= Duration::from_millis(500) && *d <= Duration::from_secs(1)));
+    assert!(delays.iter().any(|d| *d != delays[0]));
+}
+
+#[test]
+fn test_attempt_limit_and_url_override() {
+    let config = WsConfig::default();
+    assert!(config.should_retry(1000));
+    assert_eq!(
+        config.server_url(Environment::Testnet),
+        Environment::Testnet.get_server_url()
+    );
+
+    let config = config.max_attempts(3).url("ws://127.0.0.1:1");
+    assert!(config.should_retry(2));
+    assert!(!config.should_retry(3));
+    assert_eq!(config.server_url(Environment::Mainnet), "ws://127.0.0.1:1");
+}
+
+#[tokio::test]
+async fn test_gives_up_after_max_attempts() {
+    // Nothing listens on the port once the listener is dropped.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let config = WsConfig::default()
+        .url(url)
+        .backoff(Duration::from_millis(10), Duration::from_millis(20))
+        .max_attempts(3);
+    let client = WsClient::with_config(Environment::Testnet, config);
+    assert_eq!(next_state(&client).await, ConnectionState::Reconnecting);
+    assert_eq!(next_state(&client).await, ConnectionState::Exited);
+}
+
+#[tokio::test]
+async fn test_reconnects_after_server_closes() {
+    let server = spawn_server(WsMode::Close).await;
+    let config = server.ws_config();
+    let client = WsClient::with_config(Environment::Testnet, config);
+
+    for _ in 0..3 {
+        assert!(matches!(
+            server.next_event().await,
+            ServerEvent::Connected(_)
+        ));
+    }
+    client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_dropped_connections_count_as_failed_attempts() {
+    let server = spawn_server(WsMode::Close).await;
+    let config = server
+        .ws_config()
+        .backoff(Duration::from_millis(10), Duration::from_millis(20))
+        .max_attempts(3);
+    let client = WsClient::with_config(Environment::Testnet, config);
+
+    while next_state(&client).await != ConnectionState::Exited {}
+    let events = server.events().await;
+    assert!(events
+        .iter()
+        .all(|event| matches!(event, ServerEvent::Connected(_))));
+    assert_eq!(events.len(), 3);
+}
+
+#[tokio::test]
+async fn test_pings_at_the_configured_interval() {
+    let server = spawn_server(WsMode::Serve).await;
+    let config = server.ws_config().ping_interval(Duration::from_millis(50));
+    let client = WsClient::with_config(Environment::Testnet, config);
+    client.wait_for_connection().await;
+
+    assert!(matches!(
+        server.next_event().await,
+        ServerEvent::Connected(_)
+    ));
+    for _ in 0..3 {
+        assert_eq!(server.next_event().await, ServerEvent::Ping);
+    }
+    client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reconnects_after_read_timeout() {
+    let server = spawn_server(WsMode::Silent).await;
+    let config = server
+        .ws_config()
+        .ping_interval(Duration::from_millis(50))
+        .read_timeout(Duration::from_millis(200));
+    let client = WsClient::with_config(Environment::Testnet, config);
+
+    assert_eq!(next_state(&client).await, ConnectionState::Connected);
+    // `Disconnected` may be superseded by `Reconnecting` before it is seen.
+    assert!(matches!(
+        next_state(&client).await,
+        ConnectionState::Disconnected | ConnectionState::Reconnecting
+    ));
+    while next_state(&client).await != ConnectionState::Connected {}
+    for _ in 0..2 {
+        assert!(matches!(
+            server.next_event().await,
+            ServerEvent::Connected(_)
+        ));
+    }
+    client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_compression_can_be_disabled() {
+    let server = spawn_server(WsMode::Serve).await;
+    let compressed = WsClient::with_config(Environment::Testnet, server.ws_config());
+    match server.next_event().await {
+        ServerEvent::Connected(Some(extensions)) => {
+            assert!(extensions.contains("permessage-deflate"))
+        }
+        event => panic!("Unexpected event {:?}", event),
+    }
+    compressed.shutdown("test").await.unwrap();
+
+    let plain = WsClient::with_config(Environment::Testnet, server.ws_config().compression(false));
+    assert_eq!(server.next_event().await, ServerEvent::Connected(None));
+    plain.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unacknowledged_subscribe_is_kept_unless_required() {
+    // The server never acknowledges subscribe requests.
+    let server = MockExchange::builder().ws(|_| vec![]).start().await;
+    let config = server
+        .ws_config()
+        .subscribe_ack_timeout(Duration::from_millis(200));
+
+    let client = WsClient::with_config(Environment::Testnet, config.clone());
+    client.wait_for_connection().await;
+    let stream = client
+        .subscriptions()
+        .l2_book_stream(vec!["BTCUSD".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(stream.handle().targets(), ["BTCUSD".to_string()]);
+    drop(stream);
+    client.shutdown("test").await.unwrap();
+
+    let strict = WsClient::with_config(Environment::Testnet, config.require_subscribe_ack(true));
+    strict.wait_for_connection().await;
+    let result = strict
+        .subscriptions()
+        .l2_book_stream(vec!["BTCUSD".to_string()])
+        .await;
+    assert!(matches!(result, Err(ClientError::Timeout(_))));
+    strict.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_streams_end_when_reconnecting_is_given_up() {
+    // Nothing listens on the port once the listener is dropped.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let config = WsConfig::default()
+        .url(url)
+        .backoff(Duration::from_millis(200), Duration::from_millis(200))
+        .jitter(0.0)
+        .max_attempts(3)
+        .subscribe_ack_timeout(Duration::from_millis(50));
+    let client = WsClient::with_config(Environment::Testnet, config);
+    // Never acknowledged, so kept until the client gives up.
+    let mut stream = client
+        .subscriptions()
+        .l2_book_stream(vec!["BTCUSD".to_string()])
+        .await
+        .unwrap();
+    let next = timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("stream still open 5s after giving up");
+    assert!(next.is_none());
+}
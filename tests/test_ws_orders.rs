@@ -0,0 +1,157 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{within, MockExchange, Reply, WsRequest};
+use ethereal_rust_sdk::{
+    async_client::{client::HttpClient, session::SubaccountSelector},
+    error::Error,
+    models::{
+        CancelOrderResultCode, OrderSide, SubmitCreatedCode, SubmitFailedCode,
+        SubmitOrderCreatedResultCode,
+    },
+    order_builder::OrderBuilder,
+    ws_client::{ClientError, WsClient},
+    ws_orders::OrderResponse,
+};
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+/// Answers order requests the way the REST endpoints would, under `data`. A submission with a
+/// client order id starting with `slow` is answered after the next request, and one with
+/// `reject` is refused.
+fn respond(request: &WsRequest) -> serde_json::Value {
+    let id = &request.id;
+    let data = &request.request["data"];
+    match request.event() {
+        "submit_order" => {
+            assert!(data["signature"].as_str().unwrap().starts_with("0x"));
+            let client_order_id = data["data"]["clientOrderId"].clone();
+            if client_order_id == "reject" {
+                return serde_json::json!({ "id": id, "error": { "message": "InsufficientBalance" } });
+            }
+            serde_json::json!({ "id": id, "data": {
+                "id": Uuid::new_v4(),
+                "clientOrderId": client_order_id,
+                "filled": "0",
+                "result": "Ok",
+            }})
+        }
+        "dry_run_order" => {
+            assert!(data.get("signature").is_none());
+            serde_json::json!({ "id": id, "data": {
+                "code": "Ok",
+                "marginAvailable": "100",
+                "marginRequired": "8",
+                "riskAvailable": "90",
+                "riskUsed": "10",
+                "totalUsedMargin": "18",
+            }})
+        }
+        "cancel_order" => {
+            let results: Vec<_> = data["data"]["orderIds"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|order_id| serde_json::json!({ "id": order_id, "result": "Ok" }))
+                .collect();
+            serde_json::json!({ "id": id, "data": { "data": results } })
+        }
+        event => panic!("Unexpected event {}", event),
+    }
+}
+
+fn reply(request: &WsRequest) -> Vec<Reply> {
+    let response = respond(request).to_string();
+    let client_order_id = request.request["data"]["data"]["clientOrderId"].as_str();
+    if client_order_id.is_some_and(|id| id.starts_with("slow")) {
+        return vec![Reply::Defer(response)];
+    }
+    vec![Reply::Send(response)]
+}
+
+async fn clients() -> (HttpClient, WsClient) {
+    let server = MockExchange::builder().ws(reply).start().await;
+    (server.http_client().await, server.ws_client().await)
+}
+
+fn order(client_order_id: &str) -> OrderBuilder {
+    OrderBuilder::limit("BTC-USD", OrderSide::BUY, dec!(0.001), dec!(80000))
+        .client_order_id(client_order_id)
+}
+
+#[test]
+fn test_order_response_errors_are_rejections() {
+    let response: OrderResponse<serde_json::Value> =
+        serde_json::from_str(r#"{"id":1,"error":"InsufficientBalance"}"#).unwrap();
+    assert!(matches!(
+        response.into_result(),
+        Err(ClientError::RequestRejected(message)) if message == "InsufficientBalance"
+    ));
+    let response: OrderResponse<serde_json::Value> = serde_json::from_str(r#"{"id":1}"#).unwrap();
+    assert!(response.into_result().is_err());
+    let response: OrderResponse<u32> = serde_json::from_str(r#"{"id":1,"data":7}"#).unwrap();
+    assert_eq!(response.into_result().unwrap(), 7);
+}
+
+#[tokio::test]
+async fn test_submit_dry_run_and_cancel_over_the_socket() {
+    let (http_client, ws_client) = clients().await;
+    let session = http_client.session(SubaccountSelector::First).unwrap();
+
+    let created = within(session.submit_order_ws(&ws_client, order("abc123")))
+        .await
+        .unwrap();
+    assert_eq!(created.result, SubmitCreatedCode::Ok);
+    assert_eq!(created.client_order_id.as_deref(), Some("abc123"));
+
+    let dry_run = within(session.dry_run_order_ws(&ws_client, order("abc124")))
+        .await
+        .unwrap();
+    assert_eq!(dry_run.code, SubmitOrderCreatedResultCode::Ok);
+    assert_eq!(dry_run.margin_required, dec!(8));
+
+    let cancelled = within(session.cancel_orders_ws(&ws_client, vec![created.id.to_string()]))
+        .await
+        .unwrap();
+    assert_eq!(cancelled.len(), 1);
+    assert_eq!(cancelled[0].id, created.id);
+    assert_eq!(cancelled[0].result, CancelOrderResultCode::Ok);
+    ws_client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_responses_are_matched_to_their_requests() {
+    let (http_client, ws_client) = clients().await;
+    let slow = http_client
+        .prepare_order(SubaccountSelector::First, order("slow1"))
+        .unwrap();
+    let fast = http_client
+        .prepare_order(SubaccountSelector::First, order("fast1"))
+        .unwrap();
+
+    // The server answers the second submission first.
+    let (slow, fast) = within(async {
+        tokio::join!(ws_client.submit_order(&slow), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            ws_client.submit_order(&fast).await
+        })
+    })
+    .await;
+    assert_eq!(slow.unwrap().client_order_id.as_deref(), Some("slow1"));
+    assert_eq!(fast.unwrap().client_order_id.as_deref(), Some("fast1"));
+    ws_client.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_rejected_order_is_a_typed_error() {
+    let (http_client, ws_client) = clients().await;
+    let session = http_client.session(SubaccountSelector::First).unwrap();
+    match within(session.submit_order_ws(&ws_client, order("reject"))).await {
+        Err(Error::OrderRejected { code }) => {
+            assert_eq!(code, SubmitFailedCode::InsufficientBalance)
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+    ws_client.shutdown("test").await.unwrap();
+}
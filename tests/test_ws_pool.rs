@@ -0,0 +1,179 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::{within, MockExchange, Reply, WsRequest};
+use ethereal_rust_sdk::{
+    enums::Environment,
+    models::TickerMessage,
+    ws_client::{ClientError, WsClient},
+    ws_pool::WsPool,
+};
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+
+const SYMBOLS: [&str; 6] = ["BTCUSD", "ETHUSD", "SOLUSD", "XRPUSD", "DOGEUSD", "HYPEUSD"];
+
+/// Acknowledges every request and answers each subscribe with one ticker message. With
+/// `close_first`, the first connection is closed after that message.
+fn reply(request: &WsRequest, close_first: bool) -> Vec<Reply> {
+    let mut replies = vec![request.ack()];
+    if request.event() == "subscribe" {
+        // Channel messages start with `{"e":`, so they are formatted by hand.
+        replies.push(Reply::Send(format!(
+            r#"{{"e":"Ticker","t":1,"data":{{"s":"{}","t":1,"markPx":"1"}}}}"#,
+            symbol(request)
+        )));
+        if close_first && request.connection == 1 {
+            replies.push(Reply::Close);
+        }
+    }
+    replies
+}
+
+fn symbol(request: &WsRequest) -> &str {
+    request.request["data"]["symbol"].as_str().unwrap()
+}
+
+/// A pool of one client per local server; only the server of `closing` closes its first
+/// connection.
+async fn pool(size: usize, closing: Option<usize>) -> (WsPool, Vec<MockExchange>) {
+    let mut clients = Vec::new();
+    let mut servers = Vec::new();
+    for index in 0..size {
+        let close_first = closing == Some(index);
+        let server = MockExchange::builder()
+            .ws(move |request| reply(request, close_first))
+            .start()
+            .await;
+        clients.push(WsClient::with_config(
+            Environment::Testnet,
+            server.ws_config(),
+        ));
+        servers.push(server);
+    }
+    let pool = WsPool::from_clients(clients);
+    pool.wait_for_connection().await;
+    (pool, servers)
+}
+
+fn symbols() -> Vec<String> {
+    SYMBOLS.iter().map(|s| s.to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_targets_are_sharded_by_hash_or_group() {
+    let (pool, _servers) = pool(3, None).await;
+    let shards: Vec<usize> = SYMBOLS.iter().map(|s| pool.connection_for(s)).collect();
+    assert!(shards.iter().all(|shard| *shard < 3));
+    assert_eq!(
+        shards,
+        SYMBOLS
+            .iter()
+            .map(|s| pool.connection_for(s))
+            .collect::<Vec<_>>()
+    );
+
+    let pool = pool.group(["BTCUSD", "ETHUSD"], 2).group(["SOLUSD"], 4);
+    assert_eq!(pool.connection_for("BTCUSD"), 2);
+    assert_eq!(pool.connection_for("ETHUSD"), 2);
+    assert_eq!(pool.connection_for("SOLUSD"), 1);
+    pool.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_one_stream_over_all_connections() {
+    let (pool, servers) = pool(3, None).await;
+    let mut tickers = pool.subscriptions().ticker_stream(symbols()).await.unwrap();
+
+    let mut seen = HashSet::new();
+    for _ in 0..SYMBOLS.len() {
+        let ticker: TickerMessage = within(tickers.next()).await.unwrap().unwrap();
+        seen.insert(ticker.data.s);
+    }
+    assert_eq!(seen, symbols().into_iter().collect());
+
+    // Each server only received the symbols sharded onto its connection.
+    for (index, server) in servers.iter().enumerate() {
+        for request in server.requests().await {
+            assert_eq!(pool.connection_for(symbol(&request)), index);
+        }
+    }
+
+    tickers.unsubscribe().await.unwrap();
+    let mut unsubscribed = Vec::new();
+    for (index, server) in servers.iter().enumerate() {
+        for request in server.requests().await {
+            assert_eq!(request.event(), "unsubscribe");
+            assert_eq!(pool.connection_for(symbol(&request)), index);
+            unsubscribed.push(symbol(&request).to_string());
+        }
+    }
+    unsubscribed.sort();
+    let mut expected = symbols();
+    expected.sort();
+    assert_eq!(unsubscribed, expected);
+    pool.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_callbacks_receive_from_every_connection() {
+    let (pool, _servers) = pool(2, None).await;
+    let pool = pool.group(["BTCUSD"], 0).group(["ETHUSD"], 1);
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let _subscription = pool
+        .subscriptions()
+        .ticker(
+            vec!["BTCUSD".to_string(), "ETHUSD".to_string()],
+            move |ticker: TickerMessage| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(ticker.data.s);
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+    let mut seen = vec![
+        within(rx.recv()).await.unwrap(),
+        within(rx.recv()).await.unwrap(),
+    ];
+    seen.sort();
+    assert_eq!(seen, vec!["BTCUSD".to_string(), "ETHUSD".to_string()]);
+    pool.shutdown("test").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_connections_reconnect_independently() {
+    let (pool, servers) = pool(2, Some(1)).await;
+    let pool = pool.group(["BTCUSD"], 0).group(["ETHUSD"], 1);
+    let mut tickers = pool
+        .subscriptions()
+        .ticker_stream(vec!["BTCUSD".to_string(), "ETHUSD".to_string()])
+        .await
+        .unwrap();
+
+    // Two initial messages, the reconnecting connection's gap and its replayed message.
+    let mut gaps = 0;
+    let mut messages = Vec::new();
+    while messages.len() < 3 {
+        match within(tickers.next()).await.unwrap() {
+            Ok(ticker) => messages.push(ticker.data.s),
+            Err(ClientError::Gap) => gaps += 1,
+            Err(e) => panic!("Unexpected error {:?}", e),
+        }
+    }
+    assert_eq!(gaps, 1);
+    assert_eq!(messages.iter().filter(|s| *s == "ETHUSD").count(), 2);
+
+    let kept = servers[0].requests().await;
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].connection, 1);
+    let reconnected = servers[1].requests().await;
+    assert_eq!(
+        reconnected.iter().map(|r| r.connection).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    pool.shutdown("test").await.unwrap();
+}
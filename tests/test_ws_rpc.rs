@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use ethereal_rust_sdk::{
+    routing::{extract_request_id, route_key},
+    subscription_stream::{subscription_queue, StreamConfig},
+    ws_client::{
+        handle_incoming, with_request_id, ChannelRoute, ClientError, RouteSender, SubscribedTarget,
+    },
+};
+use futures_util::StreamExt;
+use tokio::sync::oneshot;
+
+#[test]
+fn test_request_id_is_injected_into_envelope() {
+    let msg = Bytes::from(r#"{"event":"submitOrder","data":{"symbol":"BTCUSD"}}"#);
+    let request = with_request_id(&msg, 7).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&request).unwrap();
+    assert_eq!(value["id"], 7);
+    assert_eq!(value["event"], "submitOrder");
+    assert_eq!(value["data"]["symbol"], "BTCUSD");
+
+    let err = with_request_id(&Bytes::from("[1, 2]"), 1).unwrap_err();
+    assert!(matches!(err, ClientError::InvalidRequest(_)));
+}
+
+#[test]
+fn test_extract_request_id() {
+    assert_eq!(extract_request_id(br#"{"id":3,"result":{}}"#), Some(3));
+    assert_eq!(extract_request_id(br#"{"result":{},"id":"12"}"#), Some(12));
+    assert_eq!(extract_request_id(br#"{"id":null}"#), None);
+    assert_eq!(extract_request_id(br#"{"result":{}}"#), None);
+    assert_eq!(extract_request_id(b"not json"), None);
+}
+
+#[tokio::test]
+async fn test_responses_are_routed_to_pending_requests() {
+    let pending = Arc::new(DashMap::new());
+    let subscriptions = Arc::new(DashMap::new());
+    let (first_tx, first_rx) = oneshot::channel();
+    let (second_tx, mut second_rx) = oneshot::channel();
+    pending.insert(1, first_tx);
+    pending.insert(2, second_tx);
+
+    let response = Bytes::from(r#"{"id":1,"result":{"ok":true}}"#);
+    handle_incoming(&response, &pending, &subscriptions).await;
+
+    assert_eq!(first_rx.await.unwrap(), response);
+    assert!(second_rx.try_recv().is_err());
+    assert!(!pending.contains_key(&1));
+    assert!(pending.contains_key(&2));
+}
+
+#[tokio::test]
+async fn test_channel_messages_are_not_taken_as_responses() {
+    let pending = Arc::new(DashMap::new());
+    let subscriptions = Arc::new(DashMap::new());
+    let (pending_tx, mut pending_rx) = oneshot::channel();
+    pending.insert(1, pending_tx);
+    let (queue, channel_rx) = subscription_queue::<serde_json::Value>(StreamConfig::default());
+    subscriptions.insert(
+        route_key("Ticker", "BTCUSD"),
+        ChannelRoute {
+            senders: vec![RouteSender {
+                subscription_id: 1,
+                queue,
+            }],
+            target: SubscribedTarget {
+                target: "BTCUSD".to_string(),
+                subscribe: Bytes::new(),
+                unsubscribe: Bytes::new(),
+            },
+            confirmed: true,
+        },
+    );
+
+    let message = Bytes::from(r#"{"e":"Ticker","id":1,"data":{"s":"BTCUSD"}}"#);
+    handle_incoming(&message, &pending, &subscriptions).await;
+
+    let received = Box::pin(channel_rx).next().await.unwrap().unwrap();
+    assert_eq!(received["data"]["s"], "BTCUSD");
+    assert!(pending_rx.try_recv().is_err());
+    assert!(pending.contains_key(&1));
+}